@@ -3,25 +3,117 @@ pub use datachannel::{
     SessionDescription, SignalingState, TransportPolicy,
 };
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CandidateType {
+    Host,
+    ServerReflexive,
+    PeerReflexive,
+    Relay,
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectedCandidate {
+    pub candidate_type: CandidateType,
+    pub address: String,
+}
+
+impl SelectedCandidate {
+    fn parse(candidate: &str) -> Option<Self> {
+        // candidate:<foundation> <component> <transport> <priority> <address> <port> typ <type> ...
+        let parts = candidate.split_whitespace().collect::<Vec<_>>();
+        let typ = parts.iter().position(|p| *p == "typ")?;
+        Some(Self {
+            candidate_type: match *parts.get(typ + 1)? {
+                "host" => CandidateType::Host,
+                "srflx" => CandidateType::ServerReflexive,
+                "prflx" => CandidateType::PeerReflexive,
+                "relay" => CandidateType::Relay,
+                _ => return None,
+            },
+            address: format!("{}:{}", parts.get(4)?, parts.get(5)?),
+        })
+    }
+}
+
+/// A snapshot of connection statistics.
+///
+/// libdatachannel doesn't expose SCTP round trip times through its C API, so RTT has to be measured by the application.
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    /// Bytes queued on data channels, waiting to be sent.
+    pub buffered_amount: usize,
+    pub local_candidate: Option<SelectedCandidate>,
+    pub remote_candidate: Option<SelectedCandidate>,
+}
+
+impl Stats {
+    pub fn is_relayed(&self) -> bool {
+        [&self.local_candidate, &self.remote_candidate].iter().any(|c| {
+            c.as_ref()
+                .map(|c| c.candidate_type == CandidateType::Relay)
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// Data channels are shared with [`Traffic`] so their buffered amounts can be read from [`PeerConnection::stats`].
+type LockedRtcDataChannel = std::sync::Mutex<Box<datachannel::RtcDataChannel<DataChannelHandler>>>;
+
+#[derive(Default)]
+struct Traffic {
+    bytes_sent: std::sync::atomic::AtomicU64,
+    bytes_received: std::sync::atomic::AtomicU64,
+    data_channels: std::sync::Mutex<Vec<std::sync::Weak<LockedRtcDataChannel>>>,
+}
+
+impl Traffic {
+    fn add_data_channel(
+        &self,
+        dc: Box<datachannel::RtcDataChannel<DataChannelHandler>>,
+    ) -> std::sync::Arc<LockedRtcDataChannel> {
+        let dc = std::sync::Arc::new(std::sync::Mutex::new(dc));
+        let mut data_channels = self.data_channels.lock().unwrap();
+        data_channels.retain(|dc| dc.strong_count() > 0);
+        data_channels.push(std::sync::Arc::downgrade(&dc));
+        dc
+    }
+
+    fn buffered_amount(&self) -> usize {
+        self.data_channels
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|dc| dc.upgrade())
+            .map(|dc| dc.lock().unwrap().buffered_amount())
+            .sum()
+    }
+}
+
 pub struct PeerConnection {
     peer_conn: Box<datachannel::RtcPeerConnection<PeerConnectionHandler>>,
     data_channel_rx: tokio::sync::mpsc::Receiver<DataChannel>,
+    traffic: std::sync::Arc<Traffic>,
 }
 
 impl PeerConnection {
     pub fn new(config: RtcConfig) -> Result<(Self, tokio::sync::mpsc::Receiver<PeerConnectionEvent>), std::io::Error> {
         let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
         let (data_channel_tx, data_channel_rx) = tokio::sync::mpsc::channel(1);
+        let traffic = std::sync::Arc::new(Traffic::default());
         let pch = PeerConnectionHandler {
             event_tx,
             pending_dc_receiver: None,
             data_channel_tx,
+            traffic: traffic.clone(),
         };
         let peer_conn = datachannel::RtcPeerConnection::new(&config, pch).map_err(datachannel_error_to_io_error)?;
         Ok((
             PeerConnection {
                 peer_conn,
                 data_channel_rx,
+                traffic,
             },
             event_rx,
         ))
@@ -42,13 +134,18 @@ impl PeerConnection {
             message_tx: Some(message_tx),
             open_tx: Some(open_tx),
             state: state.clone(),
+            traffic: self.traffic.clone(),
         };
         let dc = self
             .peer_conn
             .create_data_channel_ex(label, dch, &dc_init)
             .map_err(datachannel_error_to_io_error)?;
         Ok(DataChannel {
-            sender: DataChannelSender { state, dc },
+            sender: DataChannelSender {
+                state,
+                dc: self.traffic.add_data_channel(dc),
+                traffic: self.traffic.clone(),
+            },
             receiver: DataChannelReceiver { message_rx },
         })
     }
@@ -85,6 +182,21 @@ impl PeerConnection {
             .map_err(datachannel_error_to_io_error)?;
         Ok(())
     }
+
+    pub fn stats(&self) -> Stats {
+        let candidate_pair = self.peer_conn.selected_candidate_pair();
+        Stats {
+            bytes_sent: self.traffic.bytes_sent.load(std::sync::atomic::Ordering::Relaxed),
+            bytes_received: self.traffic.bytes_received.load(std::sync::atomic::Ordering::Relaxed),
+            buffered_amount: self.traffic.buffered_amount(),
+            local_candidate: candidate_pair
+                .as_ref()
+                .and_then(|pair| SelectedCandidate::parse(&pair.local)),
+            remote_candidate: candidate_pair
+                .as_ref()
+                .and_then(|pair| SelectedCandidate::parse(&pair.remote)),
+        }
+    }
 }
 
 struct PeerConnectionHandler {
@@ -94,6 +206,7 @@ struct PeerConnectionHandler {
         std::sync::Arc<tokio::sync::Mutex<DataChannelState>>,
     )>,
     data_channel_tx: tokio::sync::mpsc::Sender<DataChannel>,
+    traffic: std::sync::Arc<Traffic>,
 }
 
 #[derive(Debug)]
@@ -119,6 +232,7 @@ impl datachannel::PeerConnectionHandler for PeerConnectionHandler {
             message_tx: Some(message_tx),
             open_tx: Some(open_tx),
             state: state.clone(),
+            traffic: self.traffic.clone(),
         };
         self.pending_dc_receiver = Some((message_rx, state));
         dch
@@ -164,7 +278,11 @@ impl datachannel::PeerConnectionHandler for PeerConnectionHandler {
     fn on_data_channel(&mut self, dc: Box<datachannel::RtcDataChannel<Self::DCH>>) {
         let (message_rx, state) = self.pending_dc_receiver.take().unwrap();
         let _ = self.data_channel_tx.blocking_send(DataChannel {
-            sender: DataChannelSender { state, dc },
+            sender: DataChannelSender {
+                state,
+                dc: self.traffic.add_data_channel(dc),
+                traffic: self.traffic.clone(),
+            },
             receiver: DataChannelReceiver { message_rx },
         });
     }
@@ -196,7 +314,8 @@ impl DataChannel {
 
 pub struct DataChannelSender {
     state: std::sync::Arc<tokio::sync::Mutex<DataChannelState>>,
-    dc: Box<datachannel::RtcDataChannel<DataChannelHandler>>,
+    dc: std::sync::Arc<LockedRtcDataChannel>,
+    traffic: std::sync::Arc<Traffic>,
 }
 
 impl DataChannelSender {
@@ -212,10 +331,21 @@ impl DataChannelSender {
                 .map_err(|_| std::io::Error::new(std::io::ErrorKind::NotConnected, "not connected"))?;
         }
 
-        self.dc.send(msg).map_err(datachannel_error_to_io_error)?;
+        self.dc
+            .lock()
+            .unwrap()
+            .send(msg)
+            .map_err(datachannel_error_to_io_error)?;
+        self.traffic
+            .bytes_sent
+            .fetch_add(msg.len() as u64, std::sync::atomic::Ordering::Relaxed);
         Ok(())
     }
 
+    pub fn buffered_amount(&self) -> usize {
+        self.dc.lock().unwrap().buffered_amount()
+    }
+
     pub fn unsplit(self, receiver: DataChannelReceiver) -> DataChannel {
         DataChannel { sender: self, receiver }
     }
@@ -239,6 +369,7 @@ struct DataChannelHandler {
     state: std::sync::Arc<tokio::sync::Mutex<DataChannelState>>,
     open_tx: Option<tokio::sync::oneshot::Sender<()>>,
     message_tx: Option<tokio::sync::mpsc::Sender<Vec<u8>>>,
+    traffic: std::sync::Arc<Traffic>,
 }
 
 fn datachannel_error_to_io_error(err: datachannel::Error) -> std::io::Error {
@@ -268,6 +399,9 @@ impl datachannel::DataChannelHandler for DataChannelHandler {
     }
 
     fn on_message(&mut self, msg: &[u8]) {
+        self.traffic
+            .bytes_received
+            .fetch_add(msg.len() as u64, std::sync::atomic::Ordering::Relaxed);
         let _ = self.message_tx.as_mut().unwrap().blocking_send(msg.to_vec());
     }
 
@@ -15,6 +15,83 @@ pub struct CommittedState {
     pub packet: Vec<u8>,
 }
 
+/// Called with each finished replay, along with how the round went.
+type OnReplayComplete =
+    dyn Fn(&mut dyn std::io::Read, crate::stepper::BattleOutcome) -> anyhow::Result<()> + Send + Sync;
+
+/// A finished replay that's waiting on the remote side's signature before it's handed off.
+struct UnsignedReplay {
+    round_number: u8,
+    digest: [u8; 32],
+    outcome: crate::stepper::BattleOutcome,
+    replay: Box<dyn crate::replay::ReadWriteSeek + Send>,
+}
+
+#[derive(Default)]
+struct PendingSignatures {
+    replay: Option<UnsignedReplay>,
+    remote_signature: Option<crate::net::RoundSignature>,
+    /// The key the remote side presented during the handshake, which its signatures have to be made with.
+    remote_public_key: Vec<u8>,
+}
+
+impl PendingSignatures {
+    /// Takes the pending replay once the remote side's signature for it is in, or regardless if `force` is set.
+    fn take_ready(
+        &mut self,
+        force: bool,
+    ) -> Option<(
+        Box<dyn crate::replay::ReadWriteSeek + Send>,
+        crate::stepper::BattleOutcome,
+    )> {
+        let round_number = self.replay.as_ref()?.round_number;
+        let remote_signature = if self.remote_signature.as_ref().map(|s| s.round_number) == Some(round_number) {
+            self.remote_signature.take()
+        } else {
+            None
+        };
+        if remote_signature.is_none() && !force {
+            return None;
+        }
+
+        let mut unsigned = self.replay.take().unwrap();
+        let Some(remote_signature) = remote_signature else {
+            log::warn!("round {} finished without the remote side's signature", round_number);
+            return Some((unsigned.replay, unsigned.outcome));
+        };
+
+        let signature = crate::replay::signing::Signature {
+            public_key: remote_signature.public_key,
+            signature: remote_signature.signature,
+        };
+        if signature.public_key != self.remote_public_key {
+            log::warn!(
+                "remote signature for round {} is not from the key presented during the handshake",
+                round_number
+            );
+        } else if !signature.verify(&unsigned.digest) {
+            log::warn!("remote signature for round {} does not match our replay", round_number);
+        } else if let Err(e) = crate::replay::append_signature(&mut *unsigned.replay, &signature) {
+            log::error!("failed to append remote signature: {}", e);
+        }
+        Some((unsigned.replay, unsigned.outcome))
+    }
+}
+
+fn complete_replay(
+    on_replay_complete: &OnReplayComplete,
+    mut r: Box<dyn crate::replay::ReadWriteSeek + Send>,
+    outcome: crate::stepper::BattleOutcome,
+) {
+    if let Err(e) = r.seek(std::io::SeekFrom::Start(0)) {
+        log::error!("failed to rewind replay: {}", e);
+        return;
+    }
+    if let Err(e) = on_replay_complete(&mut r, outcome) {
+        log::error!("on_replay_complete failed: {}", e);
+    }
+}
+
 pub struct RoundState {
     pub number: u8,
     pub round: Option<Round>,
@@ -61,7 +138,9 @@ pub struct Match {
             + Send
             + Sync,
     >,
-    on_replay_complete: std::sync::Arc<dyn Fn(&mut dyn std::io::Read) -> anyhow::Result<()> + Send + Sync>,
+    on_replay_complete: std::sync::Arc<OnReplayComplete>,
+    signing_key: Option<std::sync::Arc<crate::replay::signing::SigningKey>>,
+    pending_signatures: std::sync::Arc<parking_lot::Mutex<PendingSignatures>>,
 }
 
 impl Match {
@@ -85,7 +164,12 @@ impl Match {
             + Send
             + Sync
             + 'static,
-        on_replay_complete: impl Fn(&mut dyn std::io::Read) -> anyhow::Result<()> + Send + Sync + 'static,
+        on_replay_complete: impl Fn(&mut dyn std::io::Read, crate::stepper::BattleOutcome) -> anyhow::Result<()>
+            + Send
+            + Sync
+            + 'static,
+        signing_key: Option<crate::replay::signing::SigningKey>,
+        remote_signing_public_key: Vec<u8>,
     ) -> anyhow::Result<std::sync::Arc<Self>> {
         let (round_started_tx, round_started_rx) = tokio::sync::mpsc::channel(1);
         let did_polite_win_last_round = rng.gen::<bool>();
@@ -122,6 +206,11 @@ impl Match {
             round_started_rx: tokio::sync::Mutex::new(round_started_rx),
             replay_writer_factory: Box::new(replay_writer_factory),
             on_replay_complete: std::sync::Arc::new(on_replay_complete),
+            signing_key: signing_key.map(std::sync::Arc::new),
+            pending_signatures: std::sync::Arc::new(parking_lot::Mutex::new(PendingSignatures {
+                remote_public_key: remote_signing_public_key,
+                ..Default::default()
+            })),
         });
         Ok(match_)
     }
@@ -145,7 +234,20 @@ impl Match {
     pub async fn run(&self, mut receiver: Box<dyn crate::net::Receiver + Send + Sync>) -> anyhow::Result<()> {
         let mut last_round_number = 0;
         loop {
-            let input = receiver.receive().await?;
+            let input = match receiver.receive().await? {
+                crate::net::Message::Input(input) => input,
+                crate::net::Message::RoundSignature(signature) => {
+                    let r = {
+                        let mut pending_signatures = self.pending_signatures.lock();
+                        pending_signatures.remote_signature = Some(signature);
+                        pending_signatures.take_ready(false)
+                    };
+                    if let Some((r, outcome)) = r {
+                        complete_replay(&*self.on_replay_complete, r, outcome);
+                    }
+                    continue;
+                }
+            };
 
             // We need to wait for the next round to start to avoid dropping inputs on the floor.
             if input.round_number != last_round_number {
@@ -283,9 +385,11 @@ impl Match {
                 packet: vec![0u8; self.local_hooks.packet_size()],
                 dt: std::time::Duration::ZERO,
             },
+            last_committed_local_joyflags: 0,
             first_state_committed_local_packet: Some(first_state_committed_local_packet),
             first_state_committed_rx: Some(first_state_committed_rx),
             committed_state: None,
+            next_wram_checksum_tick: crate::replay::WRAM_CHECKSUM_INTERVAL,
             stepper: crate::stepper::Fastforwarder::new(
                 &self.rom,
                 self.local_hooks,
@@ -297,6 +401,8 @@ impl Match {
             sender: self.sender.clone(),
             shadow: self.shadow.clone(),
             on_replay_complete: self.on_replay_complete.clone(),
+            signing_key: self.signing_key.clone(),
+            pending_signatures: self.pending_signatures.clone(),
             last_local_input_time: now,
             last_remote_input_time: now,
         });
@@ -314,15 +420,19 @@ pub struct Round {
     dtick: i32,
     iq: crate::input::PairQueue<crate::input::PartialInput, crate::input::PartialInput>,
     last_committed_remote_input: crate::input::Input,
+    last_committed_local_joyflags: u16,
     first_state_committed_local_packet: Option<tokio::sync::oneshot::Sender<()>>,
     first_state_committed_rx: Option<tokio::sync::oneshot::Receiver<()>>,
     committed_state: Option<CommittedState>,
+    next_wram_checksum_tick: u32,
     stepper: crate::stepper::Fastforwarder,
     replay_writer: Option<crate::replay::Writer>,
     primary_thread_handle: mgba::thread::Handle,
     sender: std::sync::Arc<tokio::sync::Mutex<Box<dyn crate::net::Sender + Send + Sync>>>,
     shadow: std::sync::Arc<parking_lot::Mutex<crate::shadow::Shadow>>,
-    on_replay_complete: std::sync::Arc<dyn Fn(&mut dyn std::io::Read) -> anyhow::Result<()> + Send + Sync>,
+    on_replay_complete: std::sync::Arc<OnReplayComplete>,
+    signing_key: Option<std::sync::Arc<crate::replay::signing::SigningKey>>,
+    pending_signatures: std::sync::Arc<parking_lot::Mutex<PendingSignatures>>,
     last_local_input_time: std::time::Instant,
     last_remote_input_time: std::time::Instant,
 }
@@ -340,6 +450,17 @@ impl Round {
         self.local_player_index
     }
 
+    /// The joyflags of the last committed input for each player, P1 first.
+    pub fn last_committed_joyflags(&self) -> [u16; 2] {
+        let local = self.last_committed_local_joyflags;
+        let remote = self.last_committed_remote_input.joyflags;
+        if self.local_player_index == 0 {
+            [local, remote]
+        } else {
+            [remote, local]
+        }
+    }
+
     pub fn set_first_committed_state(
         &mut self,
         local_state: Box<mgba::state::State>,
@@ -482,6 +603,24 @@ impl Round {
                 }
             }
             self.last_committed_remote_input = ip.remote.clone();
+            self.last_committed_local_joyflags = ip.local.joyflags;
+        }
+
+        if ff_result.committed_state.tick >= self.next_wram_checksum_tick
+            && ff_result
+                .round_result
+                .map(|rr| ff_result.committed_state.tick < rr.tick)
+                .unwrap_or(true)
+        {
+            if let Some(replay_writer) = self.replay_writer.as_mut() {
+                replay_writer
+                    .write_wram_checksum(&crate::replay::WramChecksum {
+                        tick: ff_result.committed_state.tick,
+                        crc32: crate::replay::wram_checksum(&ff_result.committed_state.state),
+                    })
+                    .expect("write wram checksum");
+            }
+            self.next_wram_checksum_tick = ff_result.committed_state.tick + crate::replay::WRAM_CHECKSUM_INTERVAL;
         }
 
         core.load_state(&ff_result.dirty_state.state).expect("load dirty state");
@@ -509,6 +648,7 @@ impl Round {
         }
 
         if let Some(replay_writer) = self.replay_writer.take() {
+            let digest = replay_writer.digest();
             let mut r = replay_writer.finish()?;
             log::info!(
                 "replay finished at {:x} (real tick {:x})",
@@ -516,9 +656,44 @@ impl Round {
                 self.current_tick
             );
 
-            r.seek(std::io::SeekFrom::Start(0))?;
-            if let Err(e) = (self.on_replay_complete)(&mut r) {
-                log::error!("on_replay_complete failed: {}", e);
+            if let Some(signing_key) = self.signing_key.as_ref() {
+                // Neither of these is worth losing the replay over: it's still usable without our signature, and the
+                // remote side just won't have it.
+                let signature = signing_key.sign(&digest);
+                if let Err(e) = crate::replay::append_signature(&mut *r, &signature) {
+                    log::error!("failed to append local signature: {}", e);
+                }
+                if let Err(e) = self
+                    .sender
+                    .lock()
+                    .await
+                    .send_round_signature(&crate::net::RoundSignature {
+                        round_number: self.number,
+                        public_key: signature.public_key,
+                        signature: signature.signature,
+                    })
+                    .await
+                {
+                    log::error!("failed to send round signature: {}", e);
+                }
+
+                // Hold on to the replay until the remote side's signature comes in (it may already have).
+                let ready = {
+                    let mut pending_signatures = self.pending_signatures.lock();
+                    let stale = pending_signatures.take_ready(true);
+                    pending_signatures.replay = Some(UnsignedReplay {
+                        round_number: self.number,
+                        digest,
+                        outcome: round_result.outcome,
+                        replay: r,
+                    });
+                    [stale, pending_signatures.take_ready(false)]
+                };
+                for (r, outcome) in ready.into_iter().flatten() {
+                    complete_replay(&*self.on_replay_complete, r, outcome);
+                }
+            } else {
+                complete_replay(&*self.on_replay_complete, r, round_result.outcome);
             }
         }
 
@@ -571,6 +746,15 @@ impl Round {
     }
 }
 
+impl Drop for Match {
+    fn drop(&mut self) {
+        // If the remote side's signature never made it, hand off what we have anyway.
+        if let Some((r, outcome)) = self.pending_signatures.lock().take_ready(true) {
+            complete_replay(&*self.on_replay_complete, r, outcome);
+        }
+    }
+}
+
 impl Drop for Round {
     fn drop(&mut self) {
         // HACK: This is the only safe way to set the FPS without clogging everything else up.
@@ -3,7 +3,7 @@ pub async fn eval(
     rom: &[u8],
     hooks: &(dyn crate::hooks::Hooks + Sync + Send),
     extra_traps: impl FnOnce() -> Vec<(u32, Box<dyn Fn(mgba::core::CoreMutRef)>)> + Send + Sync,
-) -> Result<(crate::stepper::RoundResult, Box<mgba::state::State>), anyhow::Error> {
+) -> Result<(crate::stepper::RoundResult, Box<mgba::state::State>, Option<u32>), anyhow::Error> {
     let mut core = mgba::core::Core::new_gba("tango")?;
 
     let vf = mgba::vfile::VFile::from_vec(rom.to_vec());
@@ -19,6 +19,9 @@ pub async fn eval(
         0,
         Box::new(|| {}),
     );
+    stepper_state
+        .lock_inner()
+        .set_wram_checksums(replay.wram_checksums.clone());
 
     hooks.patch(core.as_mut());
     {
@@ -42,7 +45,7 @@ pub async fn eval(
 
             // For old-style replays, we don't have a precise ending, so we have to just take the result.
             if let Some(result) = stepper_state.round_result() {
-                return Ok((result, core.as_mut().save_state()?));
+                return Ok((result, core.as_mut().save_state()?, stepper_state.wram_divergence()));
             }
         }
 
@@ -52,12 +55,12 @@ pub async fn eval(
     // The result is one frame past the last frame.
     core.as_mut().run_frame();
 
-    let result = {
+    let (result, divergence) = {
         let mut stepper_state = stepper_state.lock_inner();
         if let Some(err) = stepper_state.take_error() {
             return Err(err);
         }
-        stepper_state.round_result()
+        (stepper_state.round_result(), stepper_state.wram_divergence())
     };
 
     let result = if let Some(result) = result {
@@ -66,5 +69,149 @@ pub async fn eval(
         return Err(anyhow::anyhow!("failed to read round result"));
     };
 
-    Ok((result, core.as_mut().save_state()?))
+    Ok((result, core.as_mut().save_state()?, divergence))
+}
+
+/// Plays back a replay one frame at a time, for tools that need to look at the emulator state as it goes.
+pub struct Playback {
+    core: mgba::core::Core,
+    stepper_state: crate::stepper::State,
+}
+
+impl Playback {
+    /// Starts playback from the replay's local state. If `commit_tick` is reached, the state at the start of that tick
+    /// can be retrieved with [`Playback::take_committed_state`].
+    pub fn new(
+        replay: &crate::replay::Replay,
+        rom: &[u8],
+        hooks: &(dyn crate::hooks::Hooks + Sync + Send),
+        commit_tick: u32,
+    ) -> Result<Self, anyhow::Error> {
+        let mut core = mgba::core::Core::new_gba("tango")?;
+        core.enable_video_buffer();
+
+        let vf = mgba::vfile::VFile::from_vec(rom.to_vec());
+        core.as_mut().load_rom(vf)?;
+        core.as_mut().reset();
+
+        let stepper_state = crate::stepper::State::new(
+            (replay.metadata.match_type as u8, replay.metadata.match_subtype as u8),
+            replay.local_player_index,
+            replay.input_pairs.clone(),
+            commit_tick,
+            Box::new(|| {}),
+        );
+
+        hooks.patch(core.as_mut());
+        {
+            let mut traps = hooks.common_traps();
+            traps.extend(hooks.stepper_traps(stepper_state.clone()));
+            core.set_traps(traps);
+        }
+        core.as_mut().load_state(&replay.local_state)?;
+
+        Ok(Self { core, stepper_state })
+    }
+
+    /// Runs a single frame. Returns false, without running anything, once there are no inputs left or the round has a
+    /// result.
+    pub fn step(&mut self) -> Result<bool, anyhow::Error> {
+        {
+            let mut stepper_state = self.stepper_state.lock_inner();
+            if let Some(err) = stepper_state.take_error() {
+                return Err(err);
+            }
+            if stepper_state.input_pairs_left() == 0 || stepper_state.round_result().is_some() {
+                return Ok(false);
+            }
+        }
+
+        self.core.as_mut().run_frame();
+
+        if let Some(err) = self.stepper_state.lock_inner().take_error() {
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    pub fn current_tick(&self) -> u32 {
+        self.stepper_state.lock_inner().current_tick()
+    }
+
+    pub fn save_state(&mut self) -> Result<Box<mgba::state::State>, anyhow::Error> {
+        Ok(self.core.as_mut().save_state()?)
+    }
+
+    pub fn take_committed_state(&self) -> Option<crate::battle::CommittedState> {
+        self.stepper_state.lock_inner().take_committed_state()
+    }
+
+    /// The screen as of the last frame that was run.
+    pub fn frame(&self) -> image::RgbaImage {
+        let mut frame = image::RgbaImage::from_raw(
+            mgba::gba::SCREEN_WIDTH,
+            mgba::gba::SCREEN_HEIGHT,
+            self.core.video_buffer().unwrap().to_vec(),
+        )
+        .unwrap();
+        crate::replay::export::fix_vbuf_alpha(&mut frame);
+        frame
+    }
+}
+
+/// How far into the round the first thumbnail is taken, so the battle has started by then.
+const THUMBNAIL_START_TICK: u32 = 60;
+
+/// Renders the frames that represent a replay: one from shortly after the battle starts, and the last one.
+pub fn thumbnails(
+    replay: &crate::replay::Replay,
+    rom: &[u8],
+    hooks: &(dyn crate::hooks::Hooks + Sync + Send),
+) -> Result<[image::RgbaImage; 2], anyhow::Error> {
+    let mut playback = Playback::new(replay, rom, hooks, 0)?;
+    let mut start = None;
+    while playback.step()? {
+        if start.is_none() && playback.current_tick() >= THUMBNAIL_START_TICK {
+            start = Some(playback.frame());
+        }
+    }
+    let end = playback.frame();
+    Ok([start.unwrap_or_else(|| end.clone()), end])
+}
+
+/// Plays back a replay and logs the telemetry the game's hooks can read along the way, finishing with the round result.
+pub fn telemetry(
+    replay: &crate::replay::Replay,
+    rom: &[u8],
+    hooks: &(dyn crate::hooks::Hooks + Sync + Send),
+) -> Result<Vec<crate::telemetry::Entry>, anyhow::Error> {
+    let mut playback = Playback::new(replay, rom, hooks, 0)?;
+    let mut recorder = crate::telemetry::Recorder::new(playback.current_tick());
+
+    loop {
+        let tick = playback.current_tick();
+        if !playback.step()? {
+            break;
+        }
+        if let Some(sample) = hooks.telemetry_sample(playback.core.as_mut()) {
+            recorder.record(tick, sample.by_player_index(replay.local_player_index));
+        }
+    }
+
+    if playback.stepper_state.lock_inner().round_result().is_none() {
+        // As with eval, the result is one frame past the last frame.
+        playback.core.as_mut().run_frame();
+    }
+
+    let mut stepper_state = playback.stepper_state.lock_inner();
+    if let Some(err) = stepper_state.take_error() {
+        return Err(err);
+    }
+    let result = if let Some(result) = stepper_state.round_result() {
+        result
+    } else {
+        return Err(anyhow::anyhow!("failed to read round result"));
+    };
+
+    Ok(recorder.finish(result))
 }
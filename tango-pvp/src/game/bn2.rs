@@ -795,6 +795,8 @@ impl crate::hooks::Hooks for Hooks {
                         stepper_state.set_committed_state(core.save_state().expect("save committed state"));
                     }
 
+                    stepper_state.check_wram_checksum(&core);
+
                     let ip = match stepper_state.peek_input_pair() {
                         Some(ip) => ip.clone(),
                         None => {
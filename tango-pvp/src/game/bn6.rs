@@ -795,6 +795,8 @@ impl crate::hooks::Hooks for Hooks {
                         stepper_state.set_committed_state(core.save_state().expect("save committed state"));
                     }
 
+                    stepper_state.check_wram_checksum(&core);
+
                     let ip = match stepper_state.peek_input_pair() {
                         Some(ip) => ip.clone(),
                         None => {
@@ -979,4 +981,11 @@ impl crate::hooks::Hooks for Hooks {
             .cpu_mut()
             .set_thumb_pc(self.offsets.rom.main_read_joyflags);
     }
+
+    fn telemetry_sample(&self, core: mgba::core::CoreMutRef) -> Option<crate::telemetry::Sample> {
+        let munger = self.munger();
+        Some(crate::telemetry::Sample {
+            hp: [munger.navi_hp(core, 0), munger.navi_hp(core, 1)],
+        })
+    }
 }
@@ -1,3 +1,6 @@
+const BATTLE_OBJECT_SIZE: u32 = 0xd8;
+const BATTLE_OBJECT_HP: u32 = 0x24;
+
 #[derive(Clone)]
 pub(super) struct Munger {
     pub(super) offsets: &'static super::offsets::Offsets,
@@ -76,4 +79,12 @@ impl Munger {
     pub(super) fn set_copy_data_input_state(&self, mut core: mgba::core::CoreMutRef, v: u8) {
         core.raw_write_8(self.offsets.ewram.copy_data_input_state, -1, v);
     }
+
+    /// HP of the navi on the given side of the field: 0 is the left, where the game always puts its own player.
+    pub(super) fn navi_hp(&self, mut core: mgba::core::CoreMutRef, side: u32) -> u16 {
+        core.raw_read_16(
+            self.offsets.ewram.navi_battle_objects + side * BATTLE_OBJECT_SIZE + BATTLE_OBJECT_HP,
+            -1,
+        )
+    }
 }
@@ -29,6 +29,9 @@ pub(super) struct EWRAMOffsets {
 
     /// The state of copying input data, usually returned by get_copy_data_input_state_ret.
     pub(super) copy_data_input_state: u32,
+
+    /// The battle objects for the two navis, back to back, the one on the left side of the field first.
+    pub(super) navi_battle_objects: u32,
 }
 
 #[derive(Clone, Copy)]
@@ -135,6 +138,7 @@ static EWRAM_OFFSETS_US: EWRAMOffsets = EWRAMOffsets {
     rng1_state:             0x02001120,
     rng2_state:             0x020013f0,
     copy_data_input_state:  0x0203f7d9,
+    navi_battle_objects:    0x0203a9b0,
 };
 
 static EWRAM_OFFSETS_JP: EWRAMOffsets = EWRAMOffsets {
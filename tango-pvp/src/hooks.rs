@@ -46,6 +46,11 @@ pub trait Hooks {
     fn prepare_for_fastforward(&self, core: mgba::core::CoreMutRef);
 
     fn predict_rx(&self, _rx: &mut Vec<u8>) {}
+
+    /// Reads battle telemetry at the end of a tick. Games without a mapped battle memory layout have nothing to report.
+    fn telemetry_sample(&self, _core: mgba::core::CoreMutRef) -> Option<crate::telemetry::Sample> {
+        None
+    }
 }
 
 pub fn hooks_for_gamedb_entry(entry: &tango_gamedb::Game) -> Option<&'static (dyn Hooks + Send + Sync)> {
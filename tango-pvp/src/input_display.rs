@@ -0,0 +1,83 @@
+/// Size of the controller drawn for each player, in GBA pixels.
+pub const WIDTH: u32 = 44;
+pub const HEIGHT: u32 = 16;
+
+/// Margin between a controller and the edge of the screen.
+pub const MARGIN: u32 = 2;
+
+pub struct Button {
+    pub key: u16,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+const fn button(key: u32, x: u32, y: u32, width: u32, height: u32) -> Button {
+    Button {
+        key: key as u16,
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// Where each button goes, relative to the top left of the controller.
+pub const LAYOUT: &[Button] = &[
+    button(mgba::input::keys::L, 1, 0, 10, 2),
+    button(mgba::input::keys::R, 33, 0, 10, 2),
+    button(mgba::input::keys::UP, 6, 3, 4, 4),
+    button(mgba::input::keys::LEFT, 2, 7, 4, 4),
+    button(mgba::input::keys::RIGHT, 10, 7, 4, 4),
+    button(mgba::input::keys::DOWN, 6, 11, 4, 4),
+    button(mgba::input::keys::SELECT, 16, 12, 5, 3),
+    button(mgba::input::keys::START, 23, 12, 5, 3),
+    button(mgba::input::keys::B, 31, 9, 5, 5),
+    button(mgba::input::keys::A, 37, 6, 5, 5),
+];
+
+pub const BACKGROUND_COLOR: [u8; 4] = [0x00, 0x00, 0x00, 0x80];
+pub const RELEASED_COLOR: [u8; 4] = [0x60, 0x60, 0x60, 0xc0];
+pub const PRESSED_COLOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+/// Where each player's controller goes on a screen of the given size: P1 in the bottom left and P2 in the bottom right.
+pub fn positions(screen_width: u32, screen_height: u32) -> [(u32, u32); 2] {
+    let y = screen_height - HEIGHT - MARGIN;
+    [(MARGIN, y), (screen_width - WIDTH - MARGIN, y)]
+}
+
+fn fill(img: &mut image::RgbaImage, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+    use image::Pixel;
+    for py in y..std::cmp::min(y + height, img.height()) {
+        for px in x..std::cmp::min(x + width, img.width()) {
+            img.get_pixel_mut(px, py).blend(&image::Rgba(color));
+        }
+    }
+}
+
+/// Draws one player's controller onto a frame.
+pub fn draw(img: &mut image::RgbaImage, x: u32, y: u32, joyflags: u16) {
+    fill(img, x, y, WIDTH, HEIGHT, BACKGROUND_COLOR);
+    for button in LAYOUT {
+        fill(
+            img,
+            x + button.x,
+            y + button.y,
+            button.width,
+            button.height,
+            if joyflags & button.key != 0 {
+                PRESSED_COLOR
+            } else {
+                RELEASED_COLOR
+            },
+        );
+    }
+}
+
+/// Draws both players' controllers onto a frame, P1 first.
+pub fn draw_both(img: &mut image::RgbaImage, joyflags: [u16; 2]) {
+    for ((x, y), joyflags) in positions(img.width(), img.height()).into_iter().zip(joyflags) {
+        draw(img, x, y, joyflags);
+    }
+}
@@ -3,8 +3,11 @@ pub mod eval;
 pub mod game;
 pub mod hooks;
 pub mod input;
+pub mod input_display;
 pub mod net;
+pub mod patch;
 pub mod replay;
 pub mod shadow;
 pub mod stepper;
 pub mod sync;
+pub mod telemetry;
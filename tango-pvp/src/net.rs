@@ -6,12 +6,26 @@ pub struct Input {
     pub joyflags: u16,
 }
 
+/// A peer's signature over the digest of a round they just finished.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct RoundSignature {
+    pub round_number: u8,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+pub enum Message {
+    Input(Input),
+    RoundSignature(RoundSignature),
+}
+
 #[async_trait::async_trait]
 pub trait Sender {
     async fn send(&mut self, input: &Input) -> std::io::Result<()>;
+    async fn send_round_signature(&mut self, signature: &RoundSignature) -> std::io::Result<()>;
 }
 
 #[async_trait::async_trait]
 pub trait Receiver {
-    async fn receive(&mut self) -> std::io::Result<Input>;
+    async fn receive(&mut self) -> std::io::Result<Message>;
 }
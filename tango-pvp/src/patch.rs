@@ -0,0 +1,26 @@
+/// Applies a patch from the patches directory, where each one is stored as `<name>/v<version>/<ROM code>_<revision>.bps`.
+pub fn apply_from_disk(
+    rom: &[u8],
+    game: &'static tango_gamedb::Game,
+    patches_path: &std::path::Path,
+    patch_name: &str,
+    patch_version: &str,
+) -> Result<Vec<u8>, anyhow::Error> {
+    let patch_name = std::path::Path::new(patch_name);
+    if patch_name.components().count() > 1 {
+        anyhow::bail!("attempted path traversal in patch name");
+    }
+
+    let (rom_code, revision) = game.rom_code_and_revision;
+    let raw = std::fs::read(
+        patches_path
+            .join(patch_name)
+            .join(format!("v{}", patch_version))
+            .join(format!(
+                "{}_{:02}.bps",
+                std::str::from_utf8(rom_code).unwrap(),
+                revision
+            )),
+    )?;
+    Ok(bps::Patch::decode(&raw)?.apply(rom)?)
+}
@@ -1,9 +1,15 @@
+pub mod container;
+pub mod dictionary;
 pub mod export;
 mod protos;
+pub mod signing;
+#[cfg(test)]
+mod tests;
 
 use byteorder::ReadBytesExt;
 use byteorder::WriteBytesExt;
 use prost::Message;
+use sha2::Digest;
 use std::io::Read;
 use std::io::Write;
 pub trait ReadWriteSeek: std::io::Read + std::io::Write + std::io::Seek {}
@@ -15,10 +21,127 @@ pub type Metadata = protos::replay11::Metadata;
 pub struct Writer {
     encoder: Option<zstd::stream::write::Encoder<'static, Box<dyn ReadWriteSeek + Send>>>,
     num_inputs: u32,
+    digester: signing::Digester,
+    /// Only used for the frames holding the initial states.
+    dictionary: Option<std::sync::Arc<dictionary::Dictionary>>,
+    /// The first state written, which the second one is stored relative to.
+    base_state: Option<Vec<u8>>,
 }
 
 pub const HEADER: &[u8] = b"TOOT";
-pub const VERSION: u8 = 0x12;
+pub const VERSION: u8 = 0x16;
+
+/// Every format version that can still be decoded, oldest first.
+pub const SUPPORTED_VERSIONS: &[u8] = &[0x11, 0x12, 0x13, 0x14, 0x15, 0x16];
+
+/// How the record stream following the initial states is laid out.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Layout {
+    /// 0x11 and 0x12: nothing but input pairs. 0x12 added the time since the previous input to each pair.
+    Untagged,
+
+    /// 0x13 onwards: every record is prefixed with a tag, so other kinds of records (e.g. WRAM checksums) can be
+    /// interleaved. 0x14 added signature records and 0x15 added end of round records.
+    Tagged,
+}
+
+impl Layout {
+    fn for_version(version: u8) -> Option<Self> {
+        match version {
+            0x11 | 0x12 => Some(Layout::Untagged),
+            0x13 | 0x14 | 0x15 | 0x16 => Some(Layout::Tagged),
+            _ => None,
+        }
+    }
+}
+
+fn unsupported_version(version: u8) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("unsupported version: {:02x}", version),
+    )
+}
+
+/// How often, in ticks, the writer records a WRAM checksum.
+pub const WRAM_CHECKSUM_INTERVAL: u32 = 60;
+
+/// The most record data we'll decompress for one round. Even a long round is a few megabytes, so a replay that goes
+/// past this is corrupt or was made to blow up when decompressed.
+const MAX_RECORDS_SIZE: u64 = 64 * 1024 * 1024;
+
+const RECORD_INPUT_PAIR: u8 = 0x00;
+const RECORD_WRAM_CHECKSUM: u8 = 0x01;
+const RECORD_SIGNATURE: u8 = 0x02;
+const RECORD_END_OF_ROUND: u8 = 0x03;
+
+/// How often, in input pairs, the writer ends its current zstd frame. Everything before the last frame boundary can
+/// still be read back if the writer never gets to finish.
+pub const FRAME_INTERVAL: u32 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WramChecksum {
+    pub tick: u32,
+    pub crc32: u32,
+}
+
+pub fn wram_checksum(state: &mgba::state::State) -> u32 {
+    crc32fast::hash(state.wram())
+}
+
+/// Computes the SHA-256 and CRC32 of a (patched) ROM, as recorded in [`metadata::GameInfo`].
+pub fn hash_rom(rom: &[u8]) -> (Vec<u8>, u32) {
+    (sha2::Sha256::digest(rom).to_vec(), crc32fast::hash(rom))
+}
+
+/// Checks a ROM against the hashes recorded for a side, or `None` if the replay predates hashes being recorded.
+pub fn rom_matches(game_info: &metadata::GameInfo, rom: &[u8]) -> Option<bool> {
+    if game_info.rom_sha256.is_empty() {
+        return None;
+    }
+    Some(sha2::Sha256::digest(rom).as_slice() == game_info.rom_sha256.as_slice())
+}
+
+/// Changes to a replay's metadata, e.g. before sharing it publicly. Fields left as `None` are kept as they are.
+#[derive(Clone, Default, Debug)]
+pub struct MetadataEdit {
+    pub local_nickname: Option<String>,
+    pub remote_nickname: Option<String>,
+    pub link_code: Option<String>,
+    /// Applies to both sides.
+    pub reveal_setup: Option<bool>,
+}
+
+impl MetadataEdit {
+    /// Replaces the nicknames with player numbers and clears the link code. Setups are only hidden if asked.
+    pub fn anonymize(local_player_index: u8, hide_setup: bool) -> Self {
+        Self {
+            local_nickname: Some(format!("Player {}", local_player_index + 1)),
+            remote_nickname: Some(format!("Player {}", 2 - local_player_index)),
+            link_code: Some(String::new()),
+            reveal_setup: if hide_setup { Some(false) } else { None },
+        }
+    }
+
+    pub fn apply(&self, metadata: &mut Metadata) {
+        for (side, nickname) in [
+            (metadata.local_side.as_mut(), self.local_nickname.as_ref()),
+            (metadata.remote_side.as_mut(), self.remote_nickname.as_ref()),
+        ] {
+            let Some(side) = side else {
+                continue;
+            };
+            if let Some(nickname) = nickname {
+                side.nickname = nickname.clone();
+            }
+            if let Some(reveal_setup) = self.reveal_setup {
+                side.reveal_setup = reveal_setup;
+            }
+        }
+        if let Some(link_code) = self.link_code.as_ref() {
+            metadata.link_code = link_code.clone();
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Replay {
@@ -28,33 +151,55 @@ pub struct Replay {
     pub local_state: Box<mgba::state::State>,
     pub remote_state: Box<mgba::state::State>,
     pub input_pairs: Vec<crate::input::Pair<crate::input::Input, crate::input::Input>>,
+    /// Checksums of the local side's WRAM at the start of the given ticks, as seen when the replay was recorded.
+    pub wram_checksums: Vec<WramChecksum>,
+    /// Signatures over [`signing::digest`], from either side.
+    pub signatures: Vec<signing::Signature>,
 }
 
 pub fn decode_metadata(version: u8, raw: &[u8]) -> Result<Metadata, std::io::Error> {
-    Ok(match version {
-        VERSION => protos::replay11::Metadata::decode(raw)?,
-        _ => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("invalid version: {:02x}", version),
-            ));
-        }
-    })
+    // All supported versions share the replay11 metadata message: fields have only ever been added to it.
+    if Layout::for_version(version).is_none() {
+        return Err(unsupported_version(version));
+    }
+    Ok(protos::replay11::Metadata::decode(raw)?)
 }
 
-pub fn read_metadata(r: &mut impl std::io::Read) -> Result<(usize, Metadata), std::io::Error> {
+/// Reads the header and returns the format version the replay was written with.
+pub fn read_version(r: &mut impl std::io::Read) -> Result<u8, std::io::Error> {
     let mut header = [0u8; 4];
     r.read_exact(&mut header)?;
     if header != HEADER {
         return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid header"));
     }
+    r.read_u8()
+}
+
+pub fn read_metadata(r: &mut impl std::io::Read) -> Result<(usize, Metadata), std::io::Error> {
+    let (_, num_inputs, metadata) = read_versioned_metadata(r)?;
+    Ok((num_inputs, metadata))
+}
+
+/// Reads exactly `len` bytes. The buffer only grows as the bytes actually arrive, so a corrupt length can't make us
+/// allocate more than the input holds.
+pub(crate) fn read_exact_vec(r: &mut impl std::io::Read, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    r.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            format!("expected {} bytes, got {}", len, buf.len()),
+        ));
+    }
+    Ok(buf)
+}
 
-    let version = r.read_u8()?;
+fn read_versioned_metadata(r: &mut impl std::io::Read) -> Result<(u8, usize, Metadata), std::io::Error> {
+    let version = read_version(r)?;
     let num_inputs = r.read_u32::<byteorder::LittleEndian>()? as usize;
     let metadata_len = r.read_u32::<byteorder::LittleEndian>()?;
-    let mut raw = vec![0u8; metadata_len as usize];
-    r.read_exact(&mut raw[..])?;
-    Ok((num_inputs, decode_metadata(version, &raw)?))
+    let raw = read_exact_vec(r, metadata_len as usize)?;
+    Ok((version, num_inputs, decode_metadata(version, &raw)?))
 }
 
 impl Replay {
@@ -65,101 +210,316 @@ impl Replay {
         for ip in self.input_pairs.iter_mut() {
             std::mem::swap(&mut ip.local, &mut ip.remote);
         }
+        // We only ever record checksums for our own side.
+        self.wram_checksums.clear();
         self
     }
 
-    pub fn decode(mut r: impl std::io::Read) -> std::io::Result<Self> {
-        let (num_inputs, metadata) = read_metadata(&mut r)?;
+    /// Edits the metadata, leaving the input stream alone. Signatures are dropped along with the keys they were bound
+    /// to: they covered the old metadata, and the keys would identify the players anyway.
+    pub fn edit_metadata(&mut self, edit: &MetadataEdit) {
+        edit.apply(&mut self.metadata);
+        for side in [self.metadata.local_side.as_mut(), self.metadata.remote_side.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            side.signing_public_key.clear();
+        }
+        self.signatures.clear();
+    }
+
+    pub fn decode(r: impl std::io::Read) -> std::io::Result<Self> {
+        Self::decode_records(r, None, false)
+    }
+
+    /// Decodes a replay that may have been compressed with the given dictionary.
+    pub fn decode_with_dictionary(
+        r: impl std::io::Read,
+        dictionary: Option<&dictionary::Dictionary>,
+    ) -> std::io::Result<Self> {
+        Self::decode_records(r, dictionary, false)
+    }
+
+    /// Salvages what it can from a damaged replay, e.g. one left behind by a crash: every record up to the first one
+    /// that can't be read is kept, and signatures that no longer cover the inputs are dropped. The result is only
+    /// complete if the end of the round was recorded.
+    pub fn recover(r: impl std::io::Read) -> std::io::Result<Self> {
+        let mut replay = Self::decode_records(r, None, true)?;
+        let digest = signing::digest(&replay);
+        replay.signatures.retain(|s| s.verify(&digest));
+        Ok(replay)
+    }
 
-        let mut zr = zstd::stream::read::Decoder::new(r)?;
+    fn decode_records(
+        mut r: impl std::io::Read,
+        dictionary: Option<&dictionary::Dictionary>,
+        salvage: bool,
+    ) -> std::io::Result<Self> {
+        let (version, num_inputs, metadata) = read_versioned_metadata(&mut r)?;
+        let layout = Layout::for_version(version).ok_or_else(|| unsupported_version(version))?;
 
-        let local_player_index = zr.read_u8()?;
+        // From 0x16, each state is in a frame of its own, compressed with the dictionary named here if there is one,
+        // and the remote state is stored as a delta against the local one.
+        let dictionary_id = if version >= 0x16 {
+            r.read_u32::<byteorder::LittleEndian>()?
+        } else {
+            0
+        };
+        let dictionary = match dictionary {
+            _ if dictionary_id == 0 => None,
+            Some(dictionary) if dictionary.id() == dictionary_id => Some(dictionary),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("replay needs zstd dictionary {:08x}", dictionary_id),
+                ));
+            }
+        };
 
-        let input_raw_size = zr.read_u8()? as usize;
+        let r = std::io::BufReader::new(r);
+        let (local_player_index, input_raw_size, local_state, remote_state, zr) = if version >= 0x16 {
+            let mut zr = state_decoder(r, dictionary)?;
+            let local_player_index = zr.read_u8()?;
+            let input_raw_size = zr.read_u8()? as usize;
+            let local_state = read_state(&mut zr)?;
 
-        let mut local_state = vec![0u8; zr.read_u32::<byteorder::LittleEndian>()? as usize];
-        zr.read_exact(&mut local_state)?;
-        let local_state = mgba::state::State::from_slice(&local_state);
+            let mut zr = state_decoder(zr.finish(), dictionary)?;
+            let remote_state = dictionary::state_delta(&local_state, &read_state(&mut zr)?);
 
-        let mut remote_state = vec![0u8; zr.read_u32::<byteorder::LittleEndian>()? as usize];
-        zr.read_exact(&mut remote_state)?;
+            (
+                local_player_index,
+                input_raw_size,
+                local_state,
+                remote_state,
+                zstd::stream::read::Decoder::with_buffer(zr.finish())?,
+            )
+        } else {
+            let mut zr = zstd::stream::read::Decoder::with_buffer(r)?;
+            let local_player_index = zr.read_u8()?;
+            let input_raw_size = zr.read_u8()? as usize;
+            let local_state = read_state(&mut zr)?;
+            let remote_state = read_state(&mut zr)?;
+            (local_player_index, input_raw_size, local_state, remote_state, zr)
+        };
+        let local_state = mgba::state::State::from_slice(&local_state);
         let remote_state = mgba::state::State::from_slice(&remote_state);
 
+        // One byte past the limit tells a replay that's too big apart from one that ends right at it.
+        let mut zr = zr.take(MAX_RECORDS_SIZE + 1);
         let mut input_pairs = vec![];
+        let mut wram_checksums = vec![];
+        let mut signatures = vec![];
+        let mut ended = false;
 
         loop {
-            let local_tick = if let Ok(v) = zr.read_u32::<byteorder::LittleEndian>() {
+            let tag = if layout == Layout::Untagged {
+                RECORD_INPUT_PAIR
+            } else if let Ok(v) = zr.read_u8() {
                 v
             } else {
                 break;
             };
-            let remote_tick = if let Ok(v) = zr.read_u32::<byteorder::LittleEndian>() {
-                v
-            } else {
-                break;
-            };
-            let dt = std::time::Duration::from_millis(if let Ok(v) = zr.read_u16::<byteorder::LittleEndian>() {
-                v
-            } else {
-                break;
-            } as u64);
-
-            let mut p1_input = crate::input::Input {
-                local_tick,
-                remote_tick,
-                joyflags: if let Ok(v) = zr.read_u16::<byteorder::LittleEndian>() {
-                    v
-                } else {
-                    break;
-                },
-                packet: vec![0u8; input_raw_size],
-                dt,
-            };
-            if zr.read_exact(&mut p1_input.packet).is_err() {
-                break;
-            }
 
-            let mut p2_input = crate::input::Input {
-                local_tick,
-                remote_tick: local_tick,
-                joyflags: if let Ok(v) = zr.read_u16::<byteorder::LittleEndian>() {
-                    v
-                } else {
+            match tag {
+                RECORD_INPUT_PAIR => {
+                    let Some(ip) = read_input_pair(&mut zr, local_player_index, input_raw_size, version >= 0x12) else {
+                        break;
+                    };
+                    input_pairs.push(ip);
+                }
+                RECORD_WRAM_CHECKSUM => {
+                    let (Ok(tick), Ok(crc32)) = (
+                        zr.read_u32::<byteorder::LittleEndian>(),
+                        zr.read_u32::<byteorder::LittleEndian>(),
+                    ) else {
+                        break;
+                    };
+                    wram_checksums.push(WramChecksum { tick, crc32 });
+                }
+                RECORD_SIGNATURE => {
+                    let mut public_key = vec![0u8; signing::PUBLIC_KEY_LEN];
+                    let mut signature = vec![0u8; signing::SIGNATURE_LEN];
+                    if zr.read_exact(&mut public_key).is_err() || zr.read_exact(&mut signature).is_err() {
+                        break;
+                    }
+                    signatures.push(signing::Signature { public_key, signature });
+                }
+                RECORD_END_OF_ROUND => {
+                    ended = true;
+                }
+                _ if salvage => {
                     break;
-                },
-                packet: vec![0u8; input_raw_size],
-                dt,
-            };
-            if zr.read_exact(&mut p2_input.packet).is_err() {
-                break;
+                }
+                _ => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid record tag: {:02x}", tag),
+                    ));
+                }
             }
+        }
 
-            let (local, remote) = if local_player_index == 0 {
-                (p1_input, p2_input)
-            } else {
-                (p2_input, p1_input)
-            };
-
-            input_pairs.push(crate::input::Pair { local, remote });
+        if zr.limit() == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "replay records too large",
+            ));
         }
 
+        // Before 0x15, the input count in the header was the only sign that the round ended.
+        let ended = ended || version < 0x15;
+
         Ok(Self {
-            is_complete: num_inputs > 0 && num_inputs == input_pairs.len(),
+            is_complete: if salvage {
+                ended && !input_pairs.is_empty()
+            } else {
+                ended && num_inputs > 0 && num_inputs == input_pairs.len()
+            },
             metadata,
             local_player_index,
             local_state,
             remote_state,
             input_pairs,
+            wram_checksums,
+            signatures,
         })
     }
+
+    /// Writes the replay out in the current format.
+    pub fn encode(
+        &self,
+        writer: impl ReadWriteSeek + Send + 'static,
+    ) -> std::io::Result<Box<dyn ReadWriteSeek + Send>> {
+        self.encode_with_dictionary(writer, None)
+    }
+
+    /// Writes the replay out in the current format, compressing the initial states with the given dictionary.
+    pub fn encode_with_dictionary(
+        &self,
+        writer: impl ReadWriteSeek + Send + 'static,
+        dictionary: Option<std::sync::Arc<dictionary::Dictionary>>,
+    ) -> std::io::Result<Box<dyn ReadWriteSeek + Send>> {
+        let mut writer = Writer::with_dictionary(
+            writer,
+            self.metadata.clone(),
+            self.local_player_index,
+            self.input_pairs.first().map(|ip| ip.local.packet.len()).unwrap_or(0) as u8,
+            dictionary,
+        )?;
+        writer.write_state(&self.local_state)?;
+        writer.write_state(&self.remote_state)?;
+        let mut wram_checksums = self.wram_checksums.iter().peekable();
+        for ip in &self.input_pairs {
+            while let Some(checksum) = wram_checksums.next_if(|c| c.tick <= ip.local.local_tick) {
+                writer.write_wram_checksum(checksum)?;
+            }
+            writer.write_input(self.local_player_index, ip)?;
+        }
+        for checksum in wram_checksums {
+            writer.write_wram_checksum(checksum)?;
+        }
+        for signature in &self.signatures {
+            writer.write_signature(signature)?;
+        }
+        // Keep incomplete replays marked as such, the same way a writer that never finished would have.
+        writer.finish_round(self.is_complete)
+    }
+}
+
+/// A decoder for a single frame holding one of the initial states.
+fn state_decoder<R: std::io::BufRead>(
+    r: R,
+    dictionary: Option<&dictionary::Dictionary>,
+) -> std::io::Result<zstd::stream::read::Decoder<'static, R>> {
+    Ok(match dictionary {
+        Some(dictionary) => zstd::stream::read::Decoder::with_dictionary(r, dictionary.as_bytes())?,
+        None => zstd::stream::read::Decoder::with_buffer(r)?,
+    }
+    .single_frame())
+}
+
+fn read_state(zr: &mut impl std::io::Read) -> std::io::Result<Vec<u8>> {
+    let len = zr.read_u32::<byteorder::LittleEndian>()? as usize;
+    if len != std::mem::size_of::<mgba::state::State>() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("invalid state size: {}", len),
+        ));
+    }
+    let mut state = vec![0u8; len];
+    zr.read_exact(&mut state)?;
+    Ok(state)
+}
+
+fn new_encoder<W: std::io::Write>(
+    w: W,
+    dictionary: Option<&dictionary::Dictionary>,
+) -> std::io::Result<zstd::stream::write::Encoder<'static, W>> {
+    match dictionary {
+        Some(dictionary) => zstd::stream::write::Encoder::with_dictionary(w, 3, dictionary.as_bytes()),
+        None => zstd::stream::write::Encoder::new(w, 3),
+    }
+}
+
+/// Reads a single input pair, or `None` if the stream ends partway through one. Pairs written without a time since
+/// the previous input get a zero one.
+fn read_input_pair(
+    zr: &mut impl std::io::Read,
+    local_player_index: u8,
+    input_raw_size: usize,
+    with_dt: bool,
+) -> Option<crate::input::Pair<crate::input::Input, crate::input::Input>> {
+    let local_tick = zr.read_u32::<byteorder::LittleEndian>().ok()?;
+    let remote_tick = zr.read_u32::<byteorder::LittleEndian>().ok()?;
+    let dt = if with_dt {
+        std::time::Duration::from_millis(zr.read_u16::<byteorder::LittleEndian>().ok()? as u64)
+    } else {
+        std::time::Duration::ZERO
+    };
+
+    let mut p1_input = crate::input::Input {
+        local_tick,
+        remote_tick,
+        joyflags: zr.read_u16::<byteorder::LittleEndian>().ok()?,
+        packet: vec![0u8; input_raw_size],
+        dt,
+    };
+    zr.read_exact(&mut p1_input.packet).ok()?;
+
+    let mut p2_input = crate::input::Input {
+        local_tick,
+        remote_tick: local_tick,
+        joyflags: zr.read_u16::<byteorder::LittleEndian>().ok()?,
+        packet: vec![0u8; input_raw_size],
+        dt,
+    };
+    zr.read_exact(&mut p2_input.packet).ok()?;
+
+    let (local, remote) = if local_player_index == 0 {
+        (p1_input, p2_input)
+    } else {
+        (p2_input, p1_input)
+    };
+
+    Some(crate::input::Pair { local, remote })
 }
 
 impl Writer {
     pub fn new(
+        writer: impl ReadWriteSeek + Send + 'static,
+        metadata: Metadata,
+        local_player_index: u8,
+        raw_input_size: u8,
+    ) -> std::io::Result<Self> {
+        Self::with_dictionary(writer, metadata, local_player_index, raw_input_size, None)
+    }
+
+    pub fn with_dictionary(
         mut writer: impl ReadWriteSeek + Send + 'static,
         metadata: Metadata,
         local_player_index: u8,
         raw_input_size: u8,
+        dictionary: Option<std::sync::Arc<dictionary::Dictionary>>,
     ) -> std::io::Result<Self> {
         writer.write_all(HEADER)?;
         writer.write_u8(VERSION)?;
@@ -167,23 +527,45 @@ impl Writer {
         let raw_metadata = metadata.encode_to_vec();
         writer.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)?;
         writer.write_all(&raw_metadata[..])?;
-        let mut encoder = zstd::Encoder::new(Box::new(writer) as Box<dyn ReadWriteSeek + Send>, 3)?;
+        writer.write_u32::<byteorder::LittleEndian>(dictionary.as_ref().map(|d| d.id()).unwrap_or(0))?;
+        let mut encoder = new_encoder(Box::new(writer) as Box<dyn ReadWriteSeek + Send>, dictionary.as_deref())?;
         encoder.write_u8(local_player_index)?;
         encoder.write_u8(raw_input_size)?;
         encoder.flush()?;
         Ok(Writer {
             encoder: Some(encoder),
             num_inputs: 0,
+            digester: signing::Digester::new(&metadata, local_player_index),
+            dictionary,
+            base_state: None,
         })
     }
 
+    /// Writes one of the initial states: the local one first, then the remote one.
     pub fn write_state(&mut self, state: &mgba::state::State) -> std::io::Result<()> {
+        let raw = match self.base_state.as_ref() {
+            None => {
+                self.base_state = Some(state.as_slice().to_vec());
+                state.as_slice().to_vec()
+            }
+            Some(base_state) => {
+                // That's all the states, so the rest of the replay is compressed without the dictionary.
+                self.dictionary = None;
+                dictionary::state_delta(base_state, state.as_slice())
+            }
+        };
         self.encoder
             .as_mut()
             .unwrap()
-            .write_u32::<byteorder::LittleEndian>(state.as_slice().len() as u32)?;
-        self.encoder.as_mut().unwrap().write_all(state.as_slice())?;
-        self.encoder.as_mut().unwrap().flush()?;
+            .write_u32::<byteorder::LittleEndian>(raw.len() as u32)?;
+        self.encoder.as_mut().unwrap().write_all(&raw)?;
+        self.end_frame()
+    }
+
+    /// Ends the current zstd frame and starts a new one.
+    fn end_frame(&mut self) -> std::io::Result<()> {
+        let w = self.encoder.take().unwrap().finish()?;
+        self.encoder = Some(new_encoder(w, self.dictionary.as_deref())?);
         Ok(())
     }
 
@@ -192,6 +574,7 @@ impl Writer {
         local_player_index: u8,
         ip: &crate::input::Pair<crate::input::Input, crate::input::Input>,
     ) -> std::io::Result<()> {
+        self.encoder.as_mut().unwrap().write_u8(RECORD_INPUT_PAIR)?;
         self.encoder
             .as_mut()
             .unwrap()
@@ -223,18 +606,75 @@ impl Writer {
             .write_u16::<byteorder::LittleEndian>(p2.joyflags)?;
         self.encoder.as_mut().unwrap().write_all(&p2.packet)?;
 
+        self.digester.update(ip);
         self.num_inputs += 1;
+        if self.num_inputs % FRAME_INTERVAL == 0 {
+            self.end_frame()?;
+        }
+        Ok(())
+    }
+
+    /// The digest of everything written so far, for signing.
+    pub fn digest(&self) -> [u8; 32] {
+        self.digester.clone().finish()
+    }
+
+    pub fn write_signature(&mut self, signature: &signing::Signature) -> std::io::Result<()> {
+        write_signature_record(self.encoder.as_mut().unwrap(), signature)
+    }
+
+    pub fn write_wram_checksum(&mut self, checksum: &WramChecksum) -> std::io::Result<()> {
+        self.encoder.as_mut().unwrap().write_u8(RECORD_WRAM_CHECKSUM)?;
+        self.encoder
+            .as_mut()
+            .unwrap()
+            .write_u32::<byteorder::LittleEndian>(checksum.tick)?;
+        self.encoder
+            .as_mut()
+            .unwrap()
+            .write_u32::<byteorder::LittleEndian>(checksum.crc32)?;
         Ok(())
     }
 
-    pub fn finish(mut self) -> std::io::Result<Box<dyn ReadWriteSeek + Send>> {
-        let mut w = self.encoder.take().unwrap().finish()?;
+    /// Marks the end of the round and closes out the replay.
+    pub fn finish(self) -> std::io::Result<Box<dyn ReadWriteSeek + Send>> {
+        self.finish_round(true)
+    }
+
+    fn finish_round(mut self, ended: bool) -> std::io::Result<Box<dyn ReadWriteSeek + Send>> {
+        let mut encoder = self.encoder.take().unwrap();
+        if ended {
+            encoder.write_u8(RECORD_END_OF_ROUND)?;
+        }
+        let mut w = encoder.finish()?;
         w.seek(std::io::SeekFrom::Start((HEADER.len() + 1) as u64))?;
-        w.write_u32::<byteorder::LittleEndian>(self.num_inputs)?;
+        w.write_u32::<byteorder::LittleEndian>(if ended { self.num_inputs } else { 0 })?;
         Ok(w)
     }
 }
 
+fn write_signature_record(w: &mut impl std::io::Write, signature: &signing::Signature) -> std::io::Result<()> {
+    if signature.public_key.len() != signing::PUBLIC_KEY_LEN || signature.signature.len() != signing::SIGNATURE_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "malformed signature",
+        ));
+    }
+    w.write_u8(RECORD_SIGNATURE)?;
+    w.write_all(&signature.public_key)?;
+    w.write_all(&signature.signature)?;
+    Ok(())
+}
+
+/// Appends a signature to an already finished replay, as its own zstd frame.
+pub fn append_signature(w: &mut (dyn ReadWriteSeek + Send), signature: &signing::Signature) -> std::io::Result<()> {
+    w.seek(std::io::SeekFrom::End(0))?;
+    let mut encoder = zstd::Encoder::new(w, 3)?;
+    write_signature_record(&mut encoder, signature)?;
+    encoder.finish()?;
+    Ok(())
+}
+
 impl Drop for Writer {
     fn drop(&mut self) {
         if let Some(encoder) = self.encoder.take() {
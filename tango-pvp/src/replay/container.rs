@@ -0,0 +1,180 @@
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use prost::Message;
+use std::io::Read;
+use std::io::Write;
+
+pub const HEADER: &[u8] = b"TOOM";
+pub const VERSION: u8 = 0x01;
+
+const OUTCOME_UNKNOWN: u8 = 0x00;
+const OUTCOME_WIN: u8 = 0x01;
+const OUTCOME_LOSS: u8 = 0x02;
+const OUTCOME_DRAW: u8 = 0x03;
+
+fn invalid_data(msg: impl Into<String>) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Reads a u32 length and then that many bytes.
+fn read_chunk(r: &mut impl std::io::Read) -> std::io::Result<Vec<u8>> {
+    let len = r.read_u32::<byteorder::LittleEndian>()? as usize;
+    super::read_exact_vec(r, len)
+}
+
+/// One round of a match.
+#[derive(Clone)]
+pub struct Round {
+    /// The round's replay, exactly as it was written, so splitting a match back up gives the original files.
+    pub raw: Vec<u8>,
+    pub outcome: Option<crate::stepper::BattleOutcome>,
+}
+
+impl Round {
+    /// Checks that the replay's header can be read before taking it.
+    pub fn new(raw: Vec<u8>, outcome: Option<crate::stepper::BattleOutcome>) -> std::io::Result<Self> {
+        super::read_metadata(&mut &raw[..])?;
+        Ok(Self { raw, outcome })
+    }
+
+    pub fn metadata(&self) -> std::io::Result<super::Metadata> {
+        Ok(super::read_metadata(&mut &self.raw[..])?.1)
+    }
+
+    pub fn replay(&self) -> std::io::Result<super::Replay> {
+        super::Replay::decode(&self.raw[..])
+    }
+}
+
+/// Every round of a match in a single file, along with the match's results.
+pub struct Container {
+    /// What the rounds have in common: the metadata of the first round, without a round number.
+    pub metadata: super::Metadata,
+
+    /// In the order they were played.
+    pub rounds: Vec<Round>,
+}
+
+/// Reads the header and returns whether what follows is a match container, as opposed to a single round.
+pub fn is_container(r: &mut impl std::io::Read) -> std::io::Result<bool> {
+    let mut header = [0u8; 4];
+    r.read_exact(&mut header)?;
+    Ok(header == HEADER)
+}
+
+impl Container {
+    pub fn new(rounds: Vec<Round>) -> std::io::Result<Self> {
+        let mut container = Self {
+            metadata: super::Metadata::default(),
+            rounds: vec![],
+        };
+        for round in rounds {
+            container.push(round)?;
+        }
+        Ok(container)
+    }
+
+    /// Adds a round, keeping the rounds in the order they were played.
+    pub fn push(&mut self, round: Round) -> std::io::Result<()> {
+        let metadata = round.metadata()?;
+        let key = (metadata.ts, metadata.round);
+        let i = self
+            .rounds
+            .iter()
+            .position(|r| r.metadata().is_ok_and(|m| (m.ts, m.round) > key))
+            .unwrap_or(self.rounds.len());
+        self.rounds.insert(i, round);
+        if i == 0 {
+            self.metadata = super::Metadata { round: 0, ..metadata };
+        }
+        Ok(())
+    }
+
+    /// The result of the match, if every round's outcome is known.
+    pub fn outcome(&self) -> Option<crate::stepper::BattleOutcome> {
+        let mut wins = 0;
+        let mut losses = 0;
+        for round in &self.rounds {
+            match round.outcome? {
+                crate::stepper::BattleOutcome::Win => wins += 1,
+                crate::stepper::BattleOutcome::Loss => losses += 1,
+                crate::stepper::BattleOutcome::Draw => {}
+            }
+        }
+        Some(match wins.cmp(&losses) {
+            std::cmp::Ordering::Greater => crate::stepper::BattleOutcome::Win,
+            std::cmp::Ordering::Less => crate::stepper::BattleOutcome::Loss,
+            std::cmp::Ordering::Equal => crate::stepper::BattleOutcome::Draw,
+        })
+    }
+
+    pub fn decode(mut r: impl std::io::Read) -> std::io::Result<Self> {
+        if !is_container(&mut r)? {
+            return Err(invalid_data("invalid header"));
+        }
+        let version = r.read_u8()?;
+        if version != VERSION {
+            return Err(invalid_data(format!("unsupported container version: {:02x}", version)));
+        }
+
+        let raw_metadata = read_chunk(&mut r)?;
+        let metadata = super::Metadata::decode(&raw_metadata[..])?;
+
+        let num_rounds = r.read_u32::<byteorder::LittleEndian>()?;
+        let mut rounds = vec![];
+        for _ in 0..num_rounds {
+            let outcome = match r.read_u8()? {
+                OUTCOME_UNKNOWN => None,
+                OUTCOME_WIN => Some(crate::stepper::BattleOutcome::Win),
+                OUTCOME_LOSS => Some(crate::stepper::BattleOutcome::Loss),
+                OUTCOME_DRAW => Some(crate::stepper::BattleOutcome::Draw),
+                v => {
+                    return Err(invalid_data(format!("invalid outcome: {:02x}", v)));
+                }
+            };
+            let raw = read_chunk(&mut r)?;
+            rounds.push(Round { raw, outcome });
+        }
+
+        Ok(Self { metadata, rounds })
+    }
+
+    pub fn encode(&self, mut w: impl std::io::Write) -> std::io::Result<()> {
+        w.write_all(HEADER)?;
+        w.write_u8(VERSION)?;
+
+        let raw_metadata = self.metadata.encode_to_vec();
+        w.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)?;
+        w.write_all(&raw_metadata)?;
+
+        w.write_u32::<byteorder::LittleEndian>(self.rounds.len() as u32)?;
+        for round in &self.rounds {
+            w.write_u8(match round.outcome {
+                None => OUTCOME_UNKNOWN,
+                Some(crate::stepper::BattleOutcome::Win) => OUTCOME_WIN,
+                Some(crate::stepper::BattleOutcome::Loss) => OUTCOME_LOSS,
+                Some(crate::stepper::BattleOutcome::Draw) => OUTCOME_DRAW,
+            })?;
+            w.write_u32::<byteorder::LittleEndian>(round.raw.len() as u32)?;
+            w.write_all(&round.raw)?;
+        }
+        w.flush()
+    }
+}
+
+/// Adds a round to the container at the given path, creating it if it doesn't exist yet. The container is rewritten
+/// through a temporary file, so a failure partway through leaves the old one intact.
+pub fn append(path: &std::path::Path, round: Round) -> std::io::Result<()> {
+    let mut container = match std::fs::File::open(path) {
+        Ok(f) => Container::decode(std::io::BufReader::new(f))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Container::new(vec![])?,
+        Err(e) => {
+            return Err(e);
+        }
+    };
+    container.push(round)?;
+
+    let tmp_path = path.with_extension("tmp");
+    container.encode(std::io::BufWriter::new(std::fs::File::create(&tmp_path)?))?;
+    std::fs::rename(&tmp_path, path)
+}
@@ -0,0 +1,53 @@
+/// A zstd dictionary for the initial states at the start of a replay, which are mostly the same from one replay to the
+/// next. Replays compressed with one record its ID, and can't be read back without it.
+#[derive(Clone)]
+pub struct Dictionary {
+    id: u32,
+    raw: Vec<u8>,
+}
+
+impl Dictionary {
+    pub fn new(raw: Vec<u8>) -> std::io::Result<Self> {
+        let id = zstd::zstd_safe::get_dict_id_from_dict(&raw);
+        if id == 0 {
+            // Raw content dictionaries have no ID, so there'd be no way to tell which one a replay needs.
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "not a zstd dictionary",
+            ));
+        }
+        Ok(Self { id, raw })
+    }
+
+    /// Trains a dictionary on the states of the given replays, stored the way the writer stores them.
+    pub fn train(replays: &[super::Replay], max_size: usize) -> std::io::Result<Self> {
+        let samples = replays
+            .iter()
+            .flat_map(|replay| {
+                [
+                    replay.local_state.as_slice().to_vec(),
+                    state_delta(replay.local_state.as_slice(), replay.remote_state.as_slice()),
+                ]
+            })
+            .collect::<Vec<_>>();
+        Self::new(zstd::dict::from_samples(&samples, max_size)?)
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+/// XORs a state against the one it is stored relative to. Both sides' states are taken at the same point in the same
+/// battle, so most of the result is zeroes. Applying the delta to the base again gives back the original state.
+pub(crate) fn state_delta(base: &[u8], state: &[u8]) -> Vec<u8> {
+    state
+        .iter()
+        .enumerate()
+        .map(|(i, b)| b ^ base.get(i).copied().unwrap_or(0))
+        .collect()
+}
@@ -1,18 +1,59 @@
-use byteorder::ByteOrder;
+use byteorder::{ByteOrder, WriteBytesExt};
 use image::EncodableLayout;
+use std::io::{Read, Seek, Write};
 use tokio::io::AsyncWriteExt;
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Format {
+    /// Encode with an external ffmpeg binary.
+    #[default]
+    Ffmpeg,
+
+    /// Raw Y4M video, with WAV audio next to it.
+    Y4mWav,
+
+    /// A directory of numbered PNG frames, with WAV audio alongside them.
+    PngSequence,
+
+    /// An animated GIF clip, without audio.
+    Gif,
+
+    /// An animated PNG clip, without audio.
+    Apng,
+}
+
+impl Format {
+    /// The file extension of the output, or `None` if the output is a directory.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Format::Ffmpeg => Some("mp4"),
+            Format::Y4mWav => Some("y4m"),
+            Format::PngSequence => None,
+            Format::Gif => Some("gif"),
+            Format::Apng => Some("png"),
+        }
+    }
+}
+
 pub struct Settings {
+    pub format: Format,
     pub ffmpeg: Option<std::path::PathBuf>,
     pub ffmpeg_audio_flags: String,
     pub ffmpeg_video_flags: String,
     pub ffmpeg_mux_flags: String,
+    /// Scale factor for the built-in formats. ffmpeg does its own scaling via `ffmpeg_video_flags`.
+    pub scale: usize,
+    /// Only export frames within this range of ticks, for each replay.
+    pub tick_range: Option<std::ops::Range<u32>>,
     pub disable_bgm: bool,
+    /// Draw both players' inputs over the video.
+    pub input_display: bool,
 }
 
 impl Settings {
     pub fn default_with_scale(factor: Option<usize>) -> Self {
         Self {
+            format: Format::Ffmpeg,
             ffmpeg: None,
             ffmpeg_audio_flags: if factor.is_some() {
                 "-c:a aac -ar 48000 -b:a 384k -ac 2".to_string()
@@ -25,12 +66,26 @@ impl Settings {
                 "-c:v libx264rgb -preset ultrafast -qp 0".to_string()
             },
             ffmpeg_mux_flags: "-movflags +faststart -strict -2".to_string(),
+            scale: factor.unwrap_or(1),
+            tick_range: None,
             disable_bgm: false,
+            input_display: false,
         }
     }
+
+    fn should_write(&self, tick: u32) -> bool {
+        self.tick_range
+            .as_ref()
+            .map(|range| range.contains(&tick))
+            .unwrap_or(true)
+    }
+
+    fn is_past_end(&self, tick: u32) -> bool {
+        self.tick_range.as_ref().map(|range| tick >= range.end).unwrap_or(false)
+    }
 }
 
-fn fix_vbuf_alpha(vbuf: &mut [u8]) {
+pub(crate) fn fix_vbuf_alpha(vbuf: &mut [u8]) {
     for chunk in vbuf.chunks_mut(4) {
         chunk[3] = 0xff;
     }
@@ -204,6 +259,434 @@ fn make_mux_ffmpeg(
     Ok(child.spawn()?)
 }
 
+fn split_flags(flags: &str) -> anyhow::Result<Vec<std::ffi::OsString>> {
+    Ok(shell_words::split(flags)?
+        .into_iter()
+        .map(std::ffi::OsString::from)
+        .collect::<Vec<_>>())
+}
+
+fn samples_to_bytes(samples: &[i16]) -> Vec<u8> {
+    let mut audio_bytes = vec![0u8; samples.len() * 2];
+    byteorder::LittleEndian::write_i16_into(samples, &mut audio_bytes[..]);
+    audio_bytes
+}
+
+fn scale_frame(vbuf: &image::RgbaImage, scale: usize) -> std::borrow::Cow<'_, image::RgbaImage> {
+    if scale <= 1 {
+        return std::borrow::Cow::Borrowed(vbuf);
+    }
+    std::borrow::Cow::Owned(image::imageops::resize(
+        vbuf,
+        vbuf.width() * scale as u32,
+        vbuf.height() * scale as u32,
+        image::imageops::FilterType::Nearest,
+    ))
+}
+
+/// Where exported frames and audio go.
+#[async_trait::async_trait]
+trait Sink: Send {
+    /// Writes a single frame of video along with the audio for each track that was produced during it.
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, tracks: &[&[i16]]) -> anyhow::Result<()>;
+
+    async fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+struct FfmpegSink {
+    ffmpeg: Option<std::path::PathBuf>,
+    mux_flags: Vec<std::ffi::OsString>,
+    output_path: std::path::PathBuf,
+    video_output: tempfile::NamedTempFile,
+    video_child: tokio::process::Child,
+    audio: Vec<(tempfile::NamedTempFile, tokio::process::Child)>,
+}
+
+impl FfmpegSink {
+    fn new(
+        output_path: &std::path::Path,
+        width: usize,
+        height: usize,
+        num_tracks: usize,
+        settings: &Settings,
+    ) -> anyhow::Result<Self> {
+        let video_output = tempfile::NamedTempFile::new()?;
+        let video_child = make_video_ffmpeg(
+            &settings.ffmpeg,
+            video_output.path(),
+            width,
+            height,
+            &split_flags(&settings.ffmpeg_video_flags)?,
+        )?;
+
+        let audio_flags = split_flags(&settings.ffmpeg_audio_flags)?;
+        let audio = (0..num_tracks)
+            .map(|_| {
+                let audio_output = tempfile::NamedTempFile::new()?;
+                let audio_child = make_audio_ffmpeg(&settings.ffmpeg, audio_output.path(), &audio_flags)?;
+                Ok((audio_output, audio_child))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            ffmpeg: settings.ffmpeg.clone(),
+            mux_flags: split_flags(&settings.ffmpeg_mux_flags)?,
+            output_path: output_path.to_path_buf(),
+            video_output,
+            video_child,
+            audio,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for FfmpegSink {
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, tracks: &[&[i16]]) -> anyhow::Result<()> {
+        for ((_, audio_child), samples) in self.audio.iter_mut().zip(tracks) {
+            audio_child
+                .stdin
+                .as_mut()
+                .unwrap()
+                .write_all(&samples_to_bytes(samples))
+                .await?;
+        }
+        self.video_child
+            .stdin
+            .as_mut()
+            .unwrap()
+            .write_all(vbuf.as_bytes())
+            .await?;
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.video_child.stdin = None;
+        self.video_child.wait().await?;
+        for (_, audio_child) in self.audio.iter_mut() {
+            audio_child.stdin = None;
+            audio_child.wait().await?;
+        }
+
+        let mut mux_child = make_mux_ffmpeg(
+            &self.ffmpeg,
+            &self.output_path,
+            self.video_output.path(),
+            &self.audio.iter().map(|(output, _)| output.path()).collect::<Vec<_>>(),
+            &self.mux_flags,
+        )?;
+        mux_child.wait().await?;
+        Ok(())
+    }
+}
+
+/// A 16-bit stereo WAV file whose sizes get filled in once we know them.
+struct WavWriter {
+    file: std::io::BufWriter<std::fs::File>,
+    data_len: u32,
+}
+
+impl WavWriter {
+    fn create(path: &std::path::Path) -> std::io::Result<Self> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        file.write_all(b"RIFF")?;
+        file.write_u32::<byteorder::LittleEndian>(0)?;
+        file.write_all(b"WAVE")?;
+        file.write_all(b"fmt ")?;
+        file.write_u32::<byteorder::LittleEndian>(16)?;
+        file.write_u16::<byteorder::LittleEndian>(1)?; // PCM
+        file.write_u16::<byteorder::LittleEndian>(2)?;
+        file.write_u32::<byteorder::LittleEndian>(SAMPLE_RATE as u32)?;
+        file.write_u32::<byteorder::LittleEndian>(SAMPLE_RATE as u32 * 2 * 2)?;
+        file.write_u16::<byteorder::LittleEndian>(2 * 2)?;
+        file.write_u16::<byteorder::LittleEndian>(16)?;
+        file.write_all(b"data")?;
+        file.write_u32::<byteorder::LittleEndian>(0)?;
+        Ok(Self { file, data_len: 0 })
+    }
+
+    fn write_samples(&mut self, samples: &[i16]) -> std::io::Result<()> {
+        let buf = samples_to_bytes(samples);
+        self.file.write_all(&buf)?;
+        self.data_len += buf.len() as u32;
+        Ok(())
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        let mut file = self.file.into_inner().map_err(|e| e.into_error())?;
+        file.seek(std::io::SeekFrom::Start(4))?;
+        file.write_u32::<byteorder::LittleEndian>(36 + self.data_len)?;
+        file.seek(std::io::SeekFrom::Start(40))?;
+        file.write_u32::<byteorder::LittleEndian>(self.data_len)?;
+        Ok(())
+    }
+}
+
+/// The path of the WAV file for a given audio track: the first track gets `<base>.wav`, the rest are numbered.
+fn wav_path(base: &std::path::Path, track: usize) -> std::path::PathBuf {
+    if track == 0 {
+        base.with_extension("wav")
+    } else {
+        base.with_extension(format!("{}.wav", track + 1))
+    }
+}
+
+fn create_wavs(base: &std::path::Path, num_tracks: usize) -> std::io::Result<Vec<WavWriter>> {
+    (0..num_tracks)
+        .map(|track| WavWriter::create(&wav_path(base, track)))
+        .collect()
+}
+
+fn write_wavs(wavs: &mut [WavWriter], tracks: &[&[i16]]) -> std::io::Result<()> {
+    for (wav, samples) in wavs.iter_mut().zip(tracks) {
+        wav.write_samples(samples)?;
+    }
+    Ok(())
+}
+
+fn finish_wavs(wavs: Vec<WavWriter>) -> std::io::Result<()> {
+    for wav in wavs {
+        wav.finish()?;
+    }
+    Ok(())
+}
+
+struct Y4mWavSink {
+    file: std::io::BufWriter<std::fs::File>,
+    scale: usize,
+    wavs: Vec<WavWriter>,
+}
+
+impl Y4mWavSink {
+    fn new(
+        output_path: &std::path::Path,
+        width: usize,
+        height: usize,
+        num_tracks: usize,
+        scale: usize,
+    ) -> anyhow::Result<Self> {
+        let scale = scale.max(1);
+        let mut file = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+        // The GBA runs at 16777216 / 280896 fps. Chroma isn't subsampled, so pixel art stays crisp.
+        writeln!(
+            file,
+            "YUV4MPEG2 W{} H{} F16777216:280896 Ip A1:1 C444",
+            width * scale,
+            height * scale
+        )?;
+        Ok(Self {
+            file,
+            scale,
+            wavs: create_wavs(output_path, num_tracks)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for Y4mWavSink {
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, tracks: &[&[i16]]) -> anyhow::Result<()> {
+        let vbuf = scale_frame(vbuf, self.scale);
+        let n = (vbuf.width() * vbuf.height()) as usize;
+        let mut planes = vec![0u8; n * 3];
+        for (i, pixel) in vbuf.pixels().enumerate() {
+            let [r, g, b, _] = pixel.0;
+            let (r, g, b) = (r as f32, g as f32, b as f32);
+            // BT.601, studio swing.
+            planes[i] = (16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0).round() as u8;
+            planes[n + i] = (128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0).round() as u8;
+            planes[n * 2 + i] = (128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0).round() as u8;
+        }
+        self.file.write_all(b"FRAME\n")?;
+        self.file.write_all(&planes)?;
+        write_wavs(&mut self.wavs, tracks)?;
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.file.flush()?;
+        finish_wavs(self.wavs)?;
+        Ok(())
+    }
+}
+
+struct PngSequenceSink {
+    output_path: std::path::PathBuf,
+    scale: usize,
+    next_index: usize,
+    wavs: Vec<WavWriter>,
+}
+
+impl PngSequenceSink {
+    fn new(output_path: &std::path::Path, num_tracks: usize, scale: usize) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(output_path)?;
+        Ok(Self {
+            output_path: output_path.to_path_buf(),
+            scale,
+            next_index: 0,
+            wavs: create_wavs(&output_path.join("audio"), num_tracks)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for PngSequenceSink {
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, tracks: &[&[i16]]) -> anyhow::Result<()> {
+        scale_frame(vbuf, self.scale).save_with_format(
+            self.output_path.join(format!("{:06}.png", self.next_index)),
+            image::ImageFormat::Png,
+        )?;
+        self.next_index += 1;
+        write_wavs(&mut self.wavs, tracks)?;
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        finish_wavs(self.wavs)?;
+        Ok(())
+    }
+}
+
+/// Clips only keep every other frame: that's plenty for sharing, and keeps GIF delays representable.
+const CLIP_FRAME_STRIDE: u64 = 2;
+
+/// When the nth kept clip frame should be shown, in hundredths of a second.
+fn clip_frame_time_cs(n: u64) -> u64 {
+    (n * CLIP_FRAME_STRIDE * 280896 * 100 + 16777216 / 2) / 16777216
+}
+
+struct GifSink {
+    encoder: image::codecs::gif::GifEncoder<std::io::BufWriter<std::fs::File>>,
+    scale: usize,
+    frame: u64,
+}
+
+impl GifSink {
+    fn new(output_path: &std::path::Path, scale: usize) -> anyhow::Result<Self> {
+        let mut encoder = image::codecs::gif::GifEncoder::new_with_speed(
+            std::io::BufWriter::new(std::fs::File::create(output_path)?),
+            10,
+        );
+        encoder.set_repeat(image::codecs::gif::Repeat::Infinite)?;
+        Ok(Self {
+            encoder,
+            scale,
+            frame: 0,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for GifSink {
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, _tracks: &[&[i16]]) -> anyhow::Result<()> {
+        let frame = self.frame;
+        self.frame += 1;
+        if frame % CLIP_FRAME_STRIDE != 0 {
+            return Ok(());
+        }
+
+        // Work out each delay from the absolute frame times so rounding to hundredths doesn't drift.
+        let n = frame / CLIP_FRAME_STRIDE;
+        let delay_cs = clip_frame_time_cs(n + 1) - clip_frame_time_cs(n);
+        self.encoder.encode_frame(image::Frame::from_parts(
+            scale_frame(vbuf, self.scale).into_owned(),
+            0,
+            0,
+            image::Delay::from_numer_denom_ms(delay_cs as u32 * 10, 1),
+        ))?;
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Two GBA frames (2 * 280896 / 16777216 s) as closely as a u16 fraction gets.
+const APNG_FRAME_DELAY: (u16, u16) = (1097, 32761);
+
+/// APNG needs the frame count up front, so frames are spooled to a compressed temporary file until the end.
+struct ApngSink {
+    output_path: std::path::PathBuf,
+    width: u32,
+    height: u32,
+    scale: usize,
+    frame: u64,
+    num_frames: u32,
+    spool: zstd::stream::write::Encoder<'static, std::io::BufWriter<std::fs::File>>,
+}
+
+impl ApngSink {
+    fn new(output_path: &std::path::Path, width: usize, height: usize, scale: usize) -> anyhow::Result<Self> {
+        Ok(Self {
+            output_path: output_path.to_path_buf(),
+            width: width as u32,
+            height: height as u32,
+            scale,
+            frame: 0,
+            num_frames: 0,
+            spool: zstd::stream::write::Encoder::new(std::io::BufWriter::new(tempfile::tempfile()?), 1)?,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for ApngSink {
+    async fn write_frame(&mut self, vbuf: &image::RgbaImage, _tracks: &[&[i16]]) -> anyhow::Result<()> {
+        let frame = self.frame;
+        self.frame += 1;
+        if frame % CLIP_FRAME_STRIDE != 0 {
+            return Ok(());
+        }
+        self.spool.write_all(vbuf.as_raw())?;
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        if self.num_frames == 0 {
+            anyhow::bail!("no frames to export");
+        }
+        let scale = self.scale.max(1) as u32;
+
+        let mut spool = self.spool.finish()?.into_inner().map_err(|e| e.into_error())?;
+        spool.seek(std::io::SeekFrom::Start(0))?;
+        let mut spool = zstd::stream::read::Decoder::new(spool)?;
+
+        let mut encoder = png::Encoder::new(
+            std::io::BufWriter::new(std::fs::File::create(&self.output_path)?),
+            self.width * scale,
+            self.height * scale,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(self.num_frames, 0)?;
+        encoder.set_frame_delay(APNG_FRAME_DELAY.0, APNG_FRAME_DELAY.1)?;
+        let mut writer = encoder.write_header()?;
+        let mut frame = image::RgbaImage::new(self.width, self.height);
+        for _ in 0..self.num_frames {
+            spool.read_exact(&mut frame)?;
+            writer.write_image_data(scale_frame(&frame, self.scale).as_raw())?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+fn make_sink(
+    output_path: &std::path::Path,
+    width: usize,
+    height: usize,
+    num_tracks: usize,
+    settings: &Settings,
+) -> anyhow::Result<Box<dyn Sink>> {
+    Ok(match settings.format {
+        Format::Ffmpeg => Box::new(FfmpegSink::new(output_path, width, height, num_tracks, settings)?),
+        Format::Y4mWav => Box::new(Y4mWavSink::new(output_path, width, height, num_tracks, settings.scale)?),
+        Format::PngSequence => Box::new(PngSequenceSink::new(output_path, num_tracks, settings.scale)?),
+        Format::Gif => Box::new(GifSink::new(output_path, settings.scale)?),
+        Format::Apng => Box::new(ApngSink::new(output_path, width, height, settings.scale)?),
+    })
+}
+
 pub async fn export(
     rom: &[u8],
     hooks: &(dyn crate::hooks::Hooks + Send + Sync + 'static),
@@ -214,26 +697,12 @@ pub async fn export(
 ) -> anyhow::Result<()> {
     let mut vbuf = image::RgbaImage::new(mgba::gba::SCREEN_WIDTH, mgba::gba::SCREEN_HEIGHT);
 
-    let video_output = tempfile::NamedTempFile::new()?;
-    let mut video_child = make_video_ffmpeg(
-        &settings.ffmpeg,
-        video_output.path(),
+    let mut sink = make_sink(
+        output_path,
         mgba::gba::SCREEN_WIDTH as usize,
         mgba::gba::SCREEN_HEIGHT as usize,
-        &shell_words::split(&settings.ffmpeg_video_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
-    )?;
-
-    let audio_output = tempfile::NamedTempFile::new()?;
-    let mut audio_child = make_audio_ffmpeg(
-        &settings.ffmpeg,
-        audio_output.path(),
-        &shell_words::split(&settings.ffmpeg_audio_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
+        1,
+        settings,
     )?;
 
     let total_frames = replays.iter().map(|replay| replay.input_pairs.len()).sum();
@@ -245,11 +714,16 @@ pub async fn export(
         let replay_len = replay.input_pairs.len();
 
         loop {
-            {
+            let current_tick = {
                 let state = state.lock_inner();
                 if (!replay.is_complete && state.input_pairs_left() == 0) || state.is_round_ended() {
                     break;
                 }
+                state.current_tick()
+            };
+
+            if settings.is_past_end(current_tick) {
+                break;
             }
 
             if let Some(err) = state.lock_inner().take_error() {
@@ -257,11 +731,15 @@ pub async fn export(
             }
 
             let samples = run_frame(&mut core, &mut samples, &mut vbuf);
-            video_child.stdin.as_mut().unwrap().write_all(&vbuf).await?;
+            if settings.input_display {
+                if let Some(joyflags) = state.lock_inner().last_joyflags() {
+                    crate::input_display::draw_both(&mut vbuf, joyflags);
+                }
+            }
+            if settings.should_write(current_tick) {
+                sink.write_frame(&vbuf, &[samples]).await?;
+            }
 
-            let mut audio_bytes = vec![0u8; samples.len() * 2];
-            byteorder::LittleEndian::write_i16_into(samples, &mut audio_bytes[..]);
-            audio_child.stdin.as_mut().unwrap().write_all(&audio_bytes).await?;
             progress_callback(
                 replay_len - state.lock_inner().input_pairs_left() + completed_total,
                 total_frames,
@@ -271,22 +749,7 @@ pub async fn export(
         completed_total += replay_len;
     }
 
-    video_child.stdin = None;
-    video_child.wait().await?;
-    audio_child.stdin = None;
-    audio_child.wait().await?;
-
-    let mut mux_child = make_mux_ffmpeg(
-        &settings.ffmpeg,
-        output_path,
-        video_output.path(),
-        &[audio_output.path()],
-        &shell_words::split(&settings.ffmpeg_mux_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
-    )?;
-    mux_child.wait().await?;
+    sink.finish().await?;
 
     Ok(())
 }
@@ -304,42 +767,19 @@ pub async fn export_twosided(
     let mut vbuf = image::RgbaImage::new(mgba::gba::SCREEN_WIDTH, mgba::gba::SCREEN_HEIGHT);
     let mut composed_vbuf = image::RgbaImage::new(mgba::gba::SCREEN_WIDTH * 2, mgba::gba::SCREEN_HEIGHT);
 
-    let video_output = tempfile::NamedTempFile::new()?;
-    let mut video_child = make_video_ffmpeg(
-        &settings.ffmpeg,
-        video_output.path(),
+    let mut sink = make_sink(
+        output_path,
         (mgba::gba::SCREEN_WIDTH * 2) as usize,
         mgba::gba::SCREEN_HEIGHT as usize,
-        &shell_words::split(&settings.ffmpeg_video_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
-    )?;
-
-    let local_audio_output = tempfile::NamedTempFile::new()?;
-    let mut local_audio_child = make_audio_ffmpeg(
-        &settings.ffmpeg,
-        local_audio_output.path(),
-        &shell_words::split(&settings.ffmpeg_audio_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
-    )?;
-
-    let remote_audio_output = tempfile::NamedTempFile::new()?;
-    let mut remote_audio_child = make_audio_ffmpeg(
-        &settings.ffmpeg,
-        remote_audio_output.path(),
-        &shell_words::split(&settings.ffmpeg_audio_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
+        2,
+        settings,
     )?;
 
     let total_frames = replays.iter().map(|replay| replay.input_pairs.len()).sum();
 
     let mut completed_total = 0;
-    let mut samples = vec![0i16; SAMPLE_RATE as usize];
+    let mut local_samples = vec![0i16; SAMPLE_RATE as usize];
+    let mut remote_samples = vec![0i16; SAMPLE_RATE as usize];
 
     for replay in replays {
         let local_replay = replay.clone();
@@ -375,6 +815,10 @@ pub async fn export_twosided(
                 );
             }
 
+            if settings.is_past_end(current_tick) {
+                break;
+            }
+
             while local_state.lock_inner().current_tick() == current_tick
                 && remote_state.lock_inner().current_tick() == current_tick
             {
@@ -386,46 +830,30 @@ pub async fn export_twosided(
                     Err(err)?;
                 }
 
-                {
-                    let local_samples = run_frame(&mut local_core, &mut samples, &mut vbuf);
-                    image::imageops::replace(&mut composed_vbuf, &vbuf, 0, 0);
-                    let mut audio_bytes = vec![0u8; local_samples.len() * 2];
-                    byteorder::LittleEndian::write_i16_into(local_samples, &mut audio_bytes[..]);
-                    local_audio_child
-                        .stdin
-                        .as_mut()
-                        .unwrap()
-                        .write_all(&audio_bytes)
-                        .await?;
+                let local_samples = run_frame(&mut local_core, &mut local_samples, &mut vbuf);
+                image::imageops::replace(&mut composed_vbuf, &vbuf, 0, 0);
+
+                let remote_samples = run_frame(&mut remote_core, &mut remote_samples, &mut vbuf);
+                image::imageops::replace(&mut composed_vbuf, &vbuf, mgba::gba::SCREEN_WIDTH as i64, 0);
+
+                if settings.input_display {
+                    if let Some(joyflags) = local_state.lock_inner().last_joyflags() {
+                        crate::input_display::draw_both(&mut composed_vbuf, joyflags);
+                    }
                 }
 
-                {
-                    let remote_samples = run_frame(&mut remote_core, &mut samples, &mut vbuf);
-                    image::imageops::replace(&mut composed_vbuf, &vbuf, mgba::gba::SCREEN_WIDTH as i64, 0);
-                    let mut audio_bytes = vec![0u8; remote_samples.len() * 2];
-                    byteorder::LittleEndian::write_i16_into(remote_samples, &mut audio_bytes[..]);
-                    remote_audio_child
-                        .stdin
-                        .as_mut()
-                        .unwrap()
-                        .write_all(&audio_bytes)
+                if settings.should_write(current_tick) {
+                    sink.write_frame(&composed_vbuf, &[local_samples, remote_samples])
                         .await?;
                 }
-
-                video_child
-                    .stdin
-                    .as_mut()
-                    .unwrap()
-                    .write_all(composed_vbuf.as_bytes())
-                    .await?;
             }
 
             while local_state.lock_inner().current_tick() == current_tick {
-                run_frame(&mut local_core, &mut samples, &mut vbuf);
+                run_frame(&mut local_core, &mut local_samples, &mut vbuf);
             }
 
             while remote_state.lock_inner().current_tick() == current_tick {
-                run_frame(&mut remote_core, &mut samples, &mut vbuf);
+                run_frame(&mut remote_core, &mut remote_samples, &mut vbuf);
             }
 
             progress_callback(current_tick as usize + completed_total, total_frames);
@@ -434,24 +862,7 @@ pub async fn export_twosided(
         completed_total += replay_len;
     }
 
-    video_child.stdin = None;
-    video_child.wait().await?;
-    local_audio_child.stdin = None;
-    local_audio_child.wait().await?;
-    remote_audio_child.stdin = None;
-    remote_audio_child.wait().await?;
-
-    let mut mux_child = make_mux_ffmpeg(
-        &settings.ffmpeg,
-        output_path,
-        video_output.path(),
-        &[local_audio_output.path(), remote_audio_output.path()],
-        &shell_words::split(&settings.ffmpeg_mux_flags)?
-            .into_iter()
-            .map(std::ffi::OsString::from)
-            .collect::<Vec<_>>(),
-    )?;
-    mux_child.wait().await?;
+    sink.finish().await?;
 
     Ok(())
 }
@@ -0,0 +1,123 @@
+use prost::Message;
+use sha2::Digest;
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+pub const SIGNATURE_LEN: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Signature {
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl Signature {
+    pub fn verify(&self, digest: &[u8]) -> bool {
+        ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, &self.public_key)
+            .verify(digest, &self.signature)
+            .is_ok()
+    }
+}
+
+/// A per-install Ed25519 key used to sign the rounds we play.
+pub struct SigningKey(ring::signature::Ed25519KeyPair);
+
+impl SigningKey {
+    pub fn from_seed(seed: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self(
+            ring::signature::Ed25519KeyPair::from_seed_unchecked(seed)
+                .map_err(|e| anyhow::anyhow!("invalid signing key: {}", e))?,
+        ))
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        use ring::signature::KeyPair;
+        self.0.public_key().as_ref().to_vec()
+    }
+
+    pub fn sign(&self, digest: &[u8]) -> Signature {
+        Signature {
+            public_key: self.public_key(),
+            signature: self.0.sign(digest).as_ref().to_vec(),
+        }
+    }
+}
+
+/// Hashes the parts of a round that both peers agree on, in the same order regardless of which side is hashing.
+///
+/// This covers the metadata (minus the timestamp, which each side picks for itself), including the key each side
+/// presented, and every committed input's joyflags and packets. Timing information, which differs per side, is left
+/// out.
+#[derive(Clone)]
+pub struct Digester {
+    hasher: sha2::Sha256,
+    local_player_index: u8,
+}
+
+impl Digester {
+    pub fn new(metadata: &super::Metadata, local_player_index: u8) -> Self {
+        let mut metadata = metadata.clone();
+        metadata.ts = 0;
+        if local_player_index != 0 {
+            std::mem::swap(&mut metadata.local_side, &mut metadata.remote_side);
+        }
+        let raw_metadata = metadata.encode_to_vec();
+
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(b"tango round\0");
+        hasher.update((raw_metadata.len() as u32).to_le_bytes());
+        hasher.update(&raw_metadata);
+        Self {
+            hasher,
+            local_player_index,
+        }
+    }
+
+    pub fn update(&mut self, ip: &crate::input::Pair<crate::input::Input, crate::input::Input>) {
+        let (p1, p2) = if self.local_player_index == 0 {
+            (&ip.local, &ip.remote)
+        } else {
+            (&ip.remote, &ip.local)
+        };
+        self.hasher.update(ip.local.local_tick.to_le_bytes());
+        for input in [p1, p2] {
+            self.hasher.update(input.joyflags.to_le_bytes());
+            self.hasher.update((input.packet.len() as u32).to_le_bytes());
+            self.hasher.update(&input.packet);
+        }
+    }
+
+    pub fn finish(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+}
+
+/// Computes the digest the peers signed at the end of the round.
+pub fn digest(replay: &super::Replay) -> [u8; 32] {
+    let mut digester = Digester::new(&replay.metadata, replay.local_player_index);
+    for ip in &replay.input_pairs {
+        digester.update(ip);
+    }
+    digester.finish()
+}
+
+/// Counts the valid signatures each side made with the key it presented during the handshake, local side first. A
+/// side that presented no key, e.g. in a replay recorded before keys were exchanged, gets `None`.
+pub fn count_bound_signatures(replay: &super::Replay) -> [Option<usize>; 2] {
+    let digest = digest(replay);
+    [
+        replay.metadata.local_side.as_ref(),
+        replay.metadata.remote_side.as_ref(),
+    ]
+    .map(|side| {
+        let public_key = side
+            .map(|side| side.signing_public_key.as_slice())
+            .filter(|public_key| !public_key.is_empty())?;
+        Some(
+            replay
+                .signatures
+                .iter()
+                .filter(|s| s.public_key == public_key && s.verify(&digest))
+                .count(),
+        )
+    })
+}
@@ -0,0 +1,550 @@
+use byteorder::WriteBytesExt;
+use prost::Message;
+use std::io::Read;
+use std::io::Write;
+
+const PACKET_SIZE: usize = 4;
+
+fn synthetic_state(seed: u8) -> Box<mgba::state::State> {
+    let raw = (0..std::mem::size_of::<mgba::state::State>())
+        .map(|i| (i as u8).wrapping_mul(31).wrapping_add(seed))
+        .collect::<Vec<_>>();
+    mgba::state::State::from_slice(&raw)
+}
+
+fn synthetic_metadata() -> super::Metadata {
+    super::Metadata {
+        ts: 1_700_000_000_000,
+        link_code: "fixture".to_string(),
+        local_side: Some(super::metadata::Side {
+            nickname: "local".to_string(),
+            game_info: Some(super::metadata::GameInfo {
+                rom_family: "bn6".to_string(),
+                rom_variant: 1,
+                patch: None,
+                rom_sha256: vec![],
+                rom_crc32: 0,
+            }),
+            reveal_setup: false,
+            signing_public_key: vec![],
+        }),
+        remote_side: Some(super::metadata::Side {
+            nickname: "remote".to_string(),
+            game_info: Some(super::metadata::GameInfo {
+                rom_family: "bn6".to_string(),
+                rom_variant: 0,
+                patch: Some(super::metadata::game_info::Patch {
+                    name: "patch".to_string(),
+                    version: "1.0.0".to_string(),
+                }),
+                rom_sha256: vec![],
+                rom_crc32: 0,
+            }),
+            reveal_setup: true,
+            signing_public_key: vec![],
+        }),
+        round: 1,
+        match_type: 1,
+        match_subtype: 0,
+    }
+}
+
+fn synthetic_input_pairs(n: u32) -> Vec<crate::input::Pair<crate::input::Input, crate::input::Input>> {
+    (0..n)
+        .map(|tick| crate::input::Pair {
+            local: crate::input::Input {
+                local_tick: tick,
+                remote_tick: tick.saturating_sub(2),
+                joyflags: (tick * 3) as u16,
+                packet: vec![tick as u8; PACKET_SIZE],
+                dt: std::time::Duration::from_millis(16 + (tick % 2) as u64),
+            },
+            remote: crate::input::Input {
+                local_tick: tick,
+                remote_tick: tick,
+                joyflags: (tick * 5) as u16,
+                packet: vec![!(tick as u8); PACKET_SIZE],
+                dt: std::time::Duration::from_millis(16 + (tick % 2) as u64),
+            },
+        })
+        .collect()
+}
+
+fn synthetic_replay(local_player_index: u8) -> super::Replay {
+    super::Replay {
+        is_complete: true,
+        metadata: synthetic_metadata(),
+        local_player_index,
+        local_state: synthetic_state(1),
+        remote_state: synthetic_state(2),
+        input_pairs: synthetic_input_pairs(200),
+        wram_checksums: (1..4)
+            .map(|i| super::WramChecksum {
+                tick: i * super::WRAM_CHECKSUM_INTERVAL,
+                crc32: 0xdeadbeef ^ i,
+            })
+            .collect(),
+        signatures: vec![],
+    }
+}
+
+/// Lays out a replay the way 0x11 and 0x12 writers did: untagged input pairs with no checksums. 0x11 pairs have no dt.
+fn encode_untagged(replay: &super::Replay, version: u8) -> Vec<u8> {
+    let mut buf = vec![];
+    buf.write_all(super::HEADER).unwrap();
+    buf.write_u8(version).unwrap();
+    buf.write_u32::<byteorder::LittleEndian>(replay.input_pairs.len() as u32)
+        .unwrap();
+    let raw_metadata = replay.metadata.encode_to_vec();
+    buf.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)
+        .unwrap();
+    buf.write_all(&raw_metadata).unwrap();
+
+    let mut zw = zstd::stream::write::Encoder::new(buf, 3).unwrap();
+    zw.write_u8(replay.local_player_index).unwrap();
+    zw.write_u8(PACKET_SIZE as u8).unwrap();
+    for state in [&replay.local_state, &replay.remote_state] {
+        zw.write_u32::<byteorder::LittleEndian>(state.as_slice().len() as u32)
+            .unwrap();
+        zw.write_all(state.as_slice()).unwrap();
+    }
+    for ip in &replay.input_pairs {
+        let (p1, p2) = if replay.local_player_index == 0 {
+            (&ip.local, &ip.remote)
+        } else {
+            (&ip.remote, &ip.local)
+        };
+        zw.write_u32::<byteorder::LittleEndian>(ip.local.local_tick).unwrap();
+        zw.write_u32::<byteorder::LittleEndian>(ip.local.remote_tick).unwrap();
+        if version >= 0x12 {
+            zw.write_u16::<byteorder::LittleEndian>(ip.local.dt.as_millis() as u16)
+                .unwrap();
+        }
+        zw.write_u16::<byteorder::LittleEndian>(p1.joyflags).unwrap();
+        zw.write_all(&p1.packet).unwrap();
+        zw.write_u16::<byteorder::LittleEndian>(p2.joyflags).unwrap();
+        zw.write_all(&p2.packet).unwrap();
+    }
+    zw.finish().unwrap()
+}
+
+/// Lays out a replay the way 0x15 writers did: both states stored in full, and an end of round record.
+fn encode_v15(replay: &super::Replay) -> Vec<u8> {
+    let mut buf = vec![];
+    buf.write_all(super::HEADER).unwrap();
+    buf.write_u8(0x15).unwrap();
+    buf.write_u32::<byteorder::LittleEndian>(replay.input_pairs.len() as u32)
+        .unwrap();
+    let raw_metadata = replay.metadata.encode_to_vec();
+    buf.write_u32::<byteorder::LittleEndian>(raw_metadata.len() as u32)
+        .unwrap();
+    buf.write_all(&raw_metadata).unwrap();
+
+    let mut zw = zstd::stream::write::Encoder::new(buf, 3).unwrap();
+    zw.write_u8(replay.local_player_index).unwrap();
+    zw.write_u8(PACKET_SIZE as u8).unwrap();
+    for state in [&replay.local_state, &replay.remote_state] {
+        zw.write_u32::<byteorder::LittleEndian>(state.as_slice().len() as u32)
+            .unwrap();
+        zw.write_all(state.as_slice()).unwrap();
+    }
+    for ip in &replay.input_pairs {
+        let (p1, p2) = if replay.local_player_index == 0 {
+            (&ip.local, &ip.remote)
+        } else {
+            (&ip.remote, &ip.local)
+        };
+        zw.write_u8(super::RECORD_INPUT_PAIR).unwrap();
+        zw.write_u32::<byteorder::LittleEndian>(ip.local.local_tick).unwrap();
+        zw.write_u32::<byteorder::LittleEndian>(ip.local.remote_tick).unwrap();
+        zw.write_u16::<byteorder::LittleEndian>(ip.local.dt.as_millis() as u16)
+            .unwrap();
+        zw.write_u16::<byteorder::LittleEndian>(p1.joyflags).unwrap();
+        zw.write_all(&p1.packet).unwrap();
+        zw.write_u16::<byteorder::LittleEndian>(p2.joyflags).unwrap();
+        zw.write_all(&p2.packet).unwrap();
+    }
+    zw.write_u8(super::RECORD_END_OF_ROUND).unwrap();
+    zw.finish().unwrap()
+}
+
+fn encode_current(replay: &super::Replay) -> Vec<u8> {
+    let mut w = replay.encode(std::io::Cursor::new(vec![])).unwrap();
+    w.seek(std::io::SeekFrom::Start(0)).unwrap();
+    let mut buf = vec![];
+    w.read_to_end(&mut buf).unwrap();
+    buf
+}
+
+fn assert_replays_eq(got: &super::Replay, want: &super::Replay) {
+    assert_eq!(got.is_complete, want.is_complete);
+    assert_eq!(got.metadata, want.metadata);
+    assert_eq!(got.local_player_index, want.local_player_index);
+    assert_eq!(got.local_state.as_slice(), want.local_state.as_slice());
+    assert_eq!(got.remote_state.as_slice(), want.remote_state.as_slice());
+    assert_eq!(got.wram_checksums, want.wram_checksums);
+    assert_eq!(got.signatures, want.signatures);
+    assert_eq!(got.input_pairs.len(), want.input_pairs.len());
+    for (got, want) in got.input_pairs.iter().zip(want.input_pairs.iter()) {
+        for (got, want) in [(&got.local, &want.local), (&got.remote, &want.remote)] {
+            assert_eq!(got.local_tick, want.local_tick);
+            assert_eq!(got.joyflags, want.joyflags);
+            assert_eq!(got.packet, want.packet);
+            assert_eq!(got.dt, want.dt);
+        }
+        assert_eq!(got.local.remote_tick, want.local.remote_tick);
+    }
+}
+
+#[test]
+fn test_round_trip_current() {
+    for local_player_index in [0, 1] {
+        let replay = synthetic_replay(local_player_index);
+        let buf = encode_current(&replay);
+        assert_eq!(super::read_version(&mut &buf[..]).unwrap(), super::VERSION);
+        assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+    }
+}
+
+/// A replay as an 0x11 writer would have recorded it: no checksums, and no time between inputs.
+fn synthetic_replay_v11(local_player_index: u8) -> super::Replay {
+    let mut replay = synthetic_replay(local_player_index);
+    replay.wram_checksums.clear();
+    for ip in replay.input_pairs.iter_mut() {
+        ip.local.dt = std::time::Duration::ZERO;
+        ip.remote.dt = std::time::Duration::ZERO;
+    }
+    replay
+}
+
+#[test]
+fn test_decode_v11() {
+    for local_player_index in [0, 1] {
+        let replay = synthetic_replay_v11(local_player_index);
+        let buf = encode_untagged(&replay, 0x11);
+        assert_eq!(super::read_version(&mut &buf[..]).unwrap(), 0x11);
+        assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+    }
+}
+
+#[test]
+fn test_upgrade_v11() {
+    let replay = synthetic_replay_v11(1);
+    let upgraded = encode_current(&super::Replay::decode(&encode_untagged(&replay, 0x11)[..]).unwrap());
+    assert_eq!(super::read_version(&mut &upgraded[..]).unwrap(), super::VERSION);
+    assert_replays_eq(&super::Replay::decode(&upgraded[..]).unwrap(), &replay);
+}
+
+#[test]
+fn test_decode_v12() {
+    for local_player_index in [0, 1] {
+        let mut replay = synthetic_replay(local_player_index);
+        replay.wram_checksums.clear();
+        let buf = encode_untagged(&replay, 0x12);
+        assert_eq!(super::read_version(&mut &buf[..]).unwrap(), 0x12);
+        assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+    }
+}
+
+#[test]
+fn test_upgrade_v12() {
+    let mut replay = synthetic_replay(1);
+    replay.wram_checksums.clear();
+    let upgraded = encode_current(&super::Replay::decode(&encode_untagged(&replay, 0x12)[..]).unwrap());
+    assert_eq!(super::read_version(&mut &upgraded[..]).unwrap(), super::VERSION);
+    assert_replays_eq(&super::Replay::decode(&upgraded[..]).unwrap(), &replay);
+}
+
+#[test]
+fn test_round_trip_incomplete() {
+    let mut replay = synthetic_replay(0);
+    replay.is_complete = false;
+    let buf = encode_current(&replay);
+    assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+}
+
+#[test]
+fn test_truncated() {
+    let replay = synthetic_replay(0);
+    let buf = encode_current(&replay);
+    let truncated = super::Replay::decode(&buf[..buf.len() - 8]);
+    // A truncated zstd stream may either fail outright or decode a prefix of the inputs, but must never claim to be
+    // complete.
+    if let Ok(truncated) = truncated {
+        assert!(!truncated.is_complete);
+    }
+}
+
+#[test]
+fn test_unsupported_version() {
+    let replay = synthetic_replay(0);
+    for version in [0x10, 0x17] {
+        let mut buf = encode_current(&replay);
+        buf[super::HEADER.len()] = version;
+        assert!(super::Replay::decode(&buf[..]).is_err());
+        assert!(super::read_metadata(&mut &buf[..]).is_err());
+    }
+    assert!(!super::SUPPORTED_VERSIONS.contains(&0x10));
+}
+
+#[test]
+fn test_decode_bad_lengths() {
+    let replay = synthetic_replay(0);
+    let raw_metadata = replay.metadata.encode_to_vec();
+    let metadata_len_offset = super::HEADER.len() + 1 + 4;
+    let error_kind = |buf: &[u8]| super::Replay::decode(buf).err().unwrap().kind();
+
+    // A metadata length far past the end of the input.
+    let mut buf = encode_untagged(&replay, 0x12);
+    buf[metadata_len_offset..metadata_len_offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(error_kind(&buf), std::io::ErrorKind::UnexpectedEof);
+
+    // A state that isn't the size of an mGBA state.
+    let mut buf = encode_untagged(&replay, 0x12);
+    buf.truncate(metadata_len_offset + 4 + raw_metadata.len());
+    let mut zw = zstd::stream::write::Encoder::new(buf, 3).unwrap();
+    zw.write_u8(replay.local_player_index).unwrap();
+    zw.write_u8(PACKET_SIZE as u8).unwrap();
+    zw.write_u32::<byteorder::LittleEndian>(16).unwrap();
+    zw.write_all(&[0u8; 16]).unwrap();
+    assert_eq!(error_kind(&zw.finish().unwrap()), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_signatures() {
+    let key_a = super::signing::SigningKey::from_seed(&[1u8; 32]).unwrap();
+    let key_b = super::signing::SigningKey::from_seed(&[2u8; 32]).unwrap();
+
+    let replay = synthetic_replay(0);
+    let digest = super::signing::digest(&replay);
+
+    // Both sides have to come up with the same digest for their own view of the round.
+    assert_eq!(super::signing::digest(&replay.clone().into_remote()), digest);
+
+    let mut w = replay.encode(std::io::Cursor::new(vec![])).unwrap();
+    super::append_signature(&mut *w, &key_a.sign(&digest)).unwrap();
+    super::append_signature(&mut *w, &key_b.sign(&digest)).unwrap();
+    w.seek(std::io::SeekFrom::Start(0)).unwrap();
+    let mut buf = vec![];
+    w.read_to_end(&mut buf).unwrap();
+
+    let mut decoded = super::Replay::decode(&buf[..]).unwrap();
+    assert_eq!(decoded.signatures.len(), 2);
+    assert_eq!(decoded.signatures[0].public_key, key_a.public_key());
+    assert_eq!(decoded.signatures[1].public_key, key_b.public_key());
+    assert!(decoded
+        .signatures
+        .iter()
+        .all(|s| s.verify(&super::signing::digest(&decoded))));
+
+    // Signatures survive re-encoding.
+    let reencoded = super::Replay::decode(&encode_current(&decoded)[..]).unwrap();
+    assert_eq!(reencoded.signatures, decoded.signatures);
+
+    // Tampering with the inputs invalidates them.
+    decoded.input_pairs[10].local.joyflags ^= 1;
+    assert!(decoded
+        .signatures
+        .iter()
+        .all(|s| !s.verify(&super::signing::digest(&decoded))));
+}
+
+#[test]
+fn test_bound_signatures() {
+    let key_a = super::signing::SigningKey::from_seed(&[1u8; 32]).unwrap();
+    let key_b = super::signing::SigningKey::from_seed(&[2u8; 32]).unwrap();
+    let key_c = super::signing::SigningKey::from_seed(&[3u8; 32]).unwrap();
+
+    let mut replay = synthetic_replay(0);
+    assert_eq!(super::signing::count_bound_signatures(&replay), [None, None]);
+
+    replay.metadata.local_side.as_mut().unwrap().signing_public_key = key_a.public_key();
+    replay.metadata.remote_side.as_mut().unwrap().signing_public_key = key_b.public_key();
+    let digest = super::signing::digest(&replay);
+
+    // A signature from a key neither side presented doesn't count for either of them.
+    replay.signatures = vec![key_a.sign(&digest), key_c.sign(&digest)];
+    assert_eq!(super::signing::count_bound_signatures(&replay), [Some(1), Some(0)]);
+
+    replay.signatures.push(key_b.sign(&digest));
+    assert_eq!(super::signing::count_bound_signatures(&replay), [Some(1), Some(1)]);
+
+    // The keys are covered by the digest, so swapping one out invalidates the signatures.
+    replay.metadata.remote_side.as_mut().unwrap().signing_public_key = key_c.public_key();
+    assert_ne!(super::signing::digest(&replay), digest);
+    assert_eq!(super::signing::count_bound_signatures(&replay), [Some(0), Some(0)]);
+}
+
+/// Writes a replay the way a match does, but leaves it for the caller to either finish or abandon.
+fn write_replay(replay: &super::Replay) -> super::Writer {
+    let mut writer = super::Writer::new(
+        std::io::Cursor::new(vec![]),
+        replay.metadata.clone(),
+        replay.local_player_index,
+        PACKET_SIZE as u8,
+    )
+    .unwrap();
+    writer.write_state(&replay.local_state).unwrap();
+    writer.write_state(&replay.remote_state).unwrap();
+    for ip in &replay.input_pairs {
+        writer.write_input(replay.local_player_index, ip).unwrap();
+    }
+    writer
+}
+
+#[test]
+fn test_end_of_round() {
+    let replay = synthetic_replay(0);
+    let mut w = write_replay(&replay).finish().unwrap();
+    w.seek(std::io::SeekFrom::Start(0)).unwrap();
+    let mut buf = vec![];
+    w.read_to_end(&mut buf).unwrap();
+    assert!(super::Replay::decode(&buf[..]).unwrap().is_complete);
+    assert!(super::Replay::recover(&buf[..]).unwrap().is_complete);
+}
+
+#[test]
+fn test_recover() {
+    let mut replay = synthetic_replay(1);
+    replay.wram_checksums.clear();
+    replay.input_pairs = synthetic_input_pairs(super::FRAME_INTERVAL * 2 + 50);
+
+    // Simulate a crash: take whatever made it to disk without ever finishing the writer.
+    let mut writer = write_replay(&replay);
+    let w = writer.encoder.as_mut().unwrap().get_mut();
+    w.seek(std::io::SeekFrom::Start(0)).unwrap();
+    let mut buf = vec![];
+    w.read_to_end(&mut buf).unwrap();
+    drop(writer);
+
+    let recovered = super::Replay::recover(&buf[..]).unwrap();
+    assert!(!recovered.is_complete);
+    assert!(recovered.input_pairs.len() >= (super::FRAME_INTERVAL * 2) as usize);
+    assert!(recovered.input_pairs.len() < replay.input_pairs.len());
+
+    // What was salvaged is written back out as a replay that decodes cleanly.
+    let mut want = replay.clone();
+    want.is_complete = false;
+    want.input_pairs.truncate(recovered.input_pairs.len());
+    assert_replays_eq(&super::Replay::decode(&encode_current(&recovered)[..]).unwrap(), &want);
+}
+
+#[test]
+fn test_anonymize() {
+    let key = super::signing::SigningKey::from_seed(&[1u8; 32]).unwrap();
+    let mut replay = synthetic_replay(1);
+    replay.metadata.local_side.as_mut().unwrap().signing_public_key = key.public_key();
+    replay.signatures.push(key.sign(&super::signing::digest(&replay)));
+
+    let mut anonymized = replay.clone();
+    anonymized.edit_metadata(&super::MetadataEdit::anonymize(1, true));
+    let decoded = super::Replay::decode(&encode_current(&anonymized)[..]).unwrap();
+
+    assert_eq!(decoded.metadata.link_code, "");
+    for (side, nickname) in [
+        (decoded.metadata.local_side.as_ref().unwrap(), "Player 2"),
+        (decoded.metadata.remote_side.as_ref().unwrap(), "Player 1"),
+    ] {
+        assert_eq!(side.nickname, nickname);
+        assert!(!side.reveal_setup);
+        assert!(side.signing_public_key.is_empty());
+    }
+    assert!(decoded.signatures.is_empty());
+
+    // Everything but the metadata and signatures is untouched.
+    let mut want = replay.clone();
+    want.metadata = decoded.metadata.clone();
+    want.signatures.clear();
+    assert_replays_eq(&decoded, &want);
+}
+
+#[test]
+fn test_container() {
+    let rounds = [
+        (2, crate::stepper::BattleOutcome::Loss),
+        (1, crate::stepper::BattleOutcome::Win),
+        (3, crate::stepper::BattleOutcome::Win),
+    ]
+    .map(|(number, outcome)| {
+        let mut replay = synthetic_replay(0);
+        replay.metadata.round = number;
+        replay.metadata.ts += number as u64;
+        super::container::Round::new(encode_current(&replay), Some(outcome)).unwrap()
+    });
+
+    let container = super::container::Container::new(rounds.to_vec()).unwrap();
+    let mut buf = vec![];
+    container.encode(&mut buf).unwrap();
+    assert!(super::container::is_container(&mut &buf[..]).unwrap());
+    assert!(!super::container::is_container(&mut &rounds[0].raw[..]).unwrap());
+
+    let decoded = super::container::Container::decode(&buf[..]).unwrap();
+    assert_eq!(decoded.metadata.round, 0);
+    assert_eq!(decoded.metadata.ts, synthetic_metadata().ts + 1);
+    assert!(decoded.outcome() == Some(crate::stepper::BattleOutcome::Win));
+
+    // Rounds come out in the order they were played, exactly as they went in.
+    for (round, want) in decoded.rounds.iter().zip([&rounds[1], &rounds[0], &rounds[2]]) {
+        assert_eq!(round.raw, want.raw);
+        assert!(round.outcome == want.outcome);
+    }
+
+    let mut decoded = decoded;
+    decoded.rounds[0].outcome = None;
+    assert!(decoded.outcome().is_none());
+
+    // A truncated container is an error, even when a length claims far more than is there.
+    let error_kind = |buf: &[u8]| super::container::Container::decode(buf).err().unwrap().kind();
+    assert_eq!(error_kind(&buf[..buf.len() - 1]), std::io::ErrorKind::UnexpectedEof);
+    let mut oversized = buf[..super::container::HEADER.len() + 1].to_vec();
+    oversized.write_u32::<byteorder::LittleEndian>(u32::MAX).unwrap();
+    assert_eq!(error_kind(&oversized), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_decode_v15() {
+    for local_player_index in [0, 1] {
+        let mut replay = synthetic_replay(local_player_index);
+        replay.wram_checksums.clear();
+        let buf = encode_v15(&replay);
+        assert_eq!(super::read_version(&mut &buf[..]).unwrap(), 0x15);
+        assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+    }
+}
+
+#[test]
+fn test_state_delta() {
+    // Noise doesn't compress, so storing the remote state in full would double the size.
+    let mut seed = 1u32;
+    let raw = (0..std::mem::size_of::<mgba::state::State>())
+        .map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as u8
+        })
+        .collect::<Vec<_>>();
+    let mut remote_raw = raw.clone();
+    for b in remote_raw.iter_mut().step_by(4096) {
+        *b = !*b;
+    }
+
+    let mut replay = synthetic_replay(0);
+    replay.wram_checksums.clear();
+    replay.local_state = mgba::state::State::from_slice(&raw);
+    replay.remote_state = mgba::state::State::from_slice(&remote_raw);
+
+    let buf = encode_current(&replay);
+    assert_replays_eq(&super::Replay::decode(&buf[..]).unwrap(), &replay);
+    assert!(buf.len() < encode_v15(&replay).len() * 2 / 3);
+}
+
+#[test]
+fn test_missing_dictionary() {
+    assert!(super::dictionary::Dictionary::new(vec![0u8; 64]).is_err());
+
+    let replay = synthetic_replay(0);
+    let mut buf = encode_current(&replay);
+    let dictionary_id_offset = super::HEADER.len() + 1 + 4 + 4 + replay.metadata.encode_to_vec().len();
+    assert_eq!(buf[dictionary_id_offset..dictionary_id_offset + 4], [0, 0, 0, 0]);
+    buf[dictionary_id_offset] = 1;
+    assert!(super::Replay::decode(&buf[..]).is_err());
+}
@@ -19,6 +19,8 @@ pub struct InnerState {
     phase: RoundPhase,
     on_round_ended: Option<Box<dyn FnOnce() + Send>>,
     error: Option<anyhow::Error>,
+    wram_checksums: std::collections::VecDeque<crate::replay::WramChecksum>,
+    wram_divergence: Option<u32>,
 }
 
 impl InnerState {
@@ -165,6 +167,50 @@ impl InnerState {
     pub fn increment_current_tick(&mut self) {
         self.current_tick += 1;
     }
+
+    pub fn set_wram_checksums(&mut self, wram_checksums: Vec<crate::replay::WramChecksum>) {
+        self.wram_checksums = wram_checksums.into_iter().collect();
+    }
+
+    /// Compares WRAM against the checksum recorded for the current tick, if there is one. Stops checking after the
+    /// first mismatch.
+    pub fn check_wram_checksum(&mut self, core: &mgba::core::CoreMutRef) {
+        let tick = self.current_tick;
+        while self.wram_checksums.front().map(|c| c.tick < tick).unwrap_or(false) {
+            self.wram_checksums.pop_front();
+        }
+        if self.wram_divergence.is_some() || self.wram_checksums.front().map(|c| c.tick != tick).unwrap_or(true) {
+            return;
+        }
+        let expected = self.wram_checksums.pop_front().unwrap();
+
+        let state = match core.save_state() {
+            Ok(state) => state,
+            Err(e) => {
+                self.set_anyhow_error(anyhow::anyhow!("save checksummed state: {}", e));
+                return;
+            }
+        };
+        if crate::replay::wram_checksum(&state) != expected.crc32 {
+            log::warn!("wram checksum mismatch at tick {}", expected.tick);
+            self.wram_divergence = Some(expected.tick);
+        }
+    }
+
+    /// The joyflags of the last input pair that was applied, for each player, P1 first.
+    pub fn last_joyflags(&self) -> Option<[u16; 2]> {
+        let ip = self.output_pairs.last()?;
+        Some(if self.local_player_index == 0 {
+            [ip.local.joyflags, ip.remote.joyflags]
+        } else {
+            [ip.remote.joyflags, ip.local.joyflags]
+        })
+    }
+
+    /// The first tick at which WRAM did not match what was recorded, if any.
+    pub fn wram_divergence(&self) -> Option<u32> {
+        self.wram_divergence
+    }
 }
 
 pub struct FastforwardResult {
@@ -174,7 +220,7 @@ pub struct FastforwardResult {
     pub output_pairs: Vec<crate::input::Pair<crate::input::Input, crate::input::Input>>,
 }
 
-#[derive(Clone, Copy, PartialEq, serde_repr::Serialize_repr)]
+#[derive(Clone, Copy, PartialEq, serde_repr::Serialize_repr, serde_repr::Deserialize_repr)]
 #[repr(i8)]
 pub enum BattleOutcome {
     Draw = -1,
@@ -220,7 +266,8 @@ impl State {
         });
         State(std::sync::Arc::new(parking_lot::Mutex::new(Some(InnerState {
             disable_bgm: false,
-            current_tick: 0,
+            // Replays that have been cut don't start at tick 0.
+            current_tick: local_packet.as_ref().map(|p| p.tick).unwrap_or(0),
             local_player_index,
             input_pairs: input_pairs
                 .iter()
@@ -261,6 +308,8 @@ impl State {
             phase: RoundPhase::InProgress,
             error: None,
             on_round_ended: Some(on_round_ended),
+            wram_checksums: std::collections::VecDeque::new(),
+            wram_divergence: None,
         }))))
     }
 
@@ -334,6 +383,8 @@ impl Fastforwarder {
             phase: RoundPhase::InProgress,
             error: None,
             on_round_ended: Some(Box::new(|| {})),
+            wram_checksums: std::collections::VecDeque::new(),
+            wram_divergence: None,
         });
 
         loop {
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests;
+
+/// What a game's hooks can read out of battle memory at the end of a tick. Hooks report the local player first.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Sample {
+    /// HP of each navi.
+    pub hp: [u16; 2],
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Hp {
+        player: u8,
+        hp: u16,
+    },
+    RoundEnd {
+        outcome: crate::stepper::BattleOutcome,
+        duration_ticks: u32,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+pub struct Entry {
+    pub tick: u32,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+/// Turns per-tick samples into a log of the things that changed.
+///
+/// HP is only logged when it changes, so the log still holds the HP of both navis at every tick.
+pub struct Recorder {
+    first_tick: u32,
+    last_sample: Option<Sample>,
+    entries: Vec<Entry>,
+}
+
+impl Sample {
+    /// Reorders a sample the hooks took into player index order.
+    pub fn by_player_index(mut self, local_player_index: u8) -> Self {
+        if local_player_index != 0 {
+            self.hp.swap(0, 1);
+        }
+        self
+    }
+}
+
+impl Recorder {
+    pub fn new(first_tick: u32) -> Self {
+        Self {
+            first_tick,
+            last_sample: None,
+            entries: vec![],
+        }
+    }
+
+    pub fn record(&mut self, tick: u32, sample: Sample) {
+        for (player, &hp) in sample.hp.iter().enumerate() {
+            if self.last_sample.map(|s| s.hp[player]) != Some(hp) {
+                self.entries.push(Entry {
+                    tick,
+                    event: Event::Hp {
+                        player: player as u8,
+                        hp,
+                    },
+                });
+            }
+        }
+
+        self.last_sample = Some(sample);
+    }
+
+    pub fn finish(mut self, result: crate::stepper::RoundResult) -> Vec<Entry> {
+        self.entries.push(Entry {
+            tick: result.tick,
+            event: Event::RoundEnd {
+                outcome: result.outcome,
+                duration_ticks: result.tick.saturating_sub(self.first_tick),
+            },
+        });
+        self.entries
+    }
+}
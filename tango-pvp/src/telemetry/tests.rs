@@ -0,0 +1,46 @@
+fn hp_sample(hp: [u16; 2]) -> super::Sample {
+    super::Sample { hp }
+}
+
+fn entry(tick: u32, event: super::Event) -> super::Entry {
+    super::Entry { tick, event }
+}
+
+#[test]
+fn test_recorder() {
+    let mut recorder = super::Recorder::new(10);
+    recorder.record(10, hp_sample([100, 100]));
+    recorder.record(11, hp_sample([100, 100]));
+    recorder.record(12, hp_sample([100, 80]));
+    recorder.record(13, hp_sample([100, 80]));
+    recorder.record(15, hp_sample([0, 80]));
+    let entries = recorder.finish(crate::stepper::RoundResult {
+        tick: 16,
+        outcome: crate::stepper::BattleOutcome::Loss,
+    });
+
+    // Unchanged HP isn't logged again.
+    assert!(
+        entries
+            == vec![
+                entry(10, super::Event::Hp { player: 0, hp: 100 }),
+                entry(10, super::Event::Hp { player: 1, hp: 100 }),
+                entry(12, super::Event::Hp { player: 1, hp: 80 }),
+                entry(15, super::Event::Hp { player: 0, hp: 0 }),
+                entry(
+                    16,
+                    super::Event::RoundEnd {
+                        outcome: crate::stepper::BattleOutcome::Loss,
+                        duration_ticks: 6,
+                    }
+                ),
+            ]
+    );
+}
+
+#[test]
+fn test_sample_by_player_index() {
+    let sample = hp_sample([100, 80]);
+    assert_eq!(sample.by_player_index(0), sample);
+    assert_eq!(sample.by_player_index(1), super::Sample { hp: [80, 100] });
+}
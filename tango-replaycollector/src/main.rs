@@ -0,0 +1,182 @@
+mod store;
+#[cfg(test)]
+mod tests;
+use envconfig::Envconfig;
+use routerify::ext::RequestExt;
+
+#[derive(Envconfig)]
+struct Config {
+    #[envconfig(from = "LISTEN_ADDR", default = "[::]:1985")]
+    listen_addr: String,
+
+    #[envconfig(from = "DATA_PATH", default = "data")]
+    data_path: String,
+
+    #[envconfig(from = "MAX_REPLAY_SIZE", default = "16777216")]
+    max_replay_size: usize,
+}
+
+struct State {
+    store: std::sync::Arc<store::Store>,
+    max_replay_size: usize,
+}
+
+fn text_response(status: hyper::StatusCode, body: impl Into<hyper::Body>) -> hyper::Response<hyper::Body> {
+    hyper::Response::builder().status(status).body(body.into()).unwrap()
+}
+
+async fn handle_healthcheck_request(
+    _request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, anyhow::Error> {
+    Ok(text_response(hyper::StatusCode::OK, "ok"))
+}
+
+async fn handle_upload_request(
+    mut request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, anyhow::Error> {
+    if request
+        .headers()
+        .get(hyper::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        != Some("application/x-tango-replay")
+    {
+        return Ok(text_response(
+            hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "expected application/x-tango-replay",
+        ));
+    }
+
+    let state = request.data::<State>().unwrap();
+    let store = state.store.clone();
+    let max_replay_size = state.max_replay_size;
+
+    let mut raw = vec![];
+    while let Some(chunk) = hyper::body::HttpBody::data(request.body_mut()).await {
+        raw.extend_from_slice(&chunk?);
+        if raw.len() > max_replay_size {
+            return Ok(text_response(hyper::StatusCode::PAYLOAD_TOO_LARGE, "replay too large"));
+        }
+    }
+
+    let (raw, entry) = match tokio::task::spawn_blocking(move || {
+        let replay = tango_pvp::replay::Replay::decode(&raw[..])?;
+        let id = <sha2::Sha256 as sha2::Digest>::digest(&raw)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        let entry = store::Entry::new(id, raw.len() as u64, &replay);
+        Ok::<_, std::io::Error>((raw, entry))
+    })
+    .await?
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return Ok(text_response(
+                hyper::StatusCode::BAD_REQUEST,
+                format!("invalid replay: {}", e),
+            ));
+        }
+    };
+
+    let id = entry.id.clone();
+    Ok(match store.insert(entry, &raw).await? {
+        store::Inserted::New => {
+            log::info!("stored replay {} ({} bytes)", id, raw.len());
+            text_response(hyper::StatusCode::CREATED, id)
+        }
+        store::Inserted::Duplicate => text_response(hyper::StatusCode::OK, id),
+    })
+}
+
+async fn handle_list_request(
+    request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, anyhow::Error> {
+    let mut query = store::Query::default();
+    for (k, v) in url::form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes()).into_owned() {
+        let parsed = match k.as_str() {
+            "nickname" => {
+                query.nickname = Some(v);
+                Ok(())
+            }
+            "link_code" => {
+                query.link_code = Some(v);
+                Ok(())
+            }
+            "rom_family" => {
+                query.rom_family = Some(v);
+                Ok(())
+            }
+            "since" => v.parse().map(|v| query.since = Some(v)),
+            "until" => v.parse().map(|v| query.until = Some(v)),
+            "offset" => v.parse().map(|v| query.offset = v),
+            "limit" => v.parse().map(|v| query.limit = Some(v)),
+            _ => Ok(()),
+        };
+        if parsed.is_err() {
+            return Ok(text_response(
+                hyper::StatusCode::BAD_REQUEST,
+                format!("invalid value for {}", k),
+            ));
+        }
+    }
+
+    let entries = request.data::<State>().unwrap().store.query(&query).await;
+    Ok(hyper::Response::builder()
+        .status(hyper::StatusCode::OK)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(hyper::Body::from(serde_json::to_vec(&entries)?))
+        .unwrap())
+}
+
+async fn handle_download_request(
+    request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, anyhow::Error> {
+    let id = request.param("id").unwrap();
+    if id.len() != 64 || !id.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()) {
+        return Ok(text_response(hyper::StatusCode::BAD_REQUEST, "invalid replay id"));
+    }
+
+    Ok(match request.data::<State>().unwrap().store.read(id).await? {
+        Some(raw) => hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "application/x-tango-replay")
+            .header(
+                hyper::header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.tangoreplay\"", id),
+            )
+            .body(hyper::Body::from(raw))
+            .unwrap(),
+        None => text_response(hyper::StatusCode::NOT_FOUND, "no such replay"),
+    })
+}
+
+fn router(store: store::Store, max_replay_size: usize) -> routerify::Router<hyper::Body, anyhow::Error> {
+    routerify::Router::builder()
+        .data(State {
+            store: std::sync::Arc::new(store),
+            max_replay_size,
+        })
+        .post("/", handle_upload_request)
+        .get("/replays", handle_list_request)
+        .get("/replays/:id", handle_download_request)
+        .get("/ok", handle_healthcheck_request)
+        .build()
+        .unwrap()
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::Builder::from_default_env()
+        .filter(Some("tango_replaycollector"), log::LevelFilter::Info)
+        .init();
+    log::info!("welcome to {} {}!", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    let config = Config::init_from_env().unwrap();
+    let addr = config.listen_addr.parse()?;
+
+    let store = store::Store::open(std::path::Path::new(&config.data_path))?;
+    let router = router(store, config.max_replay_size);
+
+    let service = routerify::RouterService::new(router).unwrap();
+    hyper::Server::bind(&addr).serve(service).await?;
+    Ok(())
+}
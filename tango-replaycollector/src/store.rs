@@ -0,0 +1,227 @@
+use tokio::io::AsyncWriteExt;
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Side {
+    pub nickname: String,
+    pub rom_family: Option<String>,
+    pub rom_variant: Option<u32>,
+    pub patch_name: Option<String>,
+    pub patch_version: Option<String>,
+}
+
+impl Side {
+    fn from_metadata(side: &tango_pvp::replay::metadata::Side) -> Self {
+        let game_info = side.game_info.as_ref();
+        let patch = game_info.and_then(|gi| gi.patch.as_ref());
+        Self {
+            nickname: side.nickname.clone(),
+            rom_family: game_info.map(|gi| gi.rom_family.clone()),
+            rom_variant: game_info.map(|gi| gi.rom_variant),
+            patch_name: patch.map(|p| p.name.clone()),
+            patch_version: patch.map(|p| p.version.clone()),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Entry {
+    /// Hex SHA-256 of the uploaded replay, which is also its file name.
+    pub id: String,
+    pub received_at: u64,
+    pub size: u64,
+    pub ts: u64,
+    pub link_code: String,
+    pub round: u32,
+    pub match_type: u32,
+    pub match_subtype: u32,
+    pub is_complete: bool,
+    /// Valid signatures made with a key one of the sides presented during the handshake.
+    pub valid_signatures: usize,
+    pub local_side: Option<Side>,
+    pub remote_side: Option<Side>,
+}
+
+impl Entry {
+    pub fn new(id: String, size: u64, replay: &tango_pvp::replay::Replay) -> Self {
+        Self {
+            id,
+            received_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            size,
+            ts: replay.metadata.ts,
+            link_code: replay.metadata.link_code.clone(),
+            round: replay.metadata.round,
+            match_type: replay.metadata.match_type,
+            match_subtype: replay.metadata.match_subtype,
+            is_complete: replay.is_complete,
+            valid_signatures: tango_pvp::replay::signing::count_bound_signatures(replay)
+                .into_iter()
+                .flatten()
+                .sum(),
+            local_side: replay.metadata.local_side.as_ref().map(Side::from_metadata),
+            remote_side: replay.metadata.remote_side.as_ref().map(Side::from_metadata),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Query {
+    /// Matches either side's nickname, ignoring case.
+    pub nickname: Option<String>,
+    pub link_code: Option<String>,
+    /// Matches either side's ROM family.
+    pub rom_family: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl Query {
+    fn matches(&self, entry: &Entry) -> bool {
+        let sides = [entry.local_side.as_ref(), entry.remote_side.as_ref()];
+
+        if let Some(nickname) = self.nickname.as_ref() {
+            let nickname = nickname.to_lowercase();
+            if !sides
+                .iter()
+                .flatten()
+                .any(|side| side.nickname.to_lowercase() == nickname)
+            {
+                return false;
+            }
+        }
+
+        if let Some(link_code) = self.link_code.as_ref() {
+            if entry.link_code != *link_code {
+                return false;
+            }
+        }
+
+        if let Some(rom_family) = self.rom_family.as_ref() {
+            if !sides
+                .iter()
+                .flatten()
+                .any(|side| side.rom_family.as_ref() == Some(rom_family))
+            {
+                return false;
+            }
+        }
+
+        if self.since.is_some_and(|since| entry.ts < since) || self.until.is_some_and(|until| entry.ts >= until) {
+            return false;
+        }
+
+        true
+    }
+}
+
+pub enum Inserted {
+    New,
+    Duplicate,
+}
+
+struct Inner {
+    entries: Vec<Entry>,
+    ids: std::collections::HashSet<String>,
+}
+
+/// Replays on disk, named by content hash, along with an append-only JSON lines index of their metadata.
+pub struct Store {
+    path: std::path::PathBuf,
+    inner: tokio::sync::RwLock<Inner>,
+}
+
+const INDEX_FILENAME: &str = "index.jsonl";
+
+impl Store {
+    pub fn open(path: &std::path::Path) -> Result<Self, anyhow::Error> {
+        std::fs::create_dir_all(path.join("replays"))?;
+
+        let mut entries = vec![];
+        let mut ids = std::collections::HashSet::new();
+        match std::fs::read_to_string(path.join(INDEX_FILENAME)) {
+            Ok(index) => {
+                for (i, line) in index.lines().enumerate() {
+                    let entry = match serde_json::from_str::<Entry>(line) {
+                        Ok(entry) => entry,
+                        Err(e) => {
+                            log::warn!("skipping bad index line {}: {}", i + 1, e);
+                            continue;
+                        }
+                    };
+                    if ids.insert(entry.id.clone()) {
+                        entries.push(entry);
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e.into());
+            }
+        }
+        log::info!("loaded {} replays from {}", entries.len(), path.display());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            inner: tokio::sync::RwLock::new(Inner { entries, ids }),
+        })
+    }
+
+    fn replay_path(&self, id: &str) -> std::path::PathBuf {
+        self.path.join("replays").join(format!("{}.tangoreplay", id))
+    }
+
+    pub async fn insert(&self, entry: Entry, raw: &[u8]) -> Result<Inserted, anyhow::Error> {
+        let mut inner = self.inner.write().await;
+        if inner.ids.contains(&entry.id) {
+            return Ok(Inserted::Duplicate);
+        }
+
+        let replay_path = self.replay_path(&entry.id);
+        let tmp_path = replay_path.with_extension("tmp");
+        tokio::fs::write(&tmp_path, raw).await?;
+        tokio::fs::rename(&tmp_path, &replay_path).await?;
+
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut index = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.join(INDEX_FILENAME))
+            .await?;
+        index.write_all(&line).await?;
+        // tokio finishes file writes in the background, so make sure the line is down before the entry is visible.
+        index.flush().await?;
+
+        inner.ids.insert(entry.id.clone());
+        inner.entries.push(entry);
+        Ok(Inserted::New)
+    }
+
+    /// Entries matching the query, most recently played first.
+    pub async fn query(&self, query: &Query) -> Vec<Entry> {
+        let inner = self.inner.read().await;
+        let mut entries = inner
+            .entries
+            .iter()
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.ts));
+        entries
+            .into_iter()
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+
+    pub async fn read(&self, id: &str) -> Result<Option<Vec<u8>>, anyhow::Error> {
+        if !self.inner.read().await.ids.contains(id) {
+            return Ok(None);
+        }
+        Ok(Some(tokio::fs::read(self.replay_path(id)).await?))
+    }
+}
@@ -0,0 +1,162 @@
+use hyper::service::Service;
+use std::io::Read;
+use std::io::Seek;
+
+fn fixture_replay(ts: u64, remote_nickname: &str) -> tango_pvp::replay::Replay {
+    let state = mgba::state::State::from_slice(&vec![0u8; std::mem::size_of::<mgba::state::State>()]);
+    let side = |nickname: &str| tango_pvp::replay::metadata::Side {
+        nickname: nickname.to_string(),
+        game_info: Some(tango_pvp::replay::metadata::GameInfo {
+            rom_family: "bn6".to_string(),
+            rom_variant: 0,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    tango_pvp::replay::Replay {
+        is_complete: true,
+        metadata: tango_pvp::replay::Metadata {
+            ts,
+            link_code: "fixture".to_string(),
+            local_side: Some(side("local")),
+            remote_side: Some(side(remote_nickname)),
+            round: 1,
+            ..Default::default()
+        },
+        local_player_index: 0,
+        local_state: state.clone(),
+        remote_state: state,
+        input_pairs: (0..10)
+            .map(|tick| {
+                let input = tango_pvp::input::Input {
+                    local_tick: tick,
+                    remote_tick: tick,
+                    joyflags: 0,
+                    packet: vec![0; 4],
+                    dt: std::time::Duration::from_millis(16),
+                };
+                tango_pvp::input::Pair {
+                    local: input.clone(),
+                    remote: input,
+                }
+            })
+            .collect(),
+        wram_checksums: vec![],
+        signatures: vec![],
+    }
+}
+
+fn encode(replay: &tango_pvp::replay::Replay) -> Vec<u8> {
+    let mut w = replay.encode(std::io::Cursor::new(vec![])).unwrap();
+    w.seek(std::io::SeekFrom::Start(0)).unwrap();
+    let mut buf = vec![];
+    w.read_to_end(&mut buf).unwrap();
+    buf
+}
+
+async fn request(
+    service: &mut routerify::RequestService<hyper::Body, anyhow::Error>,
+    request: hyper::Request<hyper::Body>,
+) -> (hyper::StatusCode, Vec<u8>) {
+    let response = service.call(request).await.unwrap();
+    let status = response.status();
+    (
+        status,
+        hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec(),
+    )
+}
+
+fn upload(raw: &[u8]) -> hyper::Request<hyper::Body> {
+    hyper::Request::post("/")
+        .header(hyper::header::CONTENT_TYPE, "application/x-tango-replay")
+        .body(hyper::Body::from(raw.to_vec()))
+        .unwrap()
+}
+
+fn get(uri: &str) -> hyper::Request<hyper::Body> {
+    hyper::Request::get(uri).body(hyper::Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_router() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = super::store::Store::open(dir.path()).unwrap();
+    let mut service = routerify::RequestServiceBuilder::new(super::router(store, 1 << 24))
+        .unwrap()
+        .build("127.0.0.1:0".parse().unwrap());
+
+    let key = tango_pvp::replay::signing::SigningKey::from_seed(&[1u8; 32]).unwrap();
+    let stranger = tango_pvp::replay::signing::SigningKey::from_seed(&[2u8; 32]).unwrap();
+    let mut signed = fixture_replay(1_700_000_000_000, "alice");
+    signed.metadata.local_side.as_mut().unwrap().signing_public_key = key.public_key();
+    let digest = tango_pvp::replay::signing::digest(&signed);
+    // Only the signature from the key the local side presented counts.
+    signed.signatures = vec![key.sign(&digest), stranger.sign(&digest)];
+    let signed = encode(&signed);
+    let unsigned = encode(&fixture_replay(1_700_000_001_000, "bob"));
+
+    let (status, signed_id) = request(&mut service, upload(&signed)).await;
+    assert_eq!(status, hyper::StatusCode::CREATED);
+    let signed_id = String::from_utf8(signed_id).unwrap();
+    let (status, unsigned_id) = request(&mut service, upload(&unsigned)).await;
+    assert_eq!(status, hyper::StatusCode::CREATED);
+    let unsigned_id = String::from_utf8(unsigned_id).unwrap();
+
+    // Uploading the same replay again is accepted, but not stored twice.
+    let (status, id) = request(&mut service, upload(&signed)).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    assert_eq!(String::from_utf8(id).unwrap(), signed_id);
+
+    let (status, _) = request(
+        &mut service,
+        hyper::Request::post("/")
+            .header(hyper::header::CONTENT_TYPE, "application/octet-stream")
+            .body(hyper::Body::from(signed.clone()))
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let (status, _) = request(&mut service, upload(b"not a replay")).await;
+    assert_eq!(status, hyper::StatusCode::BAD_REQUEST);
+
+    // Most recently played first.
+    let (status, body) = request(&mut service, get("/replays")).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    let entries = serde_json::from_slice::<Vec<super::store::Entry>>(&body).unwrap();
+    assert_eq!(
+        entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec![unsigned_id.as_str(), signed_id.as_str()]
+    );
+    assert_eq!(entries[0].valid_signatures, 0);
+    assert_eq!(entries[1].valid_signatures, 1);
+
+    let (status, body) = request(&mut service, get("/replays?nickname=ALICE")).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    let entries = serde_json::from_slice::<Vec<super::store::Entry>>(&body).unwrap();
+    assert_eq!(
+        entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec![signed_id.as_str()]
+    );
+
+    let (status, body) = request(&mut service, get("/replays?since=1700000000500&rom_family=bn6")).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    let entries = serde_json::from_slice::<Vec<super::store::Entry>>(&body).unwrap();
+    assert_eq!(
+        entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec![unsigned_id.as_str()]
+    );
+
+    let (status, _) = request(&mut service, get("/replays?limit=x")).await;
+    assert_eq!(status, hyper::StatusCode::BAD_REQUEST);
+
+    let (status, body) = request(&mut service, get(&format!("/replays/{}", signed_id))).await;
+    assert_eq!(status, hyper::StatusCode::OK);
+    assert_eq!(body, signed);
+
+    let (status, _) = request(&mut service, get(&format!("/replays/{}", "0".repeat(64)))).await;
+    assert_eq!(status, hyper::StatusCode::NOT_FOUND);
+
+    let (status, _) = request(&mut service, get("/replays/nope")).await;
+    assert_eq!(status, hyper::StatusCode::BAD_REQUEST);
+}
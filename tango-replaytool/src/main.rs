@@ -1,19 +1,62 @@
 use clap::Parser;
 use std::io::Write;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(clap::Parser)]
 struct Args {
     /// Path to replay.
     path: std::path::PathBuf,
 
-    /// Invert the replay?
-    #[clap(default_value = "true", long)]
+    /// Look at the replay from the other side? Commands that check the replay against what was recorded ignore this.
+    #[clap(default_value_t = true, long, action = clap::ArgAction::Set)]
     invert: bool,
 
+    /// zstd dictionary to read replays with. Upgrading also compresses with it.
+    #[clap(long)]
+    dictionary: Option<std::path::PathBuf>,
+
     #[command(subcommand)]
     command: Command,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum ExportFormat {
+    /// Encode with ffmpeg.
+    Ffmpeg,
+
+    /// Raw Y4M video with WAV audio next to it.
+    Y4m,
+
+    /// Numbered PNG frames and WAV audio in a directory.
+    Png,
+
+    /// Animated GIF clip.
+    Gif,
+
+    /// Animated PNG clip.
+    Apng,
+}
+
+impl From<ExportFormat> for tango_pvp::replay::export::Format {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Ffmpeg => tango_pvp::replay::export::Format::Ffmpeg,
+            ExportFormat::Y4m => tango_pvp::replay::export::Format::Y4mWav,
+            ExportFormat::Png => tango_pvp::replay::export::Format::PngSequence,
+            ExportFormat::Gif => tango_pvp::replay::export::Format::Gif,
+            ExportFormat::Apng => tango_pvp::replay::export::Format::Apng,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+pub enum StatsFormat {
+    Csv,
+    Json,
+}
+
 #[derive(clap::Subcommand)]
 pub enum Command {
     /// Copy the replay.
@@ -30,6 +73,21 @@ pub enum Command {
 
     /// Export to video.
     Export {
+        #[clap(value_enum, default_value = "ffmpeg", long)]
+        format: ExportFormat,
+
+        /// Scale factor for formats other than ffmpeg.
+        #[clap(default_value = "1", long)]
+        scale: usize,
+
+        /// First tick to export.
+        #[clap(long)]
+        from_tick: Option<u32>,
+
+        /// Tick to stop exporting at.
+        #[clap(long)]
+        to_tick: Option<u32>,
+
         #[clap(default_value = "ffmpeg", long)]
         ffmpeg: std::path::PathBuf,
 
@@ -48,6 +106,10 @@ pub enum Command {
         #[clap(default_value = "false", long)]
         disable_bgm: bool,
 
+        /// Draw both players' inputs over the video.
+        #[clap(default_value = "false", long)]
+        input_display: bool,
+
         local_rom_path: std::path::PathBuf,
 
         #[clap(default_value = "None", long)]
@@ -58,41 +120,267 @@ pub enum Command {
 
     /// Evaluate the result of a replay.
     Eval { rom_path: std::path::PathBuf },
+
+    /// Find the first tick where two replays of the same round disagree.
+    Diff {
+        /// The other replay. It is mirrored if it was recorded from the other side.
+        other_path: std::path::PathBuf,
+
+        /// ROM to also compare emulated WRAM with.
+        #[clap(long)]
+        rom_path: Option<std::path::PathBuf>,
+    },
+
+    /// Cut out a range of ticks into a new, self-contained replay.
+    Cut {
+        /// First tick to keep.
+        #[clap(long)]
+        from: u32,
+
+        /// Tick to stop at. If not given, everything until the end of the round is kept.
+        #[clap(long)]
+        to: Option<u32>,
+
+        /// ROM for the side the replay was recorded from.
+        local_rom_path: std::path::PathBuf,
+
+        /// ROM for the remote side, if it differs from the local one.
+        #[clap(long)]
+        remote_rom_path: Option<std::path::PathBuf>,
+
+        output_path: std::path::PathBuf,
+    },
+
+    /// Play the replay up to a tick and write the emulator state there as an mGBA savestate.
+    Savestate {
+        /// Tick to stop at. The state is from the start of this tick.
+        #[clap(long)]
+        tick: u32,
+
+        /// Unpatched ROM for the replay's side.
+        rom_path: std::path::PathBuf,
+
+        /// Directory to find patches in, if the side played with one.
+        #[clap(long)]
+        patches_path: Option<std::path::PathBuf>,
+
+        output_path: std::path::PathBuf,
+    },
+
+    /// Check that each player signed the round once, with the key they presented when the match was set up.
+    Verify,
+
+    /// Play the replay back and print a log of battle events, one JSON object per line.
+    Telemetry { rom_path: std::path::PathBuf },
+
+    /// Evaluate every replay in a directory (given as the replay path) and print a row per round.
+    BatchEval {
+        /// Directory to find ROMs in.
+        roms_path: std::path::PathBuf,
+
+        /// Directory to find patches in.
+        patches_path: std::path::PathBuf,
+
+        #[clap(value_enum, default_value = "csv", long)]
+        format: StatsFormat,
+
+        /// Number of worker threads. Defaults to the number of CPUs.
+        #[clap(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: Option<usize>,
+    },
+
+    /// Rewrite a replay from an older format version in the current one.
+    Upgrade {
+        /// Where to write the upgraded replay. If not given, the replay is upgraded in place, which isn't allowed
+        /// with --dictionary.
+        output_path: Option<std::path::PathBuf>,
+    },
+
+    /// Write a copy with the nicknames replaced by player numbers and the link code removed.
+    Anonymize {
+        /// Also mark both sides' setups as not revealed.
+        #[clap(long)]
+        hide_setup: bool,
+
+        output_path: std::path::PathBuf,
+    },
+
+    /// Write a copy with some of the metadata changed.
+    SetMetadata {
+        #[clap(long)]
+        local_nickname: Option<String>,
+
+        #[clap(long)]
+        remote_nickname: Option<String>,
+
+        #[clap(long)]
+        link_code: Option<String>,
+
+        /// Whether both sides' setups are marked as revealed.
+        #[clap(long)]
+        reveal_setup: Option<bool>,
+
+        output_path: std::path::PathBuf,
+    },
+
+    /// Train a zstd dictionary on the states of every replay in a directory (given as the replay path).
+    TrainDictionary {
+        /// Largest size the dictionary may be, in bytes.
+        #[clap(default_value = "112640", long)]
+        max_size: usize,
+
+        output_path: std::path::PathBuf,
+    },
+
+    /// Salvage what can be read from a damaged or unfinished replay and rewrite it as a valid one.
+    Recover {
+        /// Where to write the recovered replay. If not given, the replay is recovered in place.
+        output_path: Option<std::path::PathBuf>,
+    },
+
+    /// Write each round of a match file (given as the replay path) out as its own replay.
+    Split { output_path: std::path::PathBuf },
+
+    /// Collect the replay, along with any other replays or match files, into a single match file.
+    Merge {
+        output_path: std::path::PathBuf,
+
+        /// More replays or match files to add.
+        other_paths: Vec<std::path::PathBuf>,
+    },
+}
+
+impl Command {
+    /// Whether the command sees the replay from the other side when asked to. WRAM checksums are only recorded for
+    /// the recording side, so commands that check playback against them or write a new replay carrying them always see
+    /// the replay as recorded.
+    fn follows_invert(&self) -> bool {
+        !matches!(self, Command::Eval { .. } | Command::Cut { .. })
+    }
+}
+
+/// Reads the replay for a command that works on a single replay.
+fn read_replay(
+    args: &Args,
+    dictionary: Option<&tango_pvp::replay::dictionary::Dictionary>,
+) -> Result<tango_pvp::replay::Replay, anyhow::Error> {
+    let mut replay = tango_pvp::replay::Replay::decode_with_dictionary(std::fs::File::open(&args.path)?, dictionary)?;
+    if args.invert && args.command.follows_invert() {
+        replay = replay.into_remote();
+    }
+    Ok(replay)
 }
 
 #[tokio::main]
 pub async fn main() -> Result<(), anyhow::Error> {
     let args = Args::parse();
 
-    let mut f = std::fs::File::open(&args.path)?;
-    let mut replay = tango_pvp::replay::Replay::decode(&mut f)?;
+    let dictionary = args
+        .dictionary
+        .as_ref()
+        .map(|path| tango_pvp::replay::dictionary::Dictionary::new(std::fs::read(path)?))
+        .transpose()?
+        .map(std::sync::Arc::new);
 
-    if args.invert {
-        replay = replay.into_remote();
+    // Upgrading must keep the replay exactly as it was recorded, so it doesn't go through inversion.
+    if let Command::Upgrade { output_path } = args.command {
+        return cmd_upgrade(args.path, output_path, dictionary).await;
     }
 
+    if let Command::TrainDictionary { max_size, output_path } = args.command {
+        return cmd_train_dictionary(args.path, max_size, output_path).await;
+    }
+
+    if let Command::Recover { output_path } = args.command {
+        return cmd_recover(args.path, output_path).await;
+    }
+
+    // Match files hold rounds as they were written, so they don't go through inversion either.
+    if let Command::Split { output_path } = args.command {
+        return cmd_split(args.path, output_path).await;
+    }
+
+    if let Command::Merge {
+        output_path,
+        other_paths,
+    } = args.command
+    {
+        return cmd_merge(args.path, other_paths, output_path).await;
+    }
+
+    // Metadata edits apply to the replay as recorded, so these don't go through inversion either.
+    match args.command {
+        Command::Anonymize {
+            hide_setup,
+            output_path,
+        } => {
+            let replay = tango_pvp::replay::Replay::decode(std::fs::File::open(&args.path)?)?;
+            let edit = tango_pvp::replay::MetadataEdit::anonymize(replay.local_player_index, hide_setup);
+            return cmd_edit_metadata(replay, edit, output_path).await;
+        }
+        Command::SetMetadata {
+            local_nickname,
+            remote_nickname,
+            link_code,
+            reveal_setup,
+            output_path,
+        } => {
+            let replay = tango_pvp::replay::Replay::decode(std::fs::File::open(&args.path)?)?;
+            let edit = tango_pvp::replay::MetadataEdit {
+                local_nickname,
+                remote_nickname,
+                link_code,
+                reveal_setup,
+            };
+            return cmd_edit_metadata(replay, edit, output_path).await;
+        }
+        _ => {}
+    }
+
+    if let Command::BatchEval {
+        roms_path,
+        patches_path,
+        format,
+        jobs,
+    } = args.command
+    {
+        return cmd_batch_eval(args.path, roms_path, patches_path, format, jobs).await;
+    }
+
+    let replay = read_replay(&args, dictionary.as_deref())?;
+
     match args.command {
         Command::Copy { output_path } => cmd_copy(replay, output_path).await,
         Command::Metadata => cmd_metadata(replay).await,
         Command::Wram => cmd_wram(replay).await,
         Command::Text => cmd_text(replay).await,
         Command::Export {
+            format,
+            scale,
+            from_tick,
+            to_tick,
             ffmpeg,
             ffmpeg_audio_flags,
             ffmpeg_video_flags,
             ffmpeg_mux_flags,
             disable_bgm,
+            input_display,
             local_rom_path,
             remote_rom_path,
             output_path,
         } => {
             cmd_export(
                 replay,
+                format.into(),
+                scale,
+                from_tick,
+                to_tick,
                 ffmpeg,
                 ffmpeg_audio_flags,
                 ffmpeg_video_flags,
                 ffmpeg_mux_flags,
                 disable_bgm,
+                input_display,
                 local_rom_path,
                 remote_rom_path,
                 output_path,
@@ -100,22 +388,193 @@ pub async fn main() -> Result<(), anyhow::Error> {
             .await
         }
         Command::Eval { rom_path } => cmd_eval(replay, rom_path).await,
+        Command::Diff { other_path, rom_path } => cmd_diff(replay, other_path, rom_path).await,
+        Command::Cut {
+            from,
+            to,
+            local_rom_path,
+            remote_rom_path,
+            output_path,
+        } => cmd_cut(replay, from, to, local_rom_path, remote_rom_path, output_path).await,
+        Command::Savestate {
+            tick,
+            rom_path,
+            patches_path,
+            output_path,
+        } => cmd_savestate(replay, tick, rom_path, patches_path, output_path).await,
+        Command::Verify => cmd_verify(replay).await,
+        Command::Telemetry { rom_path } => cmd_telemetry(replay, rom_path).await,
+        Command::Upgrade { .. }
+        | Command::TrainDictionary { .. }
+        | Command::Recover { .. }
+        | Command::Split { .. }
+        | Command::Merge { .. }
+        | Command::Anonymize { .. }
+        | Command::SetMetadata { .. }
+        | Command::BatchEval { .. } => unreachable!(),
     }
 }
 
 async fn cmd_copy(replay: tango_pvp::replay::Replay, output_path: std::path::PathBuf) -> Result<(), anyhow::Error> {
-    let mut writer = tango_pvp::replay::Writer::new(
-        Box::new(std::fs::File::create(output_path)?),
-        replay.metadata,
-        replay.local_player_index,
-        replay.input_pairs.first().map(|ip| ip.local.packet.len()).unwrap_or(0) as u8,
-    )?;
-    writer.write_state(&replay.local_state)?;
-    writer.write_state(&replay.remote_state)?;
-    for ip in replay.input_pairs {
-        writer.write_input(replay.local_player_index, &ip)?;
+    replay.encode(std::fs::File::create(output_path)?)?;
+    Ok(())
+}
+
+async fn cmd_upgrade(
+    path: std::path::PathBuf,
+    output_path: Option<std::path::PathBuf>,
+    dictionary: Option<std::sync::Arc<tango_pvp::replay::dictionary::Dictionary>>,
+) -> Result<(), anyhow::Error> {
+    // Tango can't read a replay compressed with a dictionary, so never replace the only copy with one.
+    let in_place = match output_path.as_ref() {
+        Some(output_path) => {
+            output_path == &path
+                || matches!(
+                    (std::fs::canonicalize(output_path), std::fs::canonicalize(&path)),
+                    (Ok(a), Ok(b)) if a == b
+                )
+        }
+        None => true,
+    };
+    if in_place && dictionary.is_some() {
+        return Err(anyhow::anyhow!(
+            "refusing to compress {} with a dictionary in place, give an output path",
+            path.display()
+        ));
+    }
+
+    let version = tango_pvp::replay::read_version(&mut std::fs::File::open(&path)?)?;
+    if version == tango_pvp::replay::VERSION && output_path.is_none() && dictionary.is_none() {
+        eprintln!("already at version {:02x}", version);
+        return Ok(());
+    }
+
+    let replay = tango_pvp::replay::Replay::decode_with_dictionary(std::fs::File::open(&path)?, dictionary.as_deref())?;
+
+    // When upgrading in place, write to a temporary file first so a failure doesn't destroy the original.
+    let (write_path, rename_to) = match output_path {
+        Some(output_path) => (output_path, None),
+        None => (path.with_extension("tangoreplay.tmp"), Some(path)),
+    };
+    replay.encode_with_dictionary(std::fs::File::create(&write_path)?, dictionary)?;
+    if let Some(rename_to) = rename_to {
+        std::fs::rename(&write_path, &rename_to)?;
+    }
+
+    eprintln!(
+        "upgraded from version {:02x} to {:02x}",
+        version,
+        tango_pvp::replay::VERSION
+    );
+    Ok(())
+}
+
+async fn cmd_train_dictionary(
+    path: std::path::PathBuf,
+    max_size: usize,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let mut paths = vec![];
+    find_replays(&path, &mut paths)?;
+
+    let mut replays = vec![];
+    for replay_path in paths {
+        match std::fs::File::open(&replay_path)
+            .and_then(|f| tango_pvp::replay::Replay::decode(std::io::BufReader::new(f)))
+        {
+            Ok(replay) => replays.push(replay),
+            Err(e) => {
+                eprintln!("{}: {}", replay_path.display(), e);
+            }
+        }
+    }
+
+    let dictionary = tango_pvp::replay::dictionary::Dictionary::train(&replays, max_size)?;
+    std::fs::write(&output_path, dictionary.as_bytes())?;
+    eprintln!(
+        "trained dictionary {:08x} on {} replays",
+        dictionary.id(),
+        replays.len()
+    );
+    Ok(())
+}
+
+async fn cmd_recover(path: std::path::PathBuf, output_path: Option<std::path::PathBuf>) -> Result<(), anyhow::Error> {
+    let replay = tango_pvp::replay::Replay::recover(std::fs::File::open(&path)?)?;
+
+    let (write_path, rename_to) = match output_path {
+        Some(output_path) => (output_path, None),
+        None => (path.with_extension("tangoreplay.tmp"), Some(path)),
+    };
+    replay.encode(std::fs::File::create(&write_path)?)?;
+    if let Some(rename_to) = rename_to {
+        std::fs::rename(&write_path, &rename_to)?;
     }
-    writer.finish()?;
+
+    eprintln!(
+        "recovered {} input pairs and {} signatures, round {}",
+        replay.input_pairs.len(),
+        replay.signatures.len(),
+        if replay.is_complete { "complete" } else { "incomplete" }
+    );
+    Ok(())
+}
+
+async fn cmd_split(path: std::path::PathBuf, output_path: std::path::PathBuf) -> Result<(), anyhow::Error> {
+    let container =
+        tango_pvp::replay::container::Container::decode(std::io::BufReader::new(std::fs::File::open(&path)?))?;
+
+    std::fs::create_dir_all(&output_path)?;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    for (i, round) in container.rounds.iter().enumerate() {
+        let round_path = output_path.join(format!("{}-round{}.tangoreplay", stem, i + 1));
+        std::fs::write(&round_path, &round.raw)?;
+        eprintln!("wrote {}", round_path.display());
+    }
+    Ok(())
+}
+
+async fn cmd_merge(
+    path: std::path::PathBuf,
+    other_paths: Vec<std::path::PathBuf>,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let mut container = tango_pvp::replay::container::Container::new(vec![])?;
+    for path in std::iter::once(path).chain(other_paths) {
+        let raw = std::fs::read(&path)?;
+        if tango_pvp::replay::container::is_container(&mut &raw[..])? {
+            for round in tango_pvp::replay::container::Container::decode(&raw[..])?.rounds {
+                container.push(round)?;
+            }
+        } else {
+            container.push(tango_pvp::replay::container::Round::new(raw, None)?)?;
+        }
+    }
+    container.encode(std::io::BufWriter::new(std::fs::File::create(&output_path)?))?;
+
+    eprintln!(
+        "merged {} rounds, result: {}",
+        container.rounds.len(),
+        match container.outcome() {
+            Some(tango_pvp::stepper::BattleOutcome::Win) => "win",
+            Some(tango_pvp::stepper::BattleOutcome::Loss) => "loss",
+            Some(tango_pvp::stepper::BattleOutcome::Draw) => "draw",
+            None => "unknown",
+        }
+    );
+    Ok(())
+}
+
+async fn cmd_edit_metadata(
+    mut replay: tango_pvp::replay::Replay,
+    edit: tango_pvp::replay::MetadataEdit,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    if !replay.signatures.is_empty() {
+        eprintln!("dropping {} signatures", replay.signatures.len());
+    }
+    replay.edit_metadata(&edit);
+    replay.encode(std::fs::File::create(output_path)?)?;
     Ok(())
 }
 
@@ -150,11 +609,16 @@ async fn cmd_wram(replay: tango_pvp::replay::Replay) -> Result<(), anyhow::Error
 
 async fn cmd_export(
     replay: tango_pvp::replay::Replay,
+    format: tango_pvp::replay::export::Format,
+    scale: usize,
+    from_tick: Option<u32>,
+    to_tick: Option<u32>,
     ffmpeg: std::path::PathBuf,
     ffmpeg_audio_flags: String,
     ffmpeg_video_flags: String,
     ffmpeg_mux_flags: String,
     disable_bgm: bool,
+    input_display: bool,
     local_rom_path: std::path::PathBuf,
     remote_rom_path: Option<std::path::PathBuf>,
     output_path: std::path::PathBuf,
@@ -166,11 +630,19 @@ async fn cmd_export(
     };
 
     let settings = tango_pvp::replay::export::Settings {
+        format,
         ffmpeg: Some(ffmpeg),
         ffmpeg_audio_flags,
         ffmpeg_video_flags,
         ffmpeg_mux_flags,
+        scale,
+        tick_range: if from_tick.is_some() || to_tick.is_some() {
+            Some(from_tick.unwrap_or(0)..to_tick.unwrap_or(u32::MAX))
+        } else {
+            None
+        },
         disable_bgm,
+        input_display,
     };
 
     let local_rom = std::fs::read(&local_rom_path)?;
@@ -251,8 +723,642 @@ async fn cmd_eval(replay: tango_pvp::replay::Replay, rom_path: std::path::PathBu
         ));
     }
 
-    let (result, _) = tango_pvp::eval::eval(&replay, &rom, hooks, Vec::new).await?;
+    if tango_pvp::replay::rom_matches(game_info, &rom) == Some(false) {
+        eprintln!("warning: rom does not match the one the replay was recorded with");
+    }
+
+    let (result, _, divergence) = tango_pvp::eval::eval(&replay, &rom, hooks, Vec::new).await?;
     println!("{}", result.outcome as u8);
+    if let Some(tick) = divergence {
+        eprintln!("warning: playback diverged from the recording at tick {}", tick);
+    }
+
+    Ok(())
+}
+
+/// Looks up the hooks for a side of a replay, making sure the given ROM is for that game.
+fn hooks_for_side(
+    side: Option<&tango_pvp::replay::metadata::Side>,
+    rom: &[u8],
+) -> Result<&'static (dyn tango_pvp::hooks::Hooks + Send + Sync), anyhow::Error> {
+    let detected_game = tango_gamedb::detect(rom).ok_or(anyhow::anyhow!("rom detection failed"))?;
+    let game_info = side
+        .and_then(|side| side.game_info.as_ref())
+        .ok_or(anyhow::anyhow!("missing game info"))?;
+    let game = tango_gamedb::find_by_family_and_variant(&game_info.rom_family, game_info.rom_variant as u8).ok_or(
+        anyhow::anyhow!("unknown game {} {}", game_info.rom_family, game_info.rom_variant),
+    )?;
+    if game != detected_game {
+        return Err(anyhow::format_err!(
+            "expected game {:?}, got {:?}",
+            game.family_and_variant,
+            detected_game.family_and_variant
+        ));
+    }
+    Ok(tango_pvp::hooks::hooks_for_gamedb_entry(game).unwrap())
+}
+
+const HEXDUMP_WIDTH: usize = 16;
+const MAX_HEXDUMP_ROWS: usize = 32;
+
+/// Prints the rows of `a` and `b` that differ, side by side.
+fn print_hexdump_diff(base_addr: usize, a: &[u8], b: &[u8]) {
+    let num_differing = a.iter().zip(b.iter()).filter(|(x, y)| x != y).count() + a.len().abs_diff(b.len());
+    println!("{} byte(s) differ", num_differing);
+
+    let hex = |row: &[u8], other: &[u8]| {
+        row.iter()
+            .enumerate()
+            .map(|(i, v)| {
+                if other.get(i) == Some(v) {
+                    format!(" {:02x}", v)
+                } else {
+                    format!("*{:02x}", v)
+                }
+            })
+            .collect::<String>()
+    };
+
+    let mut rows_shown = 0;
+    for offset in (0..std::cmp::max(a.len(), b.len())).step_by(HEXDUMP_WIDTH) {
+        let row_a = a
+            .get(offset..std::cmp::min(offset + HEXDUMP_WIDTH, a.len()))
+            .unwrap_or(&[]);
+        let row_b = b
+            .get(offset..std::cmp::min(offset + HEXDUMP_WIDTH, b.len()))
+            .unwrap_or(&[]);
+        if row_a == row_b {
+            continue;
+        }
+        if rows_shown == MAX_HEXDUMP_ROWS {
+            println!("...");
+            break;
+        }
+        println!("{:08x}  a:{}", base_addr + offset, hex(row_a, row_b));
+        println!("{:8}  b:{}", "", hex(row_b, row_a));
+        rows_shown += 1;
+    }
+}
+
+fn diff_input(side: &str, a: &tango_pvp::input::Input, b: &tango_pvp::input::Input) -> bool {
+    let mut differs = false;
+    if a.joyflags != b.joyflags {
+        println!(
+            "{} joyflags differ: a = {:04x}, b = {:04x}",
+            side, a.joyflags, b.joyflags
+        );
+        differs = true;
+    }
+    if a.packet != b.packet {
+        println!("{} packets differ:", side);
+        print_hexdump_diff(0, &a.packet, &b.packet);
+        differs = true;
+    }
+    differs
+}
+
+async fn cmd_diff(
+    replay: tango_pvp::replay::Replay,
+    other_path: std::path::PathBuf,
+    rom_path: Option<std::path::PathBuf>,
+) -> Result<(), anyhow::Error> {
+    let mut other = tango_pvp::replay::Replay::decode(std::fs::File::open(&other_path)?)?;
+    if other.local_player_index != replay.local_player_index {
+        other = other.into_remote();
+    }
+
+    let mut first_input_divergence = None;
+    {
+        let mut a = replay.input_pairs.iter().peekable();
+        let mut b = other.input_pairs.iter().peekable();
+        loop {
+            let (ip_a, ip_b) = match (a.peek(), b.peek()) {
+                (Some(ip_a), Some(ip_b)) => (*ip_a, *ip_b),
+                (Some(ip), None) => {
+                    println!("tick {}: only a has inputs from here on", ip.local.local_tick);
+                    first_input_divergence = Some(ip.local.local_tick);
+                    break;
+                }
+                (None, Some(ip)) => {
+                    println!("tick {}: only b has inputs from here on", ip.local.local_tick);
+                    first_input_divergence = Some(ip.local.local_tick);
+                    break;
+                }
+                (None, None) => {
+                    break;
+                }
+            };
+
+            // A tick that only one of the replays has is where they diverge.
+            match ip_a.local.local_tick.cmp(&ip_b.local.local_tick) {
+                std::cmp::Ordering::Less => {
+                    println!("tick {}: missing from b", ip_a.local.local_tick);
+                    first_input_divergence = Some(ip_a.local.local_tick);
+                    break;
+                }
+                std::cmp::Ordering::Greater => {
+                    println!("tick {}: missing from a", ip_b.local.local_tick);
+                    first_input_divergence = Some(ip_b.local.local_tick);
+                    break;
+                }
+                std::cmp::Ordering::Equal => {}
+            }
+
+            let tick = ip_a.local.local_tick;
+            let local_differs = diff_input(&format!("tick {}: local", tick), &ip_a.local, &ip_b.local);
+            let remote_differs = diff_input(&format!("tick {}: remote", tick), &ip_a.remote, &ip_b.remote);
+            if local_differs || remote_differs {
+                first_input_divergence = Some(tick);
+                break;
+            }
+
+            a.next();
+            b.next();
+        }
+    }
+
+    match first_input_divergence {
+        Some(tick) => println!("inputs first diverge at tick {}", tick),
+        None => println!("inputs are identical"),
+    }
+
+    let Some(rom_path) = rom_path else {
+        return Ok(());
+    };
+
+    let rom = std::fs::read(&rom_path)?;
+    let hooks = hooks_for_side(replay.metadata.local_side.as_ref(), &rom)?;
+
+    let mut a = tango_pvp::eval::Playback::new(&replay, &rom, hooks, 0)?;
+    let mut b = tango_pvp::eval::Playback::new(&other, &rom, hooks, 0)?;
+    loop {
+        let (tick_a, tick_b) = (a.current_tick(), b.current_tick());
+        if tick_a == tick_b {
+            let (state_a, state_b) = (a.save_state()?, b.save_state()?);
+            if state_a.wram() != state_b.wram() {
+                println!("wram first diverges at tick {}:", tick_a);
+                print_hexdump_diff(0x02000000, state_a.wram(), state_b.wram());
+                return Ok(());
+            }
+        }
+
+        let stepped_a = tick_a <= tick_b && a.step()?;
+        let stepped_b = tick_b <= tick_a && b.step()?;
+        if !stepped_a && !stepped_b {
+            break;
+        }
+    }
+    println!(
+        "wram is identical up to tick {}",
+        std::cmp::min(a.current_tick(), b.current_tick())
+    );
+
+    Ok(())
+}
+
+/// Plays a replay up to the start of `tick` and returns the state there.
+fn state_at_tick(
+    replay: &tango_pvp::replay::Replay,
+    rom: &[u8],
+    hooks: &(dyn tango_pvp::hooks::Hooks + Send + Sync),
+    tick: u32,
+) -> Result<tango_pvp::battle::CommittedState, anyhow::Error> {
+    let mut playback = tango_pvp::eval::Playback::new(replay, rom, hooks, tick)?;
+    loop {
+        if let Some(committed_state) = playback.take_committed_state() {
+            return Ok(committed_state);
+        }
+        if !playback.step()? {
+            return Err(anyhow::anyhow!(
+                "replay ended at tick {} before reaching tick {}",
+                playback.current_tick(),
+                tick
+            ));
+        }
+    }
+}
+
+async fn cmd_cut(
+    replay: tango_pvp::replay::Replay,
+    from: u32,
+    to: Option<u32>,
+    local_rom_path: std::path::PathBuf,
+    remote_rom_path: Option<std::path::PathBuf>,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let to = to.unwrap_or(u32::MAX);
+    if from >= to {
+        return Err(anyhow::anyhow!("--from must be before --to"));
+    }
+
+    let local_rom = std::fs::read(&local_rom_path)?;
+    let local_hooks = hooks_for_side(replay.metadata.local_side.as_ref(), &local_rom)?;
+    let remote_rom = if let Some(remote_rom_path) = remote_rom_path {
+        std::fs::read(&remote_rom_path)?
+    } else {
+        local_rom.clone()
+    };
+    let remote_hooks = hooks_for_side(replay.metadata.remote_side.as_ref(), &remote_rom)?;
+
+    let local_state = state_at_tick(&replay, &local_rom, local_hooks, from)?;
+    let remote_state = state_at_tick(&replay.clone().into_remote(), &remote_rom, remote_hooks, from)?;
+
+    let last_tick = replay.input_pairs.last().map(|ip| ip.local.local_tick).unwrap_or(0);
+    let cut = tango_pvp::replay::Replay {
+        // Only keep the result if we're keeping the end of the round too.
+        is_complete: replay.is_complete && to > last_tick,
+        metadata: replay.metadata,
+        local_player_index: replay.local_player_index,
+        local_state: local_state.state,
+        remote_state: remote_state.state,
+        input_pairs: replay
+            .input_pairs
+            .into_iter()
+            .filter(|ip| (from..to).contains(&ip.local.local_tick))
+            .collect(),
+        wram_checksums: replay
+            .wram_checksums
+            .into_iter()
+            .filter(|c| (from..to).contains(&c.tick))
+            .collect(),
+        // The signatures were over the whole round, so they don't hold for a piece of it.
+        signatures: vec![],
+    };
+    eprintln!(
+        "cut {} input(s) from tick {} to {}",
+        cut.input_pairs.len(),
+        from,
+        std::cmp::min(to, last_tick + 1)
+    );
+    cut.encode(std::fs::File::create(output_path)?)?;
+
+    Ok(())
+}
+
+async fn cmd_savestate(
+    replay: tango_pvp::replay::Replay,
+    tick: u32,
+    rom_path: std::path::PathBuf,
+    patches_path: Option<std::path::PathBuf>,
+    output_path: std::path::PathBuf,
+) -> Result<(), anyhow::Error> {
+    let mut rom = std::fs::read(&rom_path)?;
+    let hooks = hooks_for_side(replay.metadata.local_side.as_ref(), &rom)?;
+
+    if let Some(patch) = replay
+        .metadata
+        .local_side
+        .as_ref()
+        .and_then(|side| side.game_info.as_ref())
+        .and_then(|game_info| game_info.patch.as_ref())
+    {
+        let patches_path = patches_path.ok_or_else(|| {
+            anyhow::anyhow!(
+                "replay was played with patch {} v{}, but no --patches-path was given",
+                patch.name,
+                patch.version
+            )
+        })?;
+        // hooks_for_side already checked that this is the right game.
+        let game = tango_gamedb::detect(&rom).unwrap();
+        rom = tango_pvp::patch::apply_from_disk(&rom, game, &patches_path, &patch.name, &patch.version)?;
+    }
+
+    let committed_state = state_at_tick(&replay, &rom, hooks, tick)?;
+    std::fs::write(&output_path, committed_state.state.as_slice())?;
+    eprintln!("wrote state at tick {} to {}", tick, output_path.display());
+    Ok(())
+}
+
+async fn cmd_verify(replay: tango_pvp::replay::Replay) -> Result<(), anyhow::Error> {
+    let hex = |buf: &[u8]| buf.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+
+    let digest = tango_pvp::replay::signing::digest(&replay);
+    println!("digest: {}", hex(&digest));
+
+    let sides = [
+        ("local", replay.metadata.local_side.as_ref()),
+        ("remote", replay.metadata.remote_side.as_ref()),
+    ];
+
+    let mut num_invalid = 0;
+    for signature in &replay.signatures {
+        let valid = signature.verify(&digest);
+        let owner = sides
+            .iter()
+            .find(|(_, side)| side.is_some_and(|side| side.signing_public_key == signature.public_key))
+            .map(|(name, _)| format!("{} side", name))
+            .unwrap_or_else(|| "not bound to either side".to_string());
+        println!(
+            "{}: {} ({})",
+            hex(&signature.public_key),
+            if valid { "valid" } else { "INVALID" },
+            owner
+        );
+        if !valid {
+            num_invalid += 1;
+        }
+    }
+
+    if num_invalid > 0 {
+        return Err(anyhow::anyhow!(
+            "{} of {} signature(s) are invalid",
+            num_invalid,
+            replay.signatures.len()
+        ));
+    }
+
+    // Each side has to have signed exactly once, with the key it presented during the handshake.
+    for ((name, _), count) in sides
+        .iter()
+        .zip(tango_pvp::replay::signing::count_bound_signatures(&replay))
+    {
+        match count {
+            Some(1) => {}
+            None => {
+                return Err(anyhow::anyhow!("{} side presented no signing key", name));
+            }
+            Some(0) => {
+                return Err(anyhow::anyhow!("{} side did not sign this replay", name));
+            }
+            Some(n) => {
+                return Err(anyhow::anyhow!("{} side signed this replay {} times", name, n));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn cmd_telemetry(replay: tango_pvp::replay::Replay, rom_path: std::path::PathBuf) -> Result<(), anyhow::Error> {
+    let rom = std::fs::read(&rom_path)?;
+    let hooks = hooks_for_side(replay.metadata.local_side.as_ref(), &rom)?;
+
+    let entries = tango_pvp::eval::telemetry(&replay, &rom, hooks)?;
+    let mut stdout = std::io::stdout().lock();
+    for entry in entries {
+        serde_json::to_writer(&mut stdout, &entry)?;
+        stdout.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+fn find_replays(path: &std::path::Path, replays: &mut Vec<std::path::PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_replays(&path, replays)?;
+        } else if path.extension() == Some(std::ffi::OsStr::new("tangoreplay")) {
+            replays.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// One evaluated round, as printed by batch-eval.
+#[derive(serde::Serialize)]
+struct BatchEvalRow {
+    path: String,
+    folder: String,
+    opponent: String,
+    date: String,
+    game: String,
+    patch: String,
+    match_type: u32,
+    match_subtype: u32,
+    round: u32,
+    outcome: String,
+    duration_ticks: u32,
+    duration_secs: f32,
+}
+
+#[derive(Default)]
+struct WinRate {
+    wins: usize,
+    losses: usize,
+    draws: usize,
+}
+
+fn print_win_rates(title: &str, win_rates: &std::collections::BTreeMap<String, WinRate>) {
+    eprintln!("{}:", title);
+    for (key, wr) in win_rates {
+        let total = wr.wins + wr.losses + wr.draws;
+        eprintln!(
+            "  {}: {}W {}L {}D ({:.1}%)",
+            key,
+            wr.wins,
+            wr.losses,
+            wr.draws,
+            wr.wins as f32 / total as f32 * 100.0
+        );
+    }
+}
+
+async fn cmd_batch_eval(
+    path: std::path::PathBuf,
+    roms_path: std::path::PathBuf,
+    patches_path: std::path::PathBuf,
+    format: StatsFormat,
+    jobs: Option<usize>,
+) -> Result<(), anyhow::Error> {
+    // Anything in the ROMs directory that isn't a readable file is skipped rather than failing the whole run.
+    let mut roms = std::collections::HashMap::new();
+    for entry in std::fs::read_dir(&roms_path)? {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                eprintln!("{}: {}", roms_path.display(), e);
+                continue;
+            }
+        };
+        if !path.is_file() {
+            continue;
+        }
+        let rom = match std::fs::read(&path) {
+            Ok(rom) => rom,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                continue;
+            }
+        };
+        if let Some(game) = tango_gamedb::detect(&rom) {
+            roms.insert(game.family_and_variant, rom);
+        }
+    }
+
+    let mut paths = vec![];
+    find_replays(&path, &mut paths)?;
+
+    // Group replays by the ROM they need, so each patched ROM is only built once. Only the metadata is read here: the
+    // replays themselves are decoded by the workers, one at a time each.
+    let mut groups = std::collections::BTreeMap::<_, Vec<_>>::new();
+    for replay_path in paths {
+        let metadata = match std::fs::File::open(&replay_path)
+            .and_then(|f| tango_pvp::replay::read_metadata(&mut std::io::BufReader::new(f)))
+        {
+            Ok((_, metadata)) => metadata,
+            Err(e) => {
+                eprintln!("{}: {}", replay_path.display(), e);
+                continue;
+            }
+        };
+        let Some(game_info) = metadata.local_side.as_ref().and_then(|side| side.game_info.clone()) else {
+            eprintln!("{}: missing local game info", replay_path.display());
+            continue;
+        };
+        let key = (
+            game_info.rom_family.clone(),
+            game_info.rom_variant as u8,
+            game_info.patch.as_ref().map(|p| (p.name.clone(), p.version.clone())),
+        );
+        groups.entry(key).or_default().push((replay_path, metadata));
+    }
+
+    let mut queue = std::collections::VecDeque::new();
+    for ((family, variant, patch), replays) in groups {
+        let Some(game) = tango_gamedb::find_by_family_and_variant(&family, variant) else {
+            eprintln!(
+                "unknown game {} {}, skipping {} replay(s)",
+                family,
+                variant,
+                replays.len()
+            );
+            continue;
+        };
+        let Some(rom) = roms.get(&game.family_and_variant) else {
+            eprintln!(
+                "no rom for {:?}, skipping {} replay(s)",
+                game.family_and_variant,
+                replays.len()
+            );
+            continue;
+        };
+        let rom = if let Some((name, version)) = patch.as_ref() {
+            match tango_pvp::patch::apply_from_disk(rom, game, &patches_path, name, version) {
+                Ok(rom) => rom,
+                Err(e) => {
+                    eprintln!(
+                        "failed to apply patch {} v{}: {}, skipping {} replay(s)",
+                        name,
+                        version,
+                        e,
+                        replays.len()
+                    );
+                    continue;
+                }
+            }
+        } else {
+            rom.clone()
+        };
+        let rom = std::sync::Arc::new(rom);
+        let hooks = tango_pvp::hooks::hooks_for_gamedb_entry(game).unwrap();
+        let game_label = match patch.as_ref() {
+            Some((name, version)) => (format!("{}{}", family, variant), format!("{} v{}", name, version)),
+            None => (format!("{}{}", family, variant), String::new()),
+        };
+        for (replay_path, metadata) in replays {
+            queue.push_back((replay_path, metadata, rom.clone(), hooks, game_label.clone()));
+        }
+    }
+
+    let queue = std::sync::Mutex::new(queue);
+    let rows = std::sync::Mutex::new(vec![]);
+    let handle = tokio::runtime::Handle::current();
+    let jobs = jobs.unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1));
+    std::thread::scope(|s| {
+        for _ in 0..jobs {
+            s.spawn(|| loop {
+                let Some((replay_path, metadata, rom, hooks, (game, patch))) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+
+                let replay = match std::fs::File::open(&replay_path)
+                    .and_then(|f| tango_pvp::replay::Replay::decode(std::io::BufReader::new(f)))
+                {
+                    Ok(replay) => replay,
+                    Err(e) => {
+                        eprintln!("{}: {}", replay_path.display(), e);
+                        continue;
+                    }
+                };
+
+                // Each evaluation gets its own core.
+                let (result, _, _) = match handle.block_on(tango_pvp::eval::eval(&replay, &rom, hooks, Vec::new)) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        eprintln!("{}: {}", replay_path.display(), e);
+                        continue;
+                    }
+                };
+
+                rows.lock().unwrap().push(BatchEvalRow {
+                    path: replay_path.display().to_string(),
+                    folder: replay_path
+                        .parent()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default(),
+                    opponent: metadata
+                        .remote_side
+                        .as_ref()
+                        .map(|side| side.nickname.clone())
+                        .unwrap_or_default(),
+                    date: time::OffsetDateTime::from_unix_timestamp_nanos(metadata.ts as i128 * 1_000_000)
+                        .ok()
+                        .and_then(|ts| ts.format(&time::format_description::well_known::Rfc3339).ok())
+                        .unwrap_or_default(),
+                    game,
+                    patch,
+                    match_type: metadata.match_type,
+                    match_subtype: metadata.match_subtype,
+                    round: metadata.round,
+                    outcome: match result.outcome {
+                        tango_pvp::stepper::BattleOutcome::Win => "win",
+                        tango_pvp::stepper::BattleOutcome::Loss => "loss",
+                        tango_pvp::stepper::BattleOutcome::Draw => "draw",
+                    }
+                    .to_string(),
+                    duration_ticks: result.tick,
+                    duration_secs: result.tick as f32 / tango_pvp::battle::EXPECTED_FPS,
+                });
+            });
+        }
+    });
+
+    let mut rows = rows.into_inner().unwrap();
+    rows.sort_by(|a, b| a.path.cmp(&b.path));
+
+    match format {
+        StatsFormat::Csv => {
+            let mut w = csv::Writer::from_writer(std::io::stdout().lock());
+            for row in &rows {
+                w.serialize(row)?;
+            }
+            w.flush()?;
+        }
+        StatsFormat::Json => {
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer_pretty(&mut stdout, &rows)?;
+            stdout.write_all(b"\n")?;
+        }
+    }
+
+    let mut by_opponent = std::collections::BTreeMap::<String, WinRate>::new();
+    let mut by_folder = std::collections::BTreeMap::<String, WinRate>::new();
+    for row in &rows {
+        for wr in [
+            by_opponent.entry(row.opponent.clone()).or_default(),
+            by_folder.entry(row.folder.clone()).or_default(),
+        ] {
+            match row.outcome.as_str() {
+                "win" => wr.wins += 1,
+                "loss" => wr.losses += 1,
+                _ => wr.draws += 1,
+            }
+        }
+    }
+    print_win_rates("win rate by opponent", &by_opponent);
+    print_win_rates("win rate by folder", &by_folder);
 
     Ok(())
 }
@@ -0,0 +1,109 @@
+use clap::Parser;
+
+fn fixture_replay() -> tango_pvp::replay::Replay {
+    let state = mgba::state::State::from_slice(&vec![0u8; std::mem::size_of::<mgba::state::State>()]);
+    let side = |nickname: &str| tango_pvp::replay::metadata::Side {
+        nickname: nickname.to_string(),
+        ..Default::default()
+    };
+    tango_pvp::replay::Replay {
+        is_complete: true,
+        metadata: tango_pvp::replay::Metadata {
+            local_side: Some(side("local")),
+            remote_side: Some(side("remote")),
+            round: 1,
+            ..Default::default()
+        },
+        local_player_index: 0,
+        local_state: state.clone(),
+        remote_state: state,
+        input_pairs: (0..120)
+            .map(|tick| {
+                let input = tango_pvp::input::Input {
+                    local_tick: tick,
+                    remote_tick: tick,
+                    joyflags: 0,
+                    packet: vec![0; 4],
+                    dt: std::time::Duration::from_millis(16),
+                };
+                tango_pvp::input::Pair {
+                    local: input.clone(),
+                    remote: input,
+                }
+            })
+            .collect(),
+        wram_checksums: vec![tango_pvp::replay::WramChecksum { tick: 60, crc32: 1 }],
+        signatures: vec![],
+    }
+}
+
+/// Reads the replay the way main does for the given command line.
+fn read_replay(args: &[&str]) -> tango_pvp::replay::Replay {
+    let args = super::Args::try_parse_from(args).unwrap();
+    super::read_replay(&args, None).unwrap()
+}
+
+#[test]
+fn test_eval_keeps_checksums() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fixture.tangoreplay");
+    let replay = fixture_replay();
+    replay.encode(std::fs::File::create(&path).unwrap()).unwrap();
+    let path = path.to_str().unwrap();
+
+    // eval reports divergence by comparing playback against the recorded checksums, so it has to see them.
+    let read = read_replay(&["tango-replaytool", path, "eval", "rom.gba"]);
+    assert_eq!(read.local_player_index, replay.local_player_index);
+    assert_eq!(read.wram_checksums, replay.wram_checksums);
+    let read = read_replay(&["tango-replaytool", "--invert", "true", path, "eval", "rom.gba"]);
+    assert_eq!(read.wram_checksums, replay.wram_checksums);
+
+    // Everything else still looks from the other side unless told not to.
+    let read = read_replay(&["tango-replaytool", path, "text"]);
+    assert_eq!(read.local_player_index, 1);
+    assert!(read.wram_checksums.is_empty());
+    let read = read_replay(&["tango-replaytool", "--invert", "false", path, "text"]);
+    assert_eq!(read.local_player_index, 0);
+    assert_eq!(read.wram_checksums, replay.wram_checksums);
+}
+
+#[test]
+fn test_cut_keeps_perspective() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fixture.tangoreplay");
+    let replay = fixture_replay();
+    replay.encode(std::fs::File::create(&path).unwrap()).unwrap();
+    let path = path.to_str().unwrap();
+
+    // The cut is written from the same side as the input, so the local ROM is the recording side's and the
+    // checksums in range carry over.
+    let read = read_replay(&[
+        "tango-replaytool",
+        path,
+        "cut",
+        "--from",
+        "30",
+        "local.gba",
+        "out.tangoreplay",
+    ]);
+    assert_eq!(read.local_player_index, replay.local_player_index);
+    assert_eq!(read.metadata.local_side, replay.metadata.local_side);
+    assert_eq!(read.wram_checksums, replay.wram_checksums);
+}
+
+#[test]
+fn test_batch_eval_jobs() {
+    let args = |jobs: &str| {
+        super::Args::try_parse_from([
+            "tango-replaytool",
+            "replays",
+            "batch-eval",
+            "roms",
+            "patches",
+            "--jobs",
+            jobs,
+        ])
+    };
+    assert!(args("0").is_err());
+    assert!(args("2").is_ok());
+}
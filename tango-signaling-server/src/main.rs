@@ -1,6 +1,7 @@
 mod httputil;
 mod iceconfig;
 mod matchmaking;
+mod presence;
 use envconfig::Envconfig;
 use prost::Message;
 use routerify::ext::RequestExt;
@@ -45,6 +46,7 @@ struct Config {
 struct State {
     real_ip_getter: httputil::RealIPGetter,
     matchmaking_server: std::sync::Arc<matchmaking::Server>,
+    presence_server: std::sync::Arc<presence::Server>,
 }
 
 async fn handle_healthcheck_request(
@@ -56,7 +58,7 @@ async fn handle_healthcheck_request(
         .unwrap())
 }
 
-pub const EXPECTED_PROTOCOL_VERSION: u8 = 0x3a;
+pub const EXPECTED_PROTOCOL_VERSION: u8 = tango_signaling::PROTOCOL_VERSION;
 
 async fn handle_matchmaking_request(
     mut request: hyper::Request<hyper::Body>,
@@ -159,6 +161,43 @@ async fn handle_matchmaking_request(
     Ok(response)
 }
 
+async fn handle_presence_request(
+    mut request: hyper::Request<hyper::Body>,
+) -> Result<hyper::Response<hyper::Body>, anyhow::Error> {
+    if !hyper_tungstenite::is_upgrade_request(&request) {
+        return Ok(hyper::Response::builder()
+            .status(hyper::StatusCode::BAD_REQUEST)
+            .body(hyper::Body::from("not upgrade request"))
+            .unwrap());
+    }
+
+    let (response, websocket) = hyper_tungstenite::upgrade(
+        &mut request,
+        Some(tungstenite::protocol::WebSocketConfig {
+            max_message_size: Some(64 * 1024),
+            max_frame_size: Some(64 * 1024),
+            ..Default::default()
+        }),
+    )?;
+
+    let presence_server = request.data::<State>().unwrap().presence_server.clone();
+    tokio::spawn(async move {
+        let websocket = match websocket.await {
+            Ok(websocket) => websocket,
+            Err(e) => {
+                log::error!("error in presence websocket connection: {}", e);
+                return;
+            }
+        };
+
+        if let Err(e) = presence_server.handle_stream(websocket).await {
+            log::error!("error in presence websocket connection: {}", e);
+        }
+    });
+
+    Ok(response)
+}
+
 fn router(
     real_ip_getter: httputil::RealIPGetter,
     iceconfig_backend: Option<Box<dyn iceconfig::Backend + Send + Sync + 'static>>,
@@ -167,8 +206,10 @@ fn router(
         .data(State {
             real_ip_getter,
             matchmaking_server: std::sync::Arc::new(matchmaking::Server::new(iceconfig_backend)),
+            presence_server: std::sync::Arc::new(presence::Server::new()),
         })
         .get("/", handle_matchmaking_request)
+        .get("/presence", handle_presence_request)
         .get("/ok", handle_healthcheck_request)
         .build()
         .unwrap()
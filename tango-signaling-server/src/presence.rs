@@ -0,0 +1,294 @@
+use byteorder::WriteBytesExt;
+use futures_util::{SinkExt, StreamExt, TryStreamExt};
+use prost::Message;
+use sha2::Digest;
+
+const RX_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+const TX_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+const MAX_SUBSCRIPTIONS: usize = 256;
+const OUTBOX_SIZE: usize = 64;
+
+struct Client {
+    connection_id: u64,
+    nickname: String,
+    status: tango_signaling::proto::presence::Status,
+    outbox: tokio::sync::mpsc::Sender<tango_signaling::proto::presence::Packet>,
+    subscriptions: std::collections::HashSet<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_connection_id: u64,
+    clients: std::collections::HashMap<String, Client>,
+    watchers: std::collections::HashMap<String, std::collections::HashSet<String>>,
+}
+
+impl Inner {
+    fn make_update(&self, identity: &str) -> tango_signaling::proto::presence::packet::Update {
+        let client = self.clients.get(identity);
+        tango_signaling::proto::presence::packet::Update {
+            identity: identity.to_string(),
+            nickname: client.map(|c| c.nickname.clone()).unwrap_or_default(),
+            status: client
+                .map(|c| c.status)
+                .unwrap_or(tango_signaling::proto::presence::Status::Offline) as i32,
+        }
+    }
+
+    fn broadcast_update(&self, identity: &str) {
+        let Some(watchers) = self.watchers.get(identity) else {
+            return;
+        };
+
+        let update = self.make_update(identity);
+        for watcher in watchers {
+            let Some(client) = self.clients.get(watcher) else {
+                continue;
+            };
+            // Slow clients just miss updates: they will resync on their next subscribe.
+            let _ = client.outbox.try_send(tango_signaling::proto::presence::Packet {
+                which: Some(tango_signaling::proto::presence::packet::Which::Update(update.clone())),
+            });
+        }
+    }
+
+    fn unsubscribe_all(&mut self, identity: &str) {
+        let Some(client) = self.clients.get_mut(identity) else {
+            return;
+        };
+        for target in std::mem::take(&mut client.subscriptions) {
+            if let Some(watchers) = self.watchers.get_mut(&target) {
+                watchers.remove(identity);
+                if watchers.is_empty() {
+                    self.watchers.remove(&target);
+                }
+            }
+        }
+    }
+}
+
+pub struct Server {
+    inner: tokio::sync::Mutex<Inner>,
+}
+
+fn identity_from_secret(secret: &[u8]) -> String {
+    sha2::Sha256::digest(secret)[..10]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            inner: tokio::sync::Mutex::new(Inner::default()),
+        }
+    }
+
+    pub async fn handle_stream(
+        &self,
+        ws: hyper_tungstenite::WebSocketStream<hyper::upgrade::Upgraded>,
+    ) -> anyhow::Result<()> {
+        let (mut tx, mut rx) = ws.split();
+
+        // Wait for hello message.
+        let hello = match tokio::time::timeout(RX_TIMEOUT, rx.try_next())
+            .await??
+            .ok_or_else(|| anyhow::format_err!("unexpected end of stream"))?
+        {
+            tungstenite::Message::Binary(d) => {
+                match tango_signaling::proto::presence::Packet::decode(d.as_slice())?.which {
+                    Some(tango_signaling::proto::presence::packet::Which::Hello(hello)) => hello,
+                    m => anyhow::bail!("unexpected message: {:?}", m),
+                }
+            }
+            m => {
+                anyhow::bail!("unexpected message: {:?}", m);
+            }
+        };
+
+        if hello.secret.len() < 16 {
+            anyhow::bail!("secret too short");
+        }
+
+        let identity = identity_from_secret(&hello.secret);
+
+        tokio::time::timeout(
+            TX_TIMEOUT,
+            tx.send(tungstenite::Message::Binary(
+                tango_signaling::proto::presence::Packet {
+                    which: Some(tango_signaling::proto::presence::packet::Which::Welcome(
+                        tango_signaling::proto::presence::packet::Welcome {
+                            identity: identity.clone(),
+                        },
+                    )),
+                }
+                .encode_to_vec(),
+            )),
+        )
+        .await??;
+
+        let (outbox_tx, mut outbox_rx) = tokio::sync::mpsc::channel(OUTBOX_SIZE);
+
+        let connection_id = {
+            let mut inner = self.inner.lock().await;
+            let connection_id = inner.next_connection_id;
+            inner.next_connection_id += 1;
+
+            // A newer connection with the same identity replaces the old one.
+            inner.unsubscribe_all(&identity);
+            inner.clients.insert(
+                identity.clone(),
+                Client {
+                    connection_id,
+                    nickname: hello.nickname,
+                    status: tango_signaling::proto::presence::Status::Online,
+                    outbox: outbox_tx,
+                    subscriptions: std::collections::HashSet::new(),
+                },
+            );
+            inner.broadcast_update(&identity);
+            connection_id
+        };
+
+        let r = self
+            .handle_stream_inner(&mut tx, &mut rx, &mut outbox_rx, &identity)
+            .await;
+
+        {
+            let mut inner = self.inner.lock().await;
+            if inner
+                .clients
+                .get(&identity)
+                .map(|c| c.connection_id == connection_id)
+                .unwrap_or(false)
+            {
+                inner.unsubscribe_all(&identity);
+                inner.clients.remove(&identity);
+                inner.broadcast_update(&identity);
+            }
+        }
+
+        r
+    }
+
+    async fn handle_stream_inner(
+        &self,
+        tx: &mut futures_util::stream::SplitSink<
+            hyper_tungstenite::WebSocketStream<hyper::upgrade::Upgraded>,
+            tungstenite::Message,
+        >,
+        rx: &mut futures_util::stream::SplitStream<hyper_tungstenite::WebSocketStream<hyper::upgrade::Upgraded>>,
+        outbox_rx: &mut tokio::sync::mpsc::Receiver<tango_signaling::proto::presence::Packet>,
+        identity: &str,
+    ) -> anyhow::Result<()> {
+        let mut ping_timer = tokio::time::interval(PING_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = ping_timer.tick() => {
+                    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?;
+                    let mut buf = vec![];
+                    buf.write_u64::<byteorder::LittleEndian>(now.as_millis() as u64)?;
+                    tokio::time::timeout(TX_TIMEOUT, tx.send(tungstenite::Message::Ping(buf))).await??;
+                }
+
+                packet = outbox_rx.recv() => {
+                    let Some(packet) = packet else {
+                        // We were replaced by a newer connection.
+                        return Ok(());
+                    };
+                    tokio::time::timeout(
+                        TX_TIMEOUT,
+                        tx.send(tungstenite::Message::Binary(packet.encode_to_vec())),
+                    )
+                    .await??;
+                }
+
+                msg = tokio::time::timeout(RX_TIMEOUT, rx.try_next()) => {
+                    let packet = match msg?? {
+                        Some(tungstenite::Message::Binary(d)) => {
+                            tango_signaling::proto::presence::Packet::decode(d.as_slice())?
+                        }
+                        Some(tungstenite::Message::Pong(_)) => {
+                            continue;
+                        }
+                        Some(tungstenite::Message::Close(_)) | None => {
+                            return Ok(());
+                        }
+                        m => {
+                            anyhow::bail!("unexpected message: {:?}", m);
+                        }
+                    };
+
+                    match packet.which {
+                        Some(tango_signaling::proto::presence::packet::Which::Subscribe(subscribe)) => {
+                            let mut inner = self.inner.lock().await;
+                            inner.unsubscribe_all(identity);
+                            let subscriptions = subscribe
+                                .identities
+                                .into_iter()
+                                .filter(|target| target != identity)
+                                .take(MAX_SUBSCRIPTIONS)
+                                .collect::<std::collections::HashSet<_>>();
+                            for target in subscriptions.iter() {
+                                inner.watchers.entry(target.clone()).or_default().insert(identity.to_string());
+                            }
+                            let updates = subscriptions
+                                .iter()
+                                .map(|target| inner.make_update(target))
+                                .collect::<Vec<_>>();
+                            let Some(client) = inner.clients.get_mut(identity) else {
+                                return Ok(());
+                            };
+                            client.subscriptions = subscriptions;
+                            for update in updates {
+                                let _ = client.outbox.try_send(tango_signaling::proto::presence::Packet {
+                                    which: Some(tango_signaling::proto::presence::packet::Which::Update(update)),
+                                });
+                            }
+                        }
+                        Some(tango_signaling::proto::presence::packet::Which::SetStatus(set_status)) => {
+                            let mut inner = self.inner.lock().await;
+                            let Some(client) = inner.clients.get_mut(identity) else {
+                                return Ok(());
+                            };
+                            client.status = tango_signaling::proto::presence::Status::from_i32(set_status.status)
+                                .unwrap_or(tango_signaling::proto::presence::Status::Online);
+                            client.nickname = set_status.nickname;
+                            inner.broadcast_update(identity);
+                        }
+                        Some(tango_signaling::proto::presence::packet::Which::SendChallenge(send_challenge)) => {
+                            let inner = self.inner.lock().await;
+                            let Some(client) = inner.clients.get(identity) else {
+                                return Ok(());
+                            };
+                            // Only friends who have added us back can be challenged, so strangers can't spam codes.
+                            if !inner
+                                .clients
+                                .get(&send_challenge.identity)
+                                .map(|target| target.subscriptions.contains(identity))
+                                .unwrap_or(false)
+                            {
+                                continue;
+                            }
+                            let target = &inner.clients[&send_challenge.identity];
+                            let _ = target.outbox.try_send(tango_signaling::proto::presence::Packet {
+                                which: Some(tango_signaling::proto::presence::packet::Which::Challenge(
+                                    tango_signaling::proto::presence::packet::Challenge {
+                                        identity: identity.to_string(),
+                                        nickname: client.nickname.clone(),
+                                        link_code: send_challenge.link_code,
+                                    },
+                                )),
+                            });
+                        }
+                        m => anyhow::bail!("unexpected message: {:?}", m),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use std::io::Result;
 
 fn main() -> Result<()> {
-    prost_build::compile_protos(&["src/proto/signaling.proto"], &["src/proto"])?;
+    prost_build::compile_protos(
+        &["src/proto/signaling.proto", "src/proto/presence.proto"],
+        &["src/proto"],
+    )?;
     Ok(())
 }
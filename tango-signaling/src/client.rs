@@ -6,10 +6,11 @@ use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 
 pub type AbortReason = crate::proto::signaling::packet::abort::Reason;
 
-async fn create_data_channel(
+pub(crate) async fn create_data_channel(
     rtc_config: datachannel_wrapper::RtcConfig,
 ) -> Result<
     (
+        datachannel_wrapper::DataChannel,
         datachannel_wrapper::DataChannel,
         tokio::sync::mpsc::Receiver<datachannel_wrapper::PeerConnectionEvent>,
         datachannel_wrapper::PeerConnection,
@@ -32,6 +33,22 @@ async fn create_data_channel(
             .stream(0),
     )?;
 
+    // Inputs go over their own channel so a lost packet doesn't hold up the ones behind it: the sender makes up for
+    // losses by repeating unacknowledged inputs.
+    let input_dc = peer_conn.create_data_channel(
+        "tango-input",
+        datachannel_wrapper::DataChannelInit::default()
+            .reliability(datachannel_wrapper::Reliability {
+                unordered: true,
+                unreliable: true,
+                max_packet_life_time: 0,
+                max_retransmits: 0,
+            })
+            .negotiated()
+            .manual_stream()
+            .stream(1),
+    )?;
+
     loop {
         if let Some(datachannel_wrapper::PeerConnectionEvent::GatheringStateChange(
             datachannel_wrapper::GatheringState::Complete,
@@ -41,7 +58,33 @@ async fn create_data_channel(
         }
     }
 
-    Ok((dc, event_rx, peer_conn))
+    Ok((dc, input_dc, event_rx, peer_conn))
+}
+
+pub(crate) async fn wait_for_connected(
+    event_rx: &mut tokio::sync::mpsc::Receiver<datachannel_wrapper::PeerConnectionEvent>,
+) -> Result<(), Error> {
+    loop {
+        let signal = event_rx.recv().await.unwrap();
+
+        if let datachannel_wrapper::PeerConnectionEvent::ConnectionStateChange(c) = signal {
+            match c {
+                datachannel_wrapper::ConnectionState::Connected => {
+                    return Ok(());
+                }
+                datachannel_wrapper::ConnectionState::Disconnected => {
+                    return Err(Error::PeerConnectionDisconnected);
+                }
+                datachannel_wrapper::ConnectionState::Failed => {
+                    return Err(Error::PeerConnectionFailed);
+                }
+                datachannel_wrapper::ConnectionState::Closed => {
+                    return Err(Error::PeerConnectionClosed);
+                }
+                _ => {}
+            }
+        }
+    }
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -70,6 +113,9 @@ pub enum Error {
     #[error("invalid packet")]
     InvalidPacket(tokio_tungstenite::tungstenite::Message),
 
+    #[error("invalid session description blob")]
+    InvalidBlob,
+
     #[error("unexpected packet: {0:?}")]
     UnexpectedPacket(crate::proto::signaling::Packet),
 
@@ -86,7 +132,14 @@ pub enum Error {
 pub struct Connecting {
     fut: futures_util::future::BoxFuture<
         'static,
-        Result<(datachannel_wrapper::DataChannel, datachannel_wrapper::PeerConnection), Error>,
+        Result<
+            (
+                datachannel_wrapper::DataChannel,
+                datachannel_wrapper::DataChannel,
+                datachannel_wrapper::PeerConnection,
+            ),
+            Error,
+        >,
     >,
 }
 
@@ -198,7 +251,7 @@ pub async fn connect(
     if use_relay == Some(true) {
         rtc_config.ice_transport_policy = datachannel_wrapper::TransportPolicy::Relay;
     }
-    let (dc, mut event_rx, mut peer_conn) = create_data_channel(rtc_config).await?;
+    let (dc, input_dc, mut event_rx, mut peer_conn) = create_data_channel(rtc_config).await?;
 
     signaling_stream
         .send(tokio_tungstenite::tungstenite::Message::Binary(
@@ -301,35 +354,22 @@ pub async fn connect(
                 peer_conn.remote_description().expect("remote sdp").sdp
             );
 
-            loop {
-                let signal = event_rx.recv().await.unwrap();
-
-                if let datachannel_wrapper::PeerConnectionEvent::ConnectionStateChange(c) = signal {
-                    match c {
-                        datachannel_wrapper::ConnectionState::Connected => {
-                            break;
-                        }
-                        datachannel_wrapper::ConnectionState::Disconnected => {
-                            return Err(Error::PeerConnectionDisconnected);
-                        }
-                        datachannel_wrapper::ConnectionState::Failed => {
-                            return Err(Error::PeerConnectionFailed);
-                        }
-                        datachannel_wrapper::ConnectionState::Closed => {
-                            return Err(Error::PeerConnectionClosed);
-                        }
-                        _ => {}
-                    }
-                }
-            }
+            wait_for_connected(&mut event_rx).await?;
 
-            Ok((dc, peer_conn))
+            Ok((dc, input_dc, peer_conn))
         }),
     })
 }
 
 impl std::future::Future for Connecting {
-    type Output = Result<(datachannel_wrapper::DataChannel, datachannel_wrapper::PeerConnection), Error>;
+    type Output = Result<
+        (
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::PeerConnection,
+        ),
+        Error,
+    >;
 
     fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<Self::Output> {
         self.fut.poll_unpin(cx)
@@ -1,9 +1,19 @@
+/// The version of the protocol clients speak to each other once connected. The matchmaking server only pairs up
+/// clients on exactly this version, so both sides take it from here.
+pub const PROTOCOL_VERSION: u8 = 0x3d;
+
 #[cfg(feature = "client")]
 mod client;
 
 #[cfg(feature = "client")]
 pub use client::*;
 
+#[cfg(feature = "client")]
+pub mod manual;
+
+#[cfg(feature = "client")]
+pub mod presence;
+
 #[cfg(feature = "proto")]
 pub mod proto;
 
@@ -0,0 +1,114 @@
+//! Connecting without a signaling server, by having players exchange session descriptions by hand.
+//!
+//! Session descriptions are passed around as base64 blobs so they survive being pasted through chat apps.
+
+use crate::client::{create_data_channel, wait_for_connected, Error};
+
+const DEFAULT_ICE_SERVERS: &[&str] = &[
+    "stun:stun.l.google.com:19302",
+    "stun:stun1.l.google.com:19302",
+    "stun:stun2.l.google.com:19302",
+    "stun:stun3.l.google.com:19302",
+    "stun:stun4.l.google.com:19302",
+];
+
+fn make_rtc_config() -> datachannel_wrapper::RtcConfig {
+    datachannel_wrapper::RtcConfig::new(DEFAULT_ICE_SERVERS)
+}
+
+fn encode_sdp(sdp: &str) -> String {
+    base64::encode(sdp)
+}
+
+fn decode_sdp(blob: &str) -> Result<String, Error> {
+    let blob = blob.chars().filter(|c| !c.is_whitespace()).collect::<String>();
+    String::from_utf8(base64::decode(blob).map_err(|_| Error::InvalidBlob)?).map_err(|_| Error::InvalidBlob)
+}
+
+pub struct Offerer {
+    dc: datachannel_wrapper::DataChannel,
+    input_dc: datachannel_wrapper::DataChannel,
+    event_rx: tokio::sync::mpsc::Receiver<datachannel_wrapper::PeerConnectionEvent>,
+    peer_conn: datachannel_wrapper::PeerConnection,
+}
+
+impl Offerer {
+    pub async fn new() -> Result<Self, Error> {
+        let (dc, input_dc, event_rx, peer_conn) = create_data_channel(make_rtc_config()).await?;
+        Ok(Self {
+            dc,
+            input_dc,
+            event_rx,
+            peer_conn,
+        })
+    }
+
+    /// The blob to send to the other player.
+    pub fn offer(&self) -> String {
+        encode_sdp(&self.peer_conn.local_description().unwrap().sdp.to_string())
+    }
+
+    pub async fn connect(
+        mut self,
+        answer: &str,
+    ) -> Result<
+        (
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::PeerConnection,
+        ),
+        Error,
+    > {
+        self.peer_conn
+            .set_remote_description(datachannel_wrapper::SessionDescription {
+                sdp_type: datachannel_wrapper::SdpType::Answer,
+                sdp: datachannel_wrapper::sdp::parse_sdp(&decode_sdp(answer)?, false)?,
+            })?;
+        wait_for_connected(&mut self.event_rx).await?;
+        Ok((self.dc, self.input_dc, self.peer_conn))
+    }
+}
+
+pub struct Answerer {
+    dc: datachannel_wrapper::DataChannel,
+    input_dc: datachannel_wrapper::DataChannel,
+    event_rx: tokio::sync::mpsc::Receiver<datachannel_wrapper::PeerConnectionEvent>,
+    peer_conn: datachannel_wrapper::PeerConnection,
+}
+
+impl Answerer {
+    pub async fn new(offer: &str) -> Result<Self, Error> {
+        let offer = decode_sdp(offer)?;
+        let (dc, input_dc, event_rx, mut peer_conn) = create_data_channel(make_rtc_config()).await?;
+        peer_conn.set_local_description(datachannel_wrapper::SdpType::Rollback)?;
+        peer_conn.set_remote_description(datachannel_wrapper::SessionDescription {
+            sdp_type: datachannel_wrapper::SdpType::Offer,
+            sdp: datachannel_wrapper::sdp::parse_sdp(&offer, false)?,
+        })?;
+        Ok(Self {
+            dc,
+            input_dc,
+            event_rx,
+            peer_conn,
+        })
+    }
+
+    /// The blob to send back to the player who made the offer.
+    pub fn answer(&self) -> String {
+        encode_sdp(&self.peer_conn.local_description().unwrap().sdp.to_string())
+    }
+
+    pub async fn connect(
+        mut self,
+    ) -> Result<
+        (
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::DataChannel,
+            datachannel_wrapper::PeerConnection,
+        ),
+        Error,
+    > {
+        wait_for_connected(&mut self.event_rx).await?;
+        Ok((self.dc, self.input_dc, self.peer_conn))
+    }
+}
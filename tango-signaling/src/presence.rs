@@ -0,0 +1,177 @@
+use futures_util::SinkExt;
+use futures_util::StreamExt;
+use futures_util::TryStreamExt;
+use prost::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+pub type Status = crate::proto::presence::Status;
+pub type Update = crate::proto::presence::packet::Update;
+pub type Challenge = crate::proto::presence::packet::Challenge;
+
+type WebSocketStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("tungstenite: {0:?}")]
+    Tungstenite(#[from] tokio_tungstenite::tungstenite::Error),
+
+    #[error("io: {0:?}")]
+    Io(#[from] std::io::Error),
+
+    #[error("prost decode error: {0:?}")]
+    ProstDecode(#[from] prost::DecodeError),
+
+    #[error("url parse error: {0:?}")]
+    UrlParse(#[from] url::ParseError),
+
+    #[error("http error: {0:?}")]
+    Http(#[from] tokio_tungstenite::tungstenite::http::Error),
+
+    #[error("invalid packet")]
+    InvalidPacket(tokio_tungstenite::tungstenite::Message),
+
+    #[error("unexpected packet: {0:?}")]
+    UnexpectedPacket(crate::proto::presence::Packet),
+}
+
+pub enum Event {
+    Update(Update),
+    Challenge(Challenge),
+}
+
+pub struct Sender {
+    tx: futures_util::stream::SplitSink<WebSocketStream, tokio_tungstenite::tungstenite::Message>,
+}
+
+impl Sender {
+    async fn send(&mut self, which: crate::proto::presence::packet::Which) -> Result<(), Error> {
+        self.tx
+            .send(tokio_tungstenite::tungstenite::Message::Binary(
+                crate::proto::presence::Packet { which: Some(which) }.encode_to_vec(),
+            ))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn subscribe(&mut self, identities: Vec<String>) -> Result<(), Error> {
+        self.send(crate::proto::presence::packet::Which::Subscribe(
+            crate::proto::presence::packet::Subscribe { identities },
+        ))
+        .await
+    }
+
+    pub async fn set_status(&mut self, status: Status, nickname: &str) -> Result<(), Error> {
+        self.send(crate::proto::presence::packet::Which::SetStatus(
+            crate::proto::presence::packet::SetStatus {
+                status: status as i32,
+                nickname: nickname.to_string(),
+            },
+        ))
+        .await
+    }
+
+    pub async fn send_challenge(&mut self, identity: &str, link_code: &str) -> Result<(), Error> {
+        self.send(crate::proto::presence::packet::Which::SendChallenge(
+            crate::proto::presence::packet::SendChallenge {
+                identity: identity.to_string(),
+                link_code: link_code.to_string(),
+            },
+        ))
+        .await
+    }
+
+    pub async fn close(&mut self) -> Result<(), Error> {
+        self.tx.close().await?;
+        Ok(())
+    }
+}
+
+pub struct Receiver {
+    rx: futures_util::stream::SplitStream<WebSocketStream>,
+}
+
+impl Receiver {
+    pub async fn receive(&mut self) -> Result<Option<Event>, Error> {
+        loop {
+            let raw = if let Some(raw) = self.rx.try_next().await? {
+                raw
+            } else {
+                return Ok(None);
+            };
+
+            let packet = match raw {
+                tokio_tungstenite::tungstenite::Message::Binary(d) => {
+                    crate::proto::presence::Packet::decode(d.as_slice())?
+                }
+                tokio_tungstenite::tungstenite::Message::Ping(_) => {
+                    // tungstenite replies to pings for us on the next read or write.
+                    continue;
+                }
+                tokio_tungstenite::tungstenite::Message::Close(_) => {
+                    return Ok(None);
+                }
+                _ => {
+                    return Err(Error::InvalidPacket(raw));
+                }
+            };
+
+            return match packet.which {
+                Some(crate::proto::presence::packet::Which::Update(update)) => Ok(Some(Event::Update(update))),
+                Some(crate::proto::presence::packet::Which::Challenge(challenge)) => {
+                    Ok(Some(Event::Challenge(challenge)))
+                }
+                _ => Err(Error::UnexpectedPacket(packet)),
+            };
+        }
+    }
+}
+
+/// Connects to the presence endpoint of a signaling server.
+///
+/// The server derives our identity from `secret`, so it must be kept private: anyone who has it can appear as us.
+pub async fn connect(addr: &str, secret: &[u8], nickname: &str) -> Result<(String, Sender, Receiver), Error> {
+    let url = url::Url::parse(addr)?;
+
+    let mut req = url.to_string().into_client_request()?;
+    req.headers_mut().append(
+        "User-Agent",
+        tokio_tungstenite::tungstenite::http::HeaderValue::from_str(&format!(
+            "tango-signaling/{}",
+            env!("CARGO_PKG_VERSION")
+        ))
+        .map_err(|e| tokio_tungstenite::tungstenite::http::Error::from(e))?,
+    );
+    let (stream, _) = tokio_tungstenite::connect_async(req).await?;
+    let (tx, rx) = stream.split();
+    let mut sender = Sender { tx };
+    let mut receiver = Receiver { rx };
+
+    sender
+        .send(crate::proto::presence::packet::Which::Hello(
+            crate::proto::presence::packet::Hello {
+                secret: secret.to_vec(),
+                nickname: nickname.to_string(),
+            },
+        ))
+        .await?;
+
+    let raw = if let Some(raw) = receiver.rx.try_next().await? {
+        raw
+    } else {
+        return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "stream ended early").into());
+    };
+
+    let packet = if let tokio_tungstenite::tungstenite::Message::Binary(d) = raw {
+        crate::proto::presence::Packet::decode(d.as_slice())?
+    } else {
+        return Err(Error::InvalidPacket(raw));
+    };
+
+    let welcome = if let Some(crate::proto::presence::packet::Which::Welcome(welcome)) = packet.which {
+        welcome
+    } else {
+        return Err(Error::UnexpectedPacket(packet));
+    };
+
+    Ok((welcome.identity, sender, receiver))
+}
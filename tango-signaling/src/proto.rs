@@ -1,3 +1,7 @@
 pub mod signaling {
     include!(concat!(env!("OUT_DIR"), "/tango.signaling.rs"));
 }
+
+pub mod presence {
+    include!(concat!(env!("OUT_DIR"), "/tango.signaling.presence.rs"));
+}
@@ -0,0 +1,24 @@
+friends = Friends
+
+friends-your-code = Your friend code
+friends-copy = Copy
+friends-connecting = Connecting to presence server...
+friends-disabled = Showing your online status is turned off in settings.
+friends-empty = You haven't added any friends yet.
+
+friends-add = Add friend
+friends-add-name = Name
+friends-add-code = Friend code
+
+friends-status = Status
+    .offline = Offline
+    .online = Online
+    .in-lobby = In lobby
+    .in-match = In match
+
+friends-challenge = Challenge
+friends-remove = Remove
+
+friends-challenge-received = {$nickname} challenged you!
+friends-challenge-accept = Accept
+friends-challenge-decline = Decline
@@ -0,0 +1,12 @@
+history = History
+
+history-ratings = Ratings
+history-opponents = Opponents
+history-all-opponents = All opponents
+history-record = {$nickname}: {$wins}-{$losses}-{$draws} (streak {$streak})
+history-evaluating = Working out results... ({$remaining} left)
+history-outcome = Outcome
+    .win = Win
+    .loss = Loss
+    .draw = Draw
+    .unknown = ?
@@ -66,3 +66,14 @@ connection-error-other = A connection error has occurred: { $error }
 connection-error-confirm = Damn!
 
 play-show-link-code = Show link code
+play-lan-host = Host a lobby on the local network
+play-lan-lobbies = LAN lobbies:
+play-manual = Connect manually without a matchmaking server
+play-manual-paste-offer = Paste your opponent's offer here, or leave empty to create one
+play-manual-create-offer = Create offer
+play-manual-answer-offer = Answer offer
+play-manual-copy-offer = Copy offer
+play-manual-paste-answer = Send the offer to your opponent, then paste their answer here
+play-manual-connect = Connect
+play-manual-copy-answer = Copy answer
+play-manual-waiting-for-offerer = Send the answer back to your opponent and wait for them to connect...
@@ -3,7 +3,10 @@ replays = Replays
 replays-export = Export
 replays-export-multi = Export+
 replays-play = Play
+replays-play-both = Play both sides side by side
 replays-scanning = Scanning...
+replays-thumbnail-start = Start of the battle
+replays-thumbnail-end = End of the battle
 
 replay-subtitle = {$game_family} @ {$link_code}: vs {$nickname}
 
@@ -11,6 +14,7 @@ replays-export-path = Save to
     .change = Change
 replays-export-scale-factor = Scale factor
 replays-export-disable-bgm = Disable music
+replays-export-input-display = Show inputs
 replays-export-twosided = Two-sided
 replays-export-success = Your replay was successfully exported.
 replays-export-error = An error occurred while exporting your replay: {$error}
@@ -19,9 +23,40 @@ replays-export-confirm-success = Yay!
 replays-export-confirm-error = Damn!
 replays-export-open = Open
 replays-export-lossless = Lossless 1x
+replays-export-format = Format
+    .ffmpeg = Video (ffmpeg)
+    .y4m-wav = Raw video (Y4M + WAV)
+    .png-sequence = PNG frames + WAV
+    .gif = Animated GIF
+    .apng = Animated PNG
+replays-export-clip = Ticks
+    .enable = Only export a range
 
 replay-viewer-pause = Pause
 replay-viewer-step = Step
 replay-viewer-speed = Speed
 replay-viewer-speed-up = Speed up
 replay-viewer-slow-down = Slow down
+replay-viewer-rom-mismatch = This ROM doesn't match the one the replay was recorded with. Playback may not be accurate.
+replay-viewer-diverged = Playback diverged from the recording at tick {$tick}.
+replay-viewer-save-state = Save an mGBA savestate here (pause first)
+
+replays-filter-search = Search by opponent or link code
+replays-filter-any = Any
+replays-filter-outcome = Only replays whose outcome is already known are matched. Opening a replay works out its outcome.
+replays-filter-period = Date
+    .day = Past day
+    .week = Past week
+    .month = Past month
+    .year = Past year
+replays-outcome = Outcome
+    .win = Win
+    .loss = Loss
+    .draw = Draw
+
+replays-upload-pending = Uploading {$count} replay(s)...
+replays-upload-error = Last upload failed, will retry later: {$error}
+
+replays-share-anonymized = Share anonymized copy
+    .keep-setup = Keep setups
+    .hide-setup = Hide setups
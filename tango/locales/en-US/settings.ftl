@@ -35,6 +35,10 @@ settings-max-queue-length = Max queue length
 settings-matchmaking-endpoint = Matchmaking endpoint
 settings-matchmaking-usetango = Use default endpoint from Tango
 settings-replaycollector-endpoint = Replay collector endpoint
+settings-save-match-files = Save matches as single files
+    .tooltip = Save every round of a match, and how it went, into one .tangomatch file instead of a replay per round. Match files aren't listed under replays yet, but tango-replaytool can split them back into round replays.
+settings-enable-presence = Show online status to friends
+settings-lan-discovery = Enable LAN play
 settings-patch-repo = Patches repository
 settings-enable-patch-autoupdate = Enable autoupdate
 settings-data-path = Data path
@@ -51,6 +55,7 @@ settings-volume = Volume
 settings-enable-updater = Enable updater
 settings-allow-prerelease-upgrades = Allow prerelease upgrades
 settings-show-own-setup = Show own setup
+settings-show-input-display = Show inputs
 settings-integer-scaling = Integer scaling
 settings-always-show-status-bar = Show status bar
     .auto = Automatic
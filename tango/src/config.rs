@@ -80,6 +80,12 @@ where
     buf.parse().map_err(serde::de::Error::custom)
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
+pub struct Friend {
+    pub identity: String,
+    pub name: String,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Eq)]
 #[serde(default)]
 pub struct Config {
@@ -104,6 +110,7 @@ pub struct Config {
     pub full_screen: bool,
     pub streamer_mode: bool,
     pub show_own_setup: bool,
+    pub show_input_display: bool,
     #[serde(deserialize_with = "ok_or_default")]
     pub graphics_backend: GraphicsBackend,
     #[serde(deserialize_with = "ok_or_default")]
@@ -124,6 +131,12 @@ pub struct Config {
     pub use_relay: Option<bool>,
     pub speed_change_percent: u32,
     pub starred_patches: std::collections::HashSet<String>,
+    pub enable_presence: bool,
+    pub presence_secret: String,
+    pub signing_key: String,
+    pub friends: Vec<Friend>,
+    pub lan_discovery: bool,
+    pub save_match_files: bool,
 }
 
 impl Default for Config {
@@ -148,6 +161,7 @@ impl Default for Config {
             full_screen: false,
             streamer_mode: false,
             show_own_setup: false,
+            show_input_display: false,
             graphics_backend: Default::default(),
             audio_backend: Default::default(),
             volume: 0x100,
@@ -166,6 +180,12 @@ impl Default for Config {
             use_relay: None,
             speed_change_percent: 300,
             starred_patches: Default::default(),
+            enable_presence: true,
+            presence_secret: "".to_string(),
+            signing_key: "".to_string(),
+            friends: vec![],
+            lan_discovery: false,
+            save_match_files: false,
         }
     }
 }
@@ -188,8 +208,29 @@ pub fn get_updater_path() -> Result<std::path::PathBuf, anyhow::Error> {
         .join("updater"))
 }
 
+pub fn get_replay_index_path() -> Result<std::path::PathBuf, anyhow::Error> {
+    Ok(get_project_dirs()
+        .ok_or_else(|| anyhow::anyhow!("could not get trill project directory"))?
+        .cache_dir()
+        .join("replay_index.bin"))
+}
+
+pub fn get_replay_thumbnails_path() -> Result<std::path::PathBuf, anyhow::Error> {
+    Ok(get_project_dirs()
+        .ok_or_else(|| anyhow::anyhow!("could not get trill project directory"))?
+        .cache_dir()
+        .join("replay_thumbnails"))
+}
+
 const DATA_DIR_NAME: &str = "Trill";
 
+fn generate_secret() -> String {
+    rand::random::<[u8; 32]>()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
 impl Config {
     pub fn system_defaults() -> Result<Self, anyhow::Error> {
         let user_dirs =
@@ -231,7 +272,7 @@ impl Config {
 
     pub fn load_or_create() -> Result<Self, anyhow::Error> {
         let config_path = get_config_path()?;
-        let config = match std::fs::File::open(config_path) {
+        let mut config = match std::fs::File::open(config_path) {
             Ok(mut file) => {
                 let mut contents = String::new();
                 file.read_to_string(&mut contents)?;
@@ -248,6 +289,15 @@ impl Config {
                 return Err(e.into());
             }
         };
+        if config.presence_secret.is_empty() || config.signing_key.is_empty() {
+            if config.presence_secret.is_empty() {
+                config.presence_secret = generate_secret();
+            }
+            if config.signing_key.is_empty() {
+                config.signing_key = generate_secret();
+            }
+            config.save()?;
+        }
         Ok(config)
     }
 
@@ -258,6 +308,15 @@ impl Config {
         Ok(())
     }
 
+    /// The per-install key used to sign replays.
+    pub fn signing_key(&self) -> Option<tango_pvp::replay::signing::SigningKey> {
+        let seed = (0..self.signing_key.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(self.signing_key.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<_>>>()?;
+        tango_pvp::replay::signing::SigningKey::from_seed(&seed).ok()
+    }
+
     pub fn saves_path(&self) -> std::path::PathBuf {
         self.data_path.join("saves")
     }
@@ -282,6 +341,10 @@ impl Config {
         self.data_path.join("crashstates")
     }
 
+    pub fn upload_queue_path(&self) -> std::path::PathBuf {
+        self.data_path.join("upload_queue")
+    }
+
     pub fn ensure_dirs(&self) -> Result<(), anyhow::Error> {
         std::fs::create_dir_all(self.saves_path())?;
         std::fs::create_dir_all(self.replays_path())?;
@@ -289,6 +352,7 @@ impl Config {
         std::fs::create_dir_all(self.roms_path())?;
         std::fs::create_dir_all(self.logs_path())?;
         std::fs::create_dir_all(self.crashstates_path())?;
+        std::fs::create_dir_all(self.upload_queue_path())?;
         Ok(())
     }
 }
@@ -1,10 +1,14 @@
 use fluent_templates::Loader;
 
-use crate::{audio, config, discord, game, i18n, input, patch, rom, save, session, stats, updater};
+use crate::{
+    audio, config, discord, game, i18n, input, patch, presence, rom, save, session, stats, updater, upload_queue,
+};
 use std::str::FromStr;
 
 mod debug_window;
 mod escape_window;
+mod friends_pane;
+mod history_pane;
 mod language_select;
 mod main_view;
 mod memoize;
@@ -88,6 +92,8 @@ pub struct SharedRootState {
     pub fps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
     pub emu_tps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
     pub discord_client: discord::Client,
+    pub presence_client: presence::Client,
+    pub uploader: upload_queue::Uploader,
     pub font_families: FontFamilies,
     pub ui_windows: ui_windows::UiWindows,
     pub selection: Option<Selection>,
@@ -114,6 +120,8 @@ impl State {
         show_updater: bool,
         config: std::sync::Arc<parking_lot::RwLock<config::Config>>,
         discord_client: discord::Client,
+        presence_client: presence::Client,
+        uploader: upload_queue::Uploader,
         audio_binder: audio::LateBinder,
         fps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
         emu_tps_counter: std::sync::Arc<parking_lot::Mutex<stats::Counter>>,
@@ -204,6 +212,8 @@ impl State {
                 font_families,
                 ui_windows: Default::default(),
                 discord_client,
+                presence_client,
+                uploader,
                 selection: committed_selection,
             },
             last_mouse_motion_time: None,
@@ -1,19 +1,108 @@
 use fluent_templates::Loader;
 
-use crate::{i18n, session};
+use crate::{i18n, session, sync};
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Tab {
+    Memory,
+    Connection,
+}
 
 pub struct State {
+    tab: Tab,
     jump_to: String,
 }
 
 impl State {
     pub fn new() -> Self {
         Self {
+            tab: Tab::Memory,
             jump_to: "".to_string(),
         }
     }
 }
 
+fn format_bytes(n: u64) -> String {
+    if n >= 1024 * 1024 {
+        format!("{:.2} MiB", n as f64 / (1024.0 * 1024.0))
+    } else if n >= 1024 {
+        format!("{:.2} KiB", n as f64 / 1024.0)
+    } else {
+        format!("{} B", n)
+    }
+}
+
+fn format_candidate(candidate: &Option<datachannel_wrapper::SelectedCandidate>) -> String {
+    let Some(candidate) = candidate else {
+        return "-".to_string();
+    };
+    format!(
+        "{} ({})",
+        candidate.address,
+        match candidate.candidate_type {
+            datachannel_wrapper::CandidateType::Host => "host",
+            datachannel_wrapper::CandidateType::ServerReflexive => "srflx",
+            datachannel_wrapper::CandidateType::PeerReflexive => "prflx",
+            datachannel_wrapper::CandidateType::Relay => "relay",
+        }
+    )
+}
+
+fn show_connection(ui: &mut egui::Ui, session: &session::Session) {
+    let session::Mode::PvP(pvp) = session.mode() else {
+        ui.label("Not in a netplay session.");
+        return;
+    };
+
+    // Keep the counters ticking.
+    ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+
+    let latency = sync::block_on(pvp.latency());
+    let stats = pvp.connection_stats();
+
+    egui::Grid::new("debug-connection-grid").num_columns(2).show(ui, |ui| {
+        // Measured by our own pings: libdatachannel doesn't hand out the transport's round trip time.
+        ui.strong("Ping");
+        ui.monospace(format!("{}ms", latency.as_millis()));
+        ui.end_row();
+
+        let Some(stats) = stats else {
+            ui.strong("Transport");
+            ui.monospace("LAN (TCP)");
+            ui.end_row();
+            return;
+        };
+
+        ui.strong("Transport");
+        ui.monospace(if stats.is_relayed() {
+            "WebRTC (relay)"
+        } else {
+            "WebRTC (direct)"
+        });
+        ui.end_row();
+
+        ui.strong("Local candidate");
+        ui.monospace(format_candidate(&stats.local_candidate));
+        ui.end_row();
+
+        ui.strong("Remote candidate");
+        ui.monospace(format_candidate(&stats.remote_candidate));
+        ui.end_row();
+
+        ui.strong("Sent");
+        ui.monospace(format_bytes(stats.bytes_sent));
+        ui.end_row();
+
+        ui.strong("Received");
+        ui.monospace(format_bytes(stats.bytes_received));
+        ui.end_row();
+
+        ui.strong("Buffered");
+        ui.monospace(format_bytes(stats.buffered_amount as u64));
+        ui.end_row();
+    });
+}
+
 pub fn show(
     ctx: &egui::Context,
     language: &unic_langid::LanguageIdentifier,
@@ -25,13 +114,19 @@ pub fn show(
         .id(egui::Id::new("debug"))
         .open(&mut open)
         .show(ctx, |ui| {
+            let state = state.as_mut().unwrap();
+
             ui.horizontal(|ui| {
-                let _ = ui.selectable_label(true, "Memory");
+                ui.selectable_value(&mut state.tab, Tab::Memory, "Memory");
+                ui.selectable_value(&mut state.tab, Tab::Connection, "Connection");
             });
 
             ui.separator();
 
-            let state = state.as_mut().unwrap();
+            if state.tab == Tab::Connection {
+                show_connection(ui, session);
+                return;
+            }
 
             let mut jumping = false;
             ui.horizontal(|ui| {
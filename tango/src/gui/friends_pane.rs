@@ -0,0 +1,230 @@
+use crate::{config, gui, i18n, presence, randomcode};
+use fluent_templates::Loader;
+
+pub struct State {
+    add_name: String,
+    add_identity: String,
+}
+
+impl State {
+    pub fn new() -> Self {
+        Self {
+            add_name: String::new(),
+            add_identity: String::new(),
+        }
+    }
+}
+
+fn status_label(language: &unic_langid::LanguageIdentifier, status: presence::Status) -> String {
+    i18n::LOCALES
+        .lookup(
+            language,
+            match status {
+                presence::Status::Offline => "friends-status.offline",
+                presence::Status::Online => "friends-status.online",
+                presence::Status::InLobby => "friends-status.in-lobby",
+                presence::Status::InMatch => "friends-status.in-match",
+            },
+        )
+        .unwrap()
+}
+
+fn status_color(status: presence::Status) -> egui::Color32 {
+    match status {
+        presence::Status::Offline => egui::Color32::GRAY,
+        presence::Status::Online => egui::Color32::from_rgb(0x4c, 0xaf, 0x50),
+        presence::Status::InLobby => egui::Color32::from_rgb(0xff, 0xc1, 0x07),
+        presence::Status::InMatch => egui::Color32::from_rgb(0xf4, 0x43, 0x36),
+    }
+}
+
+pub fn show(
+    ui: &mut egui::Ui,
+    config: &mut config::Config,
+    shared_root_state: &mut gui::SharedRootState,
+    state: &mut State,
+    init_link_code: &mut Option<String>,
+) {
+    let language = config.language.clone();
+    let presence_client = &shared_root_state.presence_client;
+
+    // Presence updates arrive in the background, so keep polling while we're visible.
+    ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+
+    egui::TopBottomPanel::bottom("friends-bottom-pane").show_inside(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let identity = state.add_identity.trim().to_lowercase();
+                if ui
+                    .add_enabled(
+                        !identity.is_empty() && !config.friends.iter().any(|f| f.identity == identity),
+                        egui::Button::new(format!(
+                            "➕ {}",
+                            i18n::LOCALES.lookup(&language, "friends-add").unwrap()
+                        )),
+                    )
+                    .clicked()
+                {
+                    config.friends.push(config::Friend {
+                        identity,
+                        name: state.add_name.trim().to_string(),
+                    });
+                    state.add_identity.clear();
+                    state.add_name.clear();
+                }
+
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.add_identity)
+                        .hint_text(i18n::LOCALES.lookup(&language, "friends-add-code").unwrap())
+                        .desired_width(200.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut state.add_name)
+                        .hint_text(i18n::LOCALES.lookup(&language, "friends-add-name").unwrap())
+                        .desired_width(f32::INFINITY),
+                );
+            });
+        });
+    });
+
+    egui::CentralPanel::default()
+        .frame(egui::Frame::none().inner_margin(egui::Margin::same(8.0)))
+        .show_inside(ui, |ui| {
+            if !config.enable_presence {
+                ui.label(i18n::LOCALES.lookup(&language, "friends-disabled").unwrap());
+            } else if let Some(identity) = presence_client.identity() {
+                ui.horizontal(|ui| {
+                    ui.strong(i18n::LOCALES.lookup(&language, "friends-your-code").unwrap());
+                    ui.monospace(&identity);
+                    if ui
+                        .button(format!(
+                            "📋 {}",
+                            i18n::LOCALES.lookup(&language, "friends-copy").unwrap()
+                        ))
+                        .clicked()
+                    {
+                        let _ = shared_root_state.clipboard.set_text(identity.clone());
+                    }
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(i18n::LOCALES.lookup(&language, "friends-connecting").unwrap());
+                });
+            }
+
+            for challenge in presence_client.incoming_challenges() {
+                let name = config
+                    .friends
+                    .iter()
+                    .find(|f| f.identity == challenge.identity && !f.name.is_empty())
+                    .map(|f| f.name.clone())
+                    .unwrap_or_else(|| challenge.nickname.clone());
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            i18n::LOCALES
+                                .lookup_with_args(
+                                    &language,
+                                    "friends-challenge-received",
+                                    &std::collections::HashMap::from([("nickname", name.into())]),
+                                )
+                                .unwrap(),
+                        );
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui
+                                .button(format!(
+                                    "❎ {}",
+                                    i18n::LOCALES.lookup(&language, "friends-challenge-decline").unwrap()
+                                ))
+                                .clicked()
+                            {
+                                presence_client.dismiss_challenge(&challenge.identity);
+                            }
+                            if ui
+                                .button(format!(
+                                    "🥊 {}",
+                                    i18n::LOCALES.lookup(&language, "friends-challenge-accept").unwrap()
+                                ))
+                                .clicked()
+                            {
+                                presence_client.dismiss_challenge(&challenge.identity);
+                                *init_link_code = Some(challenge.link_code.clone());
+                            }
+                        });
+                    });
+                });
+            }
+
+            ui.separator();
+
+            if config.friends.is_empty() {
+                ui.label(i18n::LOCALES.lookup(&language, "friends-empty").unwrap());
+                return;
+            }
+
+            let mut to_remove = None;
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .id_source("friends-pane-list")
+                .show(ui, |ui| {
+                    egui::Grid::new("friends-pane-grid")
+                        .num_columns(4)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for (i, friend) in config.friends.iter().enumerate() {
+                                let friend_status = presence_client.friend_status(&friend.identity);
+                                let status = friend_status
+                                    .as_ref()
+                                    .map(|s| s.status)
+                                    .unwrap_or(presence::Status::Offline);
+
+                                ui.label(egui::RichText::new("⏺").color(status_color(status)))
+                                    .on_hover_text(status_label(&language, status));
+
+                                ui.vertical(|ui| {
+                                    let nickname = friend_status.as_ref().map(|s| s.nickname.as_str()).unwrap_or("");
+                                    ui.strong(if !friend.name.is_empty() {
+                                        friend.name.as_str()
+                                    } else {
+                                        nickname
+                                    });
+                                    ui.small(&friend.identity);
+                                });
+
+                                ui.label(status_label(&language, status));
+
+                                ui.horizontal(|ui| {
+                                    if ui
+                                        .add_enabled(
+                                            status == presence::Status::Online && init_link_code.is_none(),
+                                            egui::Button::new(format!(
+                                                "🥊 {}",
+                                                i18n::LOCALES.lookup(&language, "friends-challenge").unwrap()
+                                            )),
+                                        )
+                                        .clicked()
+                                    {
+                                        let link_code = randomcode::generate(&language);
+                                        presence_client.send_challenge(&friend.identity, &link_code);
+                                        *init_link_code = Some(link_code);
+                                    }
+
+                                    if ui
+                                        .button("🗑️")
+                                        .on_hover_text(i18n::LOCALES.lookup(&language, "friends-remove").unwrap())
+                                        .clicked()
+                                    {
+                                        to_remove = Some(i);
+                                    }
+                                });
+                                ui.end_row();
+                            }
+                        });
+                });
+
+            if let Some(i) = to_remove {
+                config.friends.remove(i);
+            }
+        });
+}
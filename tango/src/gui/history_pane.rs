@@ -0,0 +1,409 @@
+use crate::{game, i18n, patch, replay_index, rom, scanner, sync};
+use fluent_templates::Loader;
+
+const INITIAL_RATING: f32 = 1500.0;
+const K_FACTOR: f32 = 32.0;
+
+/// How many evaluated outcomes to collect before writing them to the index.
+const OUTCOMES_PER_SAVE: usize = 32;
+
+struct Round {
+    path: std::path::PathBuf,
+    number: u32,
+    outcome: Option<tango_pvp::stepper::BattleOutcome>,
+}
+
+struct Match {
+    ts: u64,
+    game_family: &'static str,
+    nickname: String,
+    link_code: String,
+    rounds: Vec<Round>,
+}
+
+impl Match {
+    /// The outcome of the match, if every round in it has been evaluated.
+    fn outcome(&self) -> Option<tango_pvp::stepper::BattleOutcome> {
+        let mut wins = 0;
+        let mut losses = 0;
+        for round in &self.rounds {
+            match round.outcome? {
+                tango_pvp::stepper::BattleOutcome::Win => wins += 1,
+                tango_pvp::stepper::BattleOutcome::Loss => losses += 1,
+                tango_pvp::stepper::BattleOutcome::Draw => {}
+            }
+        }
+        Some(match wins.cmp(&losses) {
+            std::cmp::Ordering::Greater => tango_pvp::stepper::BattleOutcome::Win,
+            std::cmp::Ordering::Less => tango_pvp::stepper::BattleOutcome::Loss,
+            std::cmp::Ordering::Equal => tango_pvp::stepper::BattleOutcome::Draw,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Record {
+    wins: usize,
+    losses: usize,
+    draws: usize,
+
+    /// Positive for a run of wins, negative for a run of losses.
+    streak: i32,
+}
+
+impl Record {
+    fn add(&mut self, outcome: tango_pvp::stepper::BattleOutcome) {
+        match outcome {
+            tango_pvp::stepper::BattleOutcome::Win => {
+                self.wins += 1;
+                self.streak = self.streak.max(0) + 1;
+            }
+            tango_pvp::stepper::BattleOutcome::Loss => {
+                self.losses += 1;
+                self.streak = self.streak.min(0) - 1;
+            }
+            tango_pvp::stepper::BattleOutcome::Draw => {
+                self.draws += 1;
+                self.streak = 0;
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct History {
+    /// Newest first.
+    matches: Vec<Match>,
+    records: std::collections::BTreeMap<String, Record>,
+    ratings: std::collections::BTreeMap<&'static str, f32>,
+}
+
+impl History {
+    fn build(entries: &[(std::path::PathBuf, replay_index::Entry)]) -> Self {
+        let mut matches: Vec<Match> = vec![];
+
+        // Entries come newest first, so walk them backwards to put rounds together in the order they were played.
+        for (path, entry) in entries.iter().rev() {
+            let metadata = &entry.metadata;
+            let Some(remote_side) = metadata.remote_side.as_ref() else {
+                continue;
+            };
+            let Some(game_info) = metadata.local_side.as_ref().and_then(|side| side.game_info.as_ref()) else {
+                continue;
+            };
+            let Some(game) = game::find_by_family_and_variant(&game_info.rom_family, game_info.rom_variant as u8)
+            else {
+                continue;
+            };
+            let game_family = game.gamedb_entry().family_and_variant.0;
+
+            let round = Round {
+                path: path.clone(),
+                number: metadata.round,
+                outcome: entry.outcome,
+            };
+
+            if let Some(last) = matches.last_mut() {
+                if last.link_code == metadata.link_code
+                    && last.nickname == remote_side.nickname
+                    && last.game_family == game_family
+                    && last.rounds.last().is_some_and(|r| r.number < round.number)
+                {
+                    last.rounds.push(round);
+                    continue;
+                }
+            }
+
+            matches.push(Match {
+                ts: metadata.ts,
+                game_family,
+                nickname: remote_side.nickname.clone(),
+                link_code: metadata.link_code.clone(),
+                rounds: vec![round],
+            });
+        }
+
+        let mut records = std::collections::BTreeMap::<String, Record>::new();
+        let mut ratings = std::collections::BTreeMap::new();
+        let mut opponent_ratings = std::collections::HashMap::new();
+        for m in &matches {
+            let Some(outcome) = m.outcome() else {
+                continue;
+            };
+            records.entry(m.nickname.clone()).or_default().add(outcome);
+
+            let rating = ratings.entry(m.game_family).or_insert(INITIAL_RATING);
+            let opponent_rating = opponent_ratings
+                .entry((m.game_family, m.nickname.as_str()))
+                .or_insert(INITIAL_RATING);
+            let expected = 1.0 / (1.0 + 10.0f32.powf((*opponent_rating - *rating) / 400.0));
+            let score = match outcome {
+                tango_pvp::stepper::BattleOutcome::Win => 1.0,
+                tango_pvp::stepper::BattleOutcome::Loss => 0.0,
+                tango_pvp::stepper::BattleOutcome::Draw => 0.5,
+            };
+            *rating += K_FACTOR * (score - expected);
+            *opponent_rating -= K_FACTOR * (score - expected);
+        }
+
+        matches.reverse();
+        Self {
+            matches,
+            records,
+            ratings,
+        }
+    }
+}
+
+pub struct State {
+    history_scanner: scanner::Scanner<History>,
+    index: replay_index::Handle,
+    remaining: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+    selected_opponent: Option<String>,
+}
+
+impl State {
+    pub fn new(index: replay_index::Handle) -> Self {
+        Self {
+            history_scanner: scanner::Scanner::new(),
+            index,
+            remaining: std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+            selected_opponent: None,
+        }
+    }
+
+    /// Rebuilds the history, then works out the outcomes of any replays that haven't been evaluated yet.
+    pub fn rescan(
+        &self,
+        ctx: &egui::Context,
+        replays_path: &std::path::Path,
+        roms_scanner: rom::Scanner,
+        patches_path: &std::path::Path,
+    ) {
+        tokio::task::spawn_blocking({
+            let history_scanner = self.history_scanner.clone();
+            let index = self.index.clone();
+            let remaining = self.remaining.clone();
+            let replays_path = replays_path.to_path_buf();
+            let patches_path = patches_path.to_path_buf();
+            let egui_ctx = ctx.clone();
+            move || {
+                let entries = index.update(&replays_path);
+                history_scanner.rescan(|| Some(History::build(&entries)));
+                egui_ctx.request_repaint();
+
+                let unevaluated = entries
+                    .into_iter()
+                    .filter(|(path, entry)| {
+                        entry.is_complete && entry.outcome.is_none() && index.start_evaluating(path)
+                    })
+                    .collect::<Vec<_>>();
+                if unevaluated.is_empty() {
+                    return;
+                }
+
+                remaining.store(unevaluated.len(), std::sync::atomic::Ordering::Relaxed);
+                // Replays are only let go of once their outcome is saved. The ones that fail to evaluate stay marked
+                // as in progress so we don't keep retrying them.
+                let save_outcomes = |outcomes: &mut Vec<(std::path::PathBuf, tango_pvp::stepper::BattleOutcome)>| {
+                    index.set_outcomes(outcomes);
+                    for (path, _) in outcomes.drain(..) {
+                        index.finish_evaluating(&path);
+                    }
+                };
+                let mut outcomes = vec![];
+                for (path, entry) in unevaluated {
+                    if let Some(outcome) = evaluate(&path, &entry.metadata, &roms_scanner.read(), &patches_path) {
+                        outcomes.push((path, outcome));
+                    }
+                    if outcomes.len() >= OUTCOMES_PER_SAVE {
+                        save_outcomes(&mut outcomes);
+                    }
+                    remaining.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
+                    egui_ctx.request_repaint();
+                }
+                save_outcomes(&mut outcomes);
+
+                history_scanner.rescan(|| Some(History::build(&index.update(&replays_path))));
+                egui_ctx.request_repaint();
+            }
+        });
+    }
+}
+
+fn evaluate(
+    path: &std::path::Path,
+    metadata: &tango_pvp::replay::Metadata,
+    roms: &std::collections::HashMap<&'static (dyn game::Game + Send + Sync), Vec<u8>>,
+    patches_path: &std::path::Path,
+) -> Option<tango_pvp::stepper::BattleOutcome> {
+    let game_info = metadata.local_side.as_ref()?.game_info.as_ref()?;
+    let (game, rom) = patch::rom_for_game_info(game_info, roms, patches_path)?;
+
+    let replay = match std::fs::File::open(path).and_then(|mut f| tango_pvp::replay::Replay::decode(&mut f)) {
+        Ok(replay) => replay,
+        Err(e) => {
+            log::error!("failed to load replay {}: {:?}", path.display(), e);
+            return None;
+        }
+    };
+
+    let hooks = tango_pvp::hooks::hooks_for_gamedb_entry(game.gamedb_entry()).unwrap();
+    match sync::block_on(tango_pvp::eval::eval(&replay, &rom, hooks, Vec::new)) {
+        Ok((result, _, _)) => Some(result.outcome),
+        Err(e) => {
+            log::error!("failed to evaluate replay {}: {:?}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn outcome_label(
+    language: &unic_langid::LanguageIdentifier,
+    outcome: Option<tango_pvp::stepper::BattleOutcome>,
+) -> String {
+    i18n::LOCALES
+        .lookup(
+            language,
+            match outcome {
+                Some(tango_pvp::stepper::BattleOutcome::Win) => "history-outcome.win",
+                Some(tango_pvp::stepper::BattleOutcome::Loss) => "history-outcome.loss",
+                Some(tango_pvp::stepper::BattleOutcome::Draw) => "history-outcome.draw",
+                None => "history-outcome.unknown",
+            },
+        )
+        .unwrap()
+}
+
+fn game_family_label(language: &unic_langid::LanguageIdentifier, game_family: &str) -> String {
+    i18n::LOCALES
+        .lookup(language, &format!("game-{}.short", game_family))
+        .unwrap()
+}
+
+/// Shows the pane. Returns the path of a replay the user wants to open in the replays pane, if any.
+pub fn show(
+    ui: &mut egui::Ui,
+    language: &unic_langid::LanguageIdentifier,
+    state: &mut State,
+) -> Option<std::path::PathBuf> {
+    let history = state.history_scanner.read();
+    let mut open_replay = None;
+
+    egui::SidePanel::left("history-pane-left-panel")
+        .frame(egui::Frame::default().inner_margin(egui::Margin {
+            right: 8.0,
+            ..Default::default()
+        }))
+        .show_inside(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .id_source("history-pane-left")
+                .show(ui, |ui| {
+                    ui.heading(i18n::LOCALES.lookup(language, "history-ratings").unwrap());
+                    egui::Grid::new("history-pane-ratings").num_columns(2).show(ui, |ui| {
+                        for (game_family, rating) in &history.ratings {
+                            ui.label(game_family_label(language, game_family));
+                            ui.label(format!("{:.0}", rating));
+                            ui.end_row();
+                        }
+                    });
+
+                    ui.separator();
+
+                    ui.heading(i18n::LOCALES.lookup(language, "history-opponents").unwrap());
+                    ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                        if ui
+                            .selectable_label(
+                                state.selected_opponent.is_none(),
+                                i18n::LOCALES.lookup(language, "history-all-opponents").unwrap(),
+                            )
+                            .clicked()
+                        {
+                            state.selected_opponent = None;
+                        }
+
+                        for (nickname, record) in &history.records {
+                            let selected = state.selected_opponent.as_ref() == Some(nickname);
+                            if ui
+                                .selectable_label(
+                                    selected,
+                                    i18n::LOCALES
+                                        .lookup_with_args(
+                                            language,
+                                            "history-record",
+                                            &std::collections::HashMap::from([
+                                                ("nickname", nickname.clone().into()),
+                                                ("wins", record.wins.into()),
+                                                ("losses", record.losses.into()),
+                                                ("draws", record.draws.into()),
+                                                ("streak", record.streak.into()),
+                                            ]),
+                                        )
+                                        .unwrap(),
+                                )
+                                .clicked()
+                            {
+                                state.selected_opponent = if selected { None } else { Some(nickname.clone()) };
+                            }
+                        }
+                    });
+                });
+        });
+
+    egui::CentralPanel::default().show_inside(ui, |ui| {
+        let remaining = state.remaining.load(std::sync::atomic::Ordering::Relaxed);
+        if state.history_scanner.is_scanning() || remaining > 0 {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(
+                    i18n::LOCALES
+                        .lookup_with_args(
+                            language,
+                            "history-evaluating",
+                            &std::collections::HashMap::from([("remaining", remaining.into())]),
+                        )
+                        .unwrap(),
+                );
+            });
+        }
+
+        egui::ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .id_source("history-pane-matches")
+            .show(ui, |ui| {
+                egui::Grid::new("history-pane-matches-grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for m in history.matches.iter().filter(|m| {
+                            state.selected_opponent.is_none() || state.selected_opponent.as_ref() == Some(&m.nickname)
+                        }) {
+                            let ts = std::time::UNIX_EPOCH + std::time::Duration::from_millis(m.ts);
+                            ui.label(chrono::DateTime::<chrono::Local>::from(ts).to_string());
+                            ui.label(game_family_label(language, m.game_family));
+                            ui.label(m.nickname.as_str());
+                            ui.strong(outcome_label(language, m.outcome()));
+                            ui.horizontal(|ui| {
+                                for round in &m.rounds {
+                                    if ui
+                                        .small_button(format!(
+                                            "{} ({})",
+                                            round.number,
+                                            outcome_label(language, round.outcome)
+                                        ))
+                                        .on_hover_text(round.path.display().to_string())
+                                        .clicked()
+                                    {
+                                        open_replay = Some(round.path.clone());
+                                    }
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+    });
+
+    open_replay
+}
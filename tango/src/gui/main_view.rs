@@ -1,4 +1,4 @@
-use crate::{config, gui, i18n, patch, sync, updater};
+use crate::{config, gui, i18n, patch, replay_index, sync, updater};
 use fluent_templates::Loader;
 
 pub struct State {
@@ -7,17 +7,22 @@ pub struct State {
     play_pane: gui::play_pane::State,
     patches_pane: gui::patches_pane::State,
     replays_pane: gui::replays_pane::State,
+    history_pane: gui::history_pane::State,
+    friends_pane: gui::friends_pane::State,
     updater: Option<gui::updater_window::State>,
 }
 
 impl State {
     pub fn new(selection: Option<gui::save_select_view::Selection>, updater: bool) -> Self {
+        let replay_index = replay_index::Handle::new();
         Self {
             tab: Tab::Play,
             patch_selection: None,
             play_pane: gui::play_pane::State::new(selection),
             patches_pane: gui::patches_pane::State::new(),
-            replays_pane: gui::replays_pane::State::new(),
+            replays_pane: gui::replays_pane::State::new(replay_index.clone()),
+            history_pane: gui::history_pane::State::new(replay_index),
+            friends_pane: gui::friends_pane::State::new(),
             updater: if updater {
                 Some(gui::updater_window::State::new())
             } else {
@@ -32,6 +37,8 @@ enum Tab {
     Play,
     Patches,
     Replays,
+    History,
+    Friends,
 }
 
 pub fn show(
@@ -111,7 +118,25 @@ pub fn show(
                                 .on_hover_text_at_pointer(i18n::LOCALES.lookup(&config.language, "replays").unwrap())
                                 .clicked()
                             {
-                                state.replays_pane.rescan(ui.ctx(), &config.replays_path());
+                                state.replays_pane.rescan(
+                                    ui.ctx(),
+                                    &config.replays_path(),
+                                    shared_root_state.roms_scanner.clone(),
+                                    &config.patches_path(),
+                                );
+                            }
+
+                            if ui
+                                .selectable_value(&mut state.tab, Tab::History, "📈")
+                                .on_hover_text_at_pointer(i18n::LOCALES.lookup(&config.language, "history").unwrap())
+                                .clicked()
+                            {
+                                state.history_pane.rescan(
+                                    ui.ctx(),
+                                    &config.replays_path(),
+                                    shared_root_state.roms_scanner.clone(),
+                                    &config.patches_path(),
+                                );
                             }
 
                             if ui
@@ -130,6 +155,17 @@ pub fn show(
                                     }
                                 });
                             }
+
+                            ui.selectable_value(
+                                &mut state.tab,
+                                Tab::Friends,
+                                if shared_root_state.presence_client.incoming_challenges().is_empty() {
+                                    "👥"
+                                } else {
+                                    "👥❗"
+                                },
+                            )
+                            .on_hover_text_at_pointer(i18n::LOCALES.lookup(&config.language, "friends").unwrap());
                         });
                     });
                 });
@@ -167,6 +203,21 @@ pub fn show(
             Tab::Replays => {
                 gui::replays_pane::show(ui, config, shared_root_state, &mut state.replays_pane);
             }
+            Tab::History => {
+                if let Some(path) = gui::history_pane::show(ui, &config.language, &mut state.history_pane) {
+                    state.tab = Tab::Replays;
+                    state.replays_pane.rescan(
+                        ui.ctx(),
+                        &config.replays_path(),
+                        shared_root_state.roms_scanner.clone(),
+                        &config.patches_path(),
+                    );
+                    state.replays_pane.select_path(path);
+                }
+            }
+            Tab::Friends => {
+                gui::friends_pane::show(ui, config, shared_root_state, &mut state.friends_pane, init_link_code);
+            }
             Tab::Patches => {
                 let patches_path = config.patches_path().clone();
                 gui::patches_pane::show(
@@ -1,4 +1,6 @@
-use crate::{audio, config, discord, game, gui, i18n, net, patch, randomcode, rom, session, stats, sync};
+use crate::{
+    audio, config, discord, game, gui, i18n, lan, net, patch, presence, randomcode, rom, session, stats, sync,
+};
 use fluent_templates::Loader;
 use rand::RngCore;
 use sha3::digest::{ExtendableOutput, Update};
@@ -324,6 +326,26 @@ fn make_commitment(buf: &[u8]) -> [u8; 16] {
     commitment
 }
 
+/// Both sides of a manual connection see the same offer, so a code derived from it names the session the same way on
+/// each end. Whitespace is ignored, since the offer may have been wrapped when it was pasted.
+fn make_manual_session_code(offer: &str) -> String {
+    let mut shake128 = sha3::Shake128::default();
+    shake128.update(b"tango:manual:");
+    shake128.update(
+        offer
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .as_bytes(),
+    );
+    let mut code = [0u8; 4];
+    shake128.finalize_xof_into(&mut code);
+    format!(
+        "manual-{}",
+        code.iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    )
+}
+
 impl Lobby {
     async fn uncommit(&mut self) -> Result<(), anyhow::Error> {
         let sender = if let Some(sender) = self.sender.as_mut() {
@@ -596,8 +618,8 @@ async fn run_connection_task(
     session: std::sync::Arc<parking_lot::Mutex<Option<session::Session>>>,
     roms_scanner: rom::Scanner,
     patches_scanner: patch::Scanner,
-    matchmaking_addr: String,
-    link_code: String,
+    method: ConnectionMethod,
+    mut link_code: String,
     nickname: String,
     patches_path: std::path::PathBuf,
     replays_path: std::path::PathBuf,
@@ -612,41 +634,108 @@ async fn run_connection_task(
                 let connection_task = connection_task.clone();
                 let cancellation_token = cancellation_token.clone();
                 async move {
-                    *connection_task.lock().await =
-                        Some(ConnectionTask::InProgress {
-                            state: ConnectionState::Signaling,
-                            cancellation_token:
-                                cancellation_token.clone(),
-                        });
-                    const OPEN_TIMEOUT: std::time::Duration =
-                        std::time::Duration::from_secs(30);
-                    let use_relay = {
-                        let config = config.read();
-                        config.use_relay
-                    };
-                    let pending_conn = tokio::time::timeout(
-                        OPEN_TIMEOUT,
-                        tango_signaling::connect(
-                            &matchmaking_addr,
-                            &link_code,
-                            use_relay,
-                            crate::net::protocol::VERSION as u32,
-                        ),
-                    )
-                    .await.map_err(|e| std::io::Error::new(std::io::ErrorKind::TimedOut, e))??;
+                    let (mut sender, mut receiver, input_dc, peer_conn, is_offerer) = match method {
+                        ConnectionMethod::Signaling { matchmaking_addr } => {
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::Signaling,
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
+                            const OPEN_TIMEOUT: std::time::Duration =
+                                std::time::Duration::from_secs(30);
+                            let use_relay = {
+                                let config = config.read();
+                                config.use_relay
+                            };
+                            let pending_conn = tokio::time::timeout(
+                                OPEN_TIMEOUT,
+                                tango_signaling::connect(
+                                    &matchmaking_addr,
+                                    &link_code,
+                                    use_relay,
+                                    crate::net::protocol::VERSION as u32,
+                                ),
+                            )
+                            .await.map_err(|e| std::io::Error::new(std::io::ErrorKind::TimedOut, e))??;
 
-                    *connection_task.lock().await =
-                        Some(ConnectionTask::InProgress {
-                            state: ConnectionState::Waiting,
-                            cancellation_token:
-                                cancellation_token.clone(),
-                        });
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::Waiting,
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
+
+                            let (dc, input_dc, peer_conn) = pending_conn.await?;
+                            let is_offerer = peer_conn.local_description().unwrap().sdp_type == datachannel_wrapper::SdpType::Offer;
+                            let (dc_tx, dc_rx) = dc.split();
+                            (net::Sender::new(dc_tx), net::Receiver::new(dc_rx), Some(input_dc), Some(peer_conn), is_offerer)
+                        }
+                        ConnectionMethod::LanHost => {
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::Waiting,
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
 
-                    let (dc, peer_conn) = pending_conn.await?;
-                    let (dc_tx, dc_rx) = dc.split();
-                    let mut sender = net::Sender::new(dc_tx);
-                    let mut receiver = net::Receiver::new(dc_rx);
-                    net::negotiate(&mut sender, &mut receiver).await?;
+                            let stream = lan::host(&nickname).await?;
+                            link_code = lan::session_code(stream.local_addr()?);
+                            let (tcp_rx, tcp_tx) = stream.into_split();
+                            (net::Sender::new_tcp(tcp_tx), net::Receiver::new_tcp(tcp_rx), None, None, true)
+                        }
+                        ConnectionMethod::LanJoin { addr } => {
+                            let stream = lan::join(addr).await?;
+                            link_code = lan::session_code(stream.peer_addr()?);
+                            let (tcp_rx, tcp_tx) = stream.into_split();
+                            (net::Sender::new_tcp(tcp_tx), net::Receiver::new_tcp(tcp_rx), None, None, false)
+                        }
+                        ConnectionMethod::ManualOffer => {
+                            let offerer = tango_signaling::manual::Offerer::new().await?;
+                            link_code = make_manual_session_code(&offerer.offer());
+                            let (answer_tx, mut answer_rx) = tokio::sync::mpsc::channel(1);
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::ManualOffer { offer: offerer.offer(), answer_tx },
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
+                            egui_ctx.request_repaint();
+
+                            let answer = answer_rx.recv().await.ok_or_else(|| anyhow::anyhow!("no answer received"))?;
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::Waiting,
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
+                            egui_ctx.request_repaint();
+
+                            let (dc, input_dc, peer_conn) = offerer.connect(&answer).await?;
+                            let (dc_tx, dc_rx) = dc.split();
+                            (net::Sender::new(dc_tx), net::Receiver::new(dc_rx), Some(input_dc), Some(peer_conn), true)
+                        }
+                        ConnectionMethod::ManualAnswer { offer } => {
+                            let answerer = tango_signaling::manual::Answerer::new(&offer).await?;
+                            link_code = make_manual_session_code(&offer);
+                            *connection_task.lock().await =
+                                Some(ConnectionTask::InProgress {
+                                    state: ConnectionState::ManualAnswer { answer: answerer.answer() },
+                                    cancellation_token:
+                                        cancellation_token.clone(),
+                                });
+                            egui_ctx.request_repaint();
+
+                            let (dc, input_dc, peer_conn) = answerer.connect().await?;
+                            let (dc_tx, dc_rx) = dc.split();
+                            (net::Sender::new(dc_tx), net::Receiver::new(dc_rx), Some(input_dc), Some(peer_conn), false)
+                        }
+                    };
+                    let local_signing_public_key = {
+                        let config = config.read();
+                        config.signing_key().map(|k| k.public_key()).unwrap_or_default()
+                    };
+                    let remote_signing_public_key = net::negotiate(&mut sender, &mut receiver, &local_signing_public_key).await?;
 
                     let default_match_type = {
                         let config = config.read();
@@ -819,7 +908,6 @@ async fn run_connection_task(
                     }
 
                     log::info!("starting session");
-                    let is_offerer = peer_conn.local_description().unwrap().sdp_type == datachannel_wrapper::SdpType::Offer;
                     {
                         *session.lock() = Some(session::Session::new_pvp(
                             config.clone(),
@@ -843,9 +931,11 @@ async fn run_connection_task(
                             &remote_patch_overrides,
                             &remote_selection.rom,
                             remote_selection.game.save_from_wram(&remote_negotiated_state.save_data)?,
+                            remote_signing_public_key,
                             emu_tps_counter.clone(),
                             sender,
                             receiver,
+                            input_dc,
                             peer_conn,
                             is_offerer,
                             replays_path,
@@ -874,6 +964,14 @@ async fn run_connection_task(
     }
 }
 
+enum ConnectionMethod {
+    Signaling { matchmaking_addr: String },
+    LanHost,
+    LanJoin { addr: std::net::SocketAddr },
+    ManualOffer,
+    ManualAnswer { offer: String },
+}
+
 #[derive(thiserror::Error, Debug)]
 enum ConnectionError {
     #[error(transparent)]
@@ -901,6 +999,13 @@ enum ConnectionState {
     Starting,
     Signaling,
     Waiting,
+    ManualOffer {
+        offer: String,
+        answer_tx: tokio::sync::mpsc::Sender<String>,
+    },
+    ManualAnswer {
+        answer: String,
+    },
     InLobby(std::sync::Arc<tokio::sync::Mutex<Lobby>>),
 }
 
@@ -909,6 +1014,14 @@ pub struct State {
     show_link_code: bool,
     connection_task: std::sync::Arc<tokio::sync::Mutex<Option<ConnectionTask>>>,
     save_select_state: gui::save_select_view::State,
+    lan_browser: Option<lan::Browser>,
+    manual: ManualState,
+}
+
+#[derive(Default)]
+struct ManualState {
+    open: bool,
+    input: String,
 }
 
 impl State {
@@ -918,6 +1031,8 @@ impl State {
             show_link_code: false,
             connection_task: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
             save_select_state: gui::save_select_view::State::new(selection),
+            lan_browser: None,
+            manual: Default::default(),
         }
     }
 }
@@ -1252,6 +1367,8 @@ fn show_bottom_pane(
     connection_task_arc: std::sync::Arc<tokio::sync::Mutex<Option<ConnectionTask>>>,
     link_code: &mut String,
     show_link_code: &mut bool,
+    lan_browser: &mut Option<lan::Browser>,
+    manual_state: &mut ManualState,
     init_link_code: &mut Option<String>,
 ) {
     let selection = &mut shared_root_state.selection;
@@ -1317,11 +1434,80 @@ fn show_bottom_pane(
     }
 
     let discord_client = &shared_root_state.discord_client;
+    let presence_client = &shared_root_state.presence_client;
     let roms = shared_root_state.roms_scanner.read();
     let patches = shared_root_state.patches_scanner.read();
 
+    if config.lan_discovery {
+        if lan_browser.is_none() {
+            *lan_browser = Some(lan::Browser::new(ui.ctx().clone()));
+        }
+    } else {
+        *lan_browser = None;
+    }
+
+    let mut direct_method = None;
+
     egui::TopBottomPanel::bottom("play-bottom-pane").show_inside(ui, |ui| {
         ui.vertical(|ui| {
+            if let (None, Some(lan_browser)) = (connection_task.as_ref(), lan_browser.as_ref()) {
+                let lobbies = lan_browser.lobbies();
+                if !lobbies.is_empty() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.strong(i18n::LOCALES.lookup(&config.language, "play-lan-lobbies").unwrap());
+                        for lobby in lobbies {
+                            if ui
+                                .add_enabled(!error_window_open, egui::Button::new(format!("🥊 {}", lobby.nickname)))
+                                .on_hover_text(lobby.addr.to_string())
+                                .clicked()
+                            {
+                                direct_method = Some(ConnectionMethod::LanJoin { addr: lobby.addr });
+                            }
+                        }
+                    });
+                }
+                // Lobbies expire without any event to wake us up.
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+            }
+
+            if connection_task.is_none() && manual_state.open {
+                ui.horizontal(|ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add_enabled(
+                                !error_window_open,
+                                egui::Button::new(if manual_state.input.trim().is_empty() {
+                                    i18n::LOCALES
+                                        .lookup(&config.language, "play-manual-create-offer")
+                                        .unwrap()
+                                } else {
+                                    i18n::LOCALES
+                                        .lookup(&config.language, "play-manual-answer-offer")
+                                        .unwrap()
+                                }),
+                            )
+                            .clicked()
+                        {
+                            let offer = std::mem::take(&mut manual_state.input).trim().to_string();
+                            direct_method = Some(if offer.is_empty() {
+                                ConnectionMethod::ManualOffer
+                            } else {
+                                ConnectionMethod::ManualAnswer { offer }
+                            });
+                        }
+                        ui.add(
+                            egui::TextEdit::singleline(&mut manual_state.input)
+                                .hint_text(
+                                    i18n::LOCALES
+                                        .lookup(&config.language, "play-manual-paste-offer")
+                                        .unwrap(),
+                                )
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                });
+            }
+
             {
                 if let Some(ConnectionTask::InProgress {
                     state: connection_state,
@@ -1361,6 +1547,7 @@ fn show_bottom_pane(
                                     });
                                 });
                             });
+                            presence_client.set_status(presence::Status::InLobby);
                             discord_client.set_current_activity(Some(discord::make_looking_activity(
                                 link_code,
                                 &config.language,
@@ -1376,6 +1563,93 @@ fn show_bottom_pane(
                                 }),
                             )));
                         }
+                        ConnectionState::ManualOffer { offer, answer_tx } => {
+                            ui.horizontal(|ui| {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui
+                                        .button(format!(
+                                            "❎ {}",
+                                            i18n::LOCALES.lookup(&config.language, "play-cancel").unwrap()
+                                        ))
+                                        .clicked()
+                                    {
+                                        cancellation_token.cancel();
+                                    }
+
+                                    if ui
+                                        .add_enabled(
+                                            !manual_state.input.trim().is_empty(),
+                                            egui::Button::new(format!(
+                                                "🥊 {}",
+                                                i18n::LOCALES.lookup(&config.language, "play-manual-connect").unwrap()
+                                            )),
+                                        )
+                                        .clicked()
+                                    {
+                                        let answer = std::mem::take(&mut manual_state.input);
+                                        let _ = answer_tx.try_send(answer.trim().to_string());
+                                    }
+
+                                    if ui
+                                        .button(format!(
+                                            "📋 {}",
+                                            i18n::LOCALES
+                                                .lookup(&config.language, "play-manual-copy-offer")
+                                                .unwrap()
+                                        ))
+                                        .clicked()
+                                    {
+                                        let _ = shared_root_state.clipboard.set_text(offer.clone());
+                                    }
+
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut manual_state.input)
+                                            .hint_text(
+                                                i18n::LOCALES
+                                                    .lookup(&config.language, "play-manual-paste-answer")
+                                                    .unwrap(),
+                                            )
+                                            .desired_width(f32::INFINITY),
+                                    );
+                                });
+                            });
+                        }
+                        ConnectionState::ManualAnswer { answer } => {
+                            ui.horizontal(|ui| {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui
+                                        .button(format!(
+                                            "❎ {}",
+                                            i18n::LOCALES.lookup(&config.language, "play-cancel").unwrap()
+                                        ))
+                                        .clicked()
+                                    {
+                                        cancellation_token.cancel();
+                                    }
+
+                                    if ui
+                                        .button(format!(
+                                            "📋 {}",
+                                            i18n::LOCALES
+                                                .lookup(&config.language, "play-manual-copy-answer")
+                                                .unwrap()
+                                        ))
+                                        .clicked()
+                                    {
+                                        let _ = shared_root_state.clipboard.set_text(answer.clone());
+                                    }
+
+                                    ui.with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
+                                        ui.spinner();
+                                        ui.label(
+                                            i18n::LOCALES
+                                                .lookup(&config.language, "play-manual-waiting-for-offerer")
+                                                .unwrap(),
+                                        );
+                                    });
+                                });
+                            });
+                        }
                         ConnectionState::InLobby(lobby) => {
                             let mut lobby = lobby.blocking_lock();
                             if !lobby.attention_requested {
@@ -1383,6 +1657,7 @@ fn show_bottom_pane(
                                 lobby.attention_requested = true;
                             }
 
+                            presence_client.set_status(presence::Status::InLobby);
                             discord_client.set_current_activity(Some(discord::make_in_lobby_activity(
                                 &lobby.link_code,
                                 &config.language,
@@ -1404,6 +1679,7 @@ fn show_bottom_pane(
                         }
                     }
                 } else {
+                    presence_client.set_status(presence::Status::Online);
                     discord_client.set_current_activity(Some(discord::make_base_activity(None)));
                 }
             }
@@ -1454,6 +1730,26 @@ fn show_bottom_pane(
                             let _ = shared_root_state.clipboard.set_text(link_code.clone());
                         }
 
+                        if ui
+                            .add_enabled(
+                                !error_window_open,
+                                egui::SelectableLabel::new(manual_state.open, egui::RichText::new("📋")),
+                            )
+                            .on_hover_text(i18n::LOCALES.lookup(&config.language, "play-manual").unwrap())
+                            .clicked()
+                        {
+                            manual_state.open = !manual_state.open;
+                        }
+
+                        if config.lan_discovery
+                            && ui
+                                .add_enabled(!error_window_open, egui::Button::new(egui::RichText::new("📡")))
+                                .on_hover_text(i18n::LOCALES.lookup(&config.language, "play-lan-host").unwrap())
+                                .clicked()
+                        {
+                            direct_method = Some(ConnectionMethod::LanHost);
+                        }
+
                         if config.streamer_mode
                             && ui
                                 .selectable_label(*show_link_code, "👁️")
@@ -1543,13 +1839,27 @@ fn show_bottom_pane(
                         submitted = true;
                     }
 
-                    if submitted {
+                    let method = if let Some(direct_method) = direct_method.take() {
+                        Some(direct_method)
+                    } else if submitted && !link_code.is_empty() {
+                        Some(ConnectionMethod::Signaling {
+                            matchmaking_addr: if !config.matchmaking_endpoint.is_empty() {
+                                config.matchmaking_endpoint.clone()
+                            } else {
+                                config::DEFAULT_MATCHMAKING_ENDPOINT.to_string()
+                            },
+                        })
+                    } else {
+                        None
+                    };
+
+                    if submitted || method.is_some() {
                         let audio_binder = shared_root_state.audio_binder.clone();
                         let egui_ctx = ui.ctx().clone();
                         let session = shared_root_state.session.clone();
                         let emu_tps_counter = shared_root_state.emu_tps_counter.clone();
 
-                        if !link_code.is_empty() {
+                        if let Some(method) = method {
                             let cancellation_token = tokio_util::sync::CancellationToken::new();
                             *connection_task = Some(ConnectionTask::InProgress {
                                 state: ConnectionState::Starting,
@@ -1557,12 +1867,11 @@ fn show_bottom_pane(
                             });
 
                             tokio::task::spawn({
-                                let matchmaking_endpoint = if !config.matchmaking_endpoint.is_empty() {
-                                    config.matchmaking_endpoint.clone()
-                                } else {
-                                    config::DEFAULT_MATCHMAKING_ENDPOINT.to_string()
+                                // Direct connections get a code once the peer is known.
+                                let link_code = match &method {
+                                    ConnectionMethod::Signaling { .. } => link_code.to_owned(),
+                                    _ => String::new(),
                                 };
-                                let link_code = link_code.to_owned();
                                 let nickname = config.nickname.clone().unwrap_or_default();
                                 let patches_path = config.patches_path();
                                 let replays_path = config.replays_path();
@@ -1579,7 +1888,7 @@ fn show_bottom_pane(
                                         session,
                                         roms_scanner,
                                         patches_scanner,
-                                        matchmaking_endpoint,
+                                        method,
                                         link_code,
                                         nickname,
                                         patches_path,
@@ -1651,6 +1960,8 @@ pub fn show(
         connection_task_arc,
         &mut state.link_code,
         &mut state.show_link_code,
+        &mut state.lan_browser,
+        &mut state.manual,
         init_link_code,
     );
 
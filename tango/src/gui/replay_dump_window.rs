@@ -4,6 +4,28 @@ use fluent_templates::Loader;
 use super::ui_windows::UiWindowKey;
 const DEFAULT_SCALE: usize = 5;
 
+const FORMATS: &[(tango_pvp::replay::export::Format, &str)] = &[
+    (
+        tango_pvp::replay::export::Format::Ffmpeg,
+        "replays-export-format.ffmpeg",
+    ),
+    (
+        tango_pvp::replay::export::Format::Y4mWav,
+        "replays-export-format.y4m-wav",
+    ),
+    (
+        tango_pvp::replay::export::Format::PngSequence,
+        "replays-export-format.png-sequence",
+    ),
+    (tango_pvp::replay::export::Format::Gif, "replays-export-format.gif"),
+    (tango_pvp::replay::export::Format::Apng, "replays-export-format.apng"),
+];
+
+fn format_label(language: &unic_langid::LanguageIdentifier, format: tango_pvp::replay::export::Format) -> String {
+    let (_, key) = FORMATS.iter().find(|(f, _)| *f == format).unwrap();
+    i18n::LOCALES.lookup(language, key).unwrap()
+}
+
 pub struct ReplayDumpWindow {
     cancellation_token: Option<tokio_util::sync::CancellationToken>,
     output_path: std::path::PathBuf,
@@ -11,7 +33,10 @@ pub struct ReplayDumpWindow {
     remote_rom: Option<Vec<u8>>,
     replays: Vec<tango_pvp::replay::Replay>,
     scale: Option<usize>,
+    format: tango_pvp::replay::export::Format,
+    tick_range: Option<(u32, u32)>,
     disable_bgm: bool,
+    input_display: bool,
     twosided: bool,
     progress: std::sync::Arc<parking_lot::Mutex<(usize, usize)>>,
     result: std::sync::Arc<parking_lot::Mutex<Option<anyhow::Result<()>>>>,
@@ -39,7 +64,10 @@ impl ReplayDumpWindow {
             remote_rom,
             replays,
             scale: Some(DEFAULT_SCALE),
+            format: tango_pvp::replay::export::Format::Ffmpeg,
+            tick_range: None,
             disable_bgm: false,
+            input_display: false,
             twosided: false,
             progress: std::sync::Arc::new(parking_lot::Mutex::new((0, 0))),
             result: std::sync::Arc::new(parking_lot::Mutex::new(None)),
@@ -87,18 +115,21 @@ impl ReplayDumpWindow {
                                     .button(i18n::LOCALES.lookup(language, "replays-export-path.change").unwrap())
                                     .clicked()
                                 {
-                                    if let Some(path) = rfd::FileDialog::new()
+                                    let dialog = rfd::FileDialog::new()
                                         .set_directory(self.output_path.parent().unwrap_or(&std::path::PathBuf::new()))
                                         .set_file_name(
                                             self
                                                 .output_path
                                                 .file_name()
                                                 .and_then(|filename| filename.to_str())
-                                                .unwrap_or("replay.mp4"),
-                                        )
-                                        .add_filter("MP4", &["mp4"])
-                                        .save_file()
-                                    {
+                                                .unwrap_or("replay"),
+                                        );
+                                    let path = if let Some(extension) = self.format.extension() {
+                                        dialog.add_filter(&extension.to_uppercase(), &[extension]).save_file()
+                                    } else {
+                                        dialog.pick_folder()
+                                    };
+                                    if let Some(path) = path {
                                         self.output_path = path;
 
                                         if let Some(folder_path ) = self.output_path.parent() {
@@ -109,6 +140,23 @@ impl ReplayDumpWindow {
                             });
                             ui.end_row();
 
+                            ui.strong(i18n::LOCALES.lookup(language, "replays-export-format").unwrap());
+                            let previous_format = self.format;
+                            egui::ComboBox::from_id_source(("replay-dump-window-format", id))
+                                .selected_text(format_label(language, self.format))
+                                .show_ui(ui, |ui| {
+                                    for (format, _) in FORMATS {
+                                        ui.selectable_value(&mut self.format, *format, format_label(language, *format));
+                                    }
+                                });
+                            if self.format != previous_format {
+                                self.output_path.set_extension(self.format.extension().unwrap_or(""));
+                                if self.format != tango_pvp::replay::export::Format::Ffmpeg && self.scale.is_none() {
+                                    self.scale = Some(1);
+                                }
+                            }
+                            ui.end_row();
+
                             ui.strong(i18n::LOCALES.lookup(language, "replays-export-scale-factor").unwrap());
                             ui.horizontal(|ui| {
                                 let mut scale = self.scale.unwrap_or(1);
@@ -117,13 +165,34 @@ impl ReplayDumpWindow {
                                     self.scale = Some(scale);
                                 }
 
-                                let mut lossless = self.scale.is_none();
-                                let was_lossless = lossless;
-                                ui.checkbox(&mut lossless, i18n::LOCALES.lookup(language, "replays-export-lossless").unwrap());
-                                if lossless {
-                                    self.scale = None;
-                                } else if was_lossless {
-                                    self.scale = Some(DEFAULT_SCALE);
+                                if self.format == tango_pvp::replay::export::Format::Ffmpeg {
+                                    let mut lossless = self.scale.is_none();
+                                    let was_lossless = lossless;
+                                    ui.checkbox(&mut lossless, i18n::LOCALES.lookup(language, "replays-export-lossless").unwrap());
+                                    if lossless {
+                                        self.scale = None;
+                                    } else if was_lossless {
+                                        self.scale = Some(DEFAULT_SCALE);
+                                    }
+                                }
+                            });
+                            ui.end_row();
+
+                            ui.strong(i18n::LOCALES.lookup(language, "replays-export-clip").unwrap());
+                            ui.horizontal(|ui| {
+                                let mut enabled = self.tick_range.is_some();
+                                ui.checkbox(&mut enabled, i18n::LOCALES.lookup(language, "replays-export-clip.enable").unwrap());
+                                if enabled != self.tick_range.is_some() {
+                                    self.tick_range = if enabled {
+                                        Some((0, 600))
+                                    } else {
+                                        None
+                                    };
+                                }
+                                if let Some((from_tick, to_tick)) = self.tick_range.as_mut() {
+                                    ui.add(egui::DragValue::new(from_tick).speed(1));
+                                    ui.label("–");
+                                    ui.add(egui::DragValue::new(to_tick).speed(1).range(*from_tick..=u32::MAX));
                                 }
                             });
                             ui.end_row();
@@ -132,6 +201,10 @@ impl ReplayDumpWindow {
                             ui.add(egui::Checkbox::new(&mut self.disable_bgm, ""));
                             ui.end_row();
 
+                            ui.strong(i18n::LOCALES.lookup(language, "replays-export-input-display").unwrap());
+                            ui.add(egui::Checkbox::new(&mut self.input_display, ""));
+                            ui.end_row();
+
                             ui.strong(i18n::LOCALES.lookup(language, "replays-export-twosided").unwrap());
                             ui.add_enabled(self.remote_rom.is_some(), egui::Checkbox::new(&mut self.twosided, ""));
                             ui.end_row();
@@ -214,8 +287,11 @@ impl ReplayDumpWindow {
                     let progress = self.progress.clone();
                     let result = self.result.clone();
                     let mut settings = tango_pvp::replay::export::Settings::default_with_scale(self.scale);
+                    settings.format = self.format;
+                    settings.tick_range = self.tick_range.map(|(from_tick, to_tick)| from_tick..to_tick);
                     let twosided = self.twosided;
                     settings.disable_bgm = self.disable_bgm;
+                    settings.input_display = self.input_display;
                     let cancellation_token = tokio_util::sync::CancellationToken::new();
                     self.cancellation_token = Some(cancellation_token.clone());
                     tokio::task::spawn(async move {
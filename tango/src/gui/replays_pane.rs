@@ -1,5 +1,5 @@
 use super::{memoize::ResultCacheSingle, replay_dump_window::ReplayDumpWindow};
-use crate::{config, game, gui, i18n, patch, scanner, session};
+use crate::{config, game, gui, i18n, patch, replay_index, replay_thumbnails, rom, scanner, session, sync, upload_queue};
 use fluent_templates::Loader;
 use std::{rc::Rc, sync::Arc};
 use tango_dataview::save::Save;
@@ -10,24 +10,127 @@ struct CachedData {
     patch: Option<(String, semver::Version, Arc<crate::patch::Version>)>,
     rom_assets: Option<Box<dyn tango_dataview::rom::Assets + Send + Sync>>,
     local_rom: Vec<u8>,
+    remote_game: &'static (dyn game::Game + Send + Sync),
     remote_rom: Option<Vec<u8>>,
     save: Box<dyn Save + Sync + Send>,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+enum Period {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    fn duration(&self) -> std::time::Duration {
+        const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+        match self {
+            Period::Day => DAY,
+            Period::Week => DAY * 7,
+            Period::Month => DAY * 30,
+            Period::Year => DAY * 365,
+        }
+    }
+}
+
+const PERIODS: &[(Period, &str)] = &[
+    (Period::Day, "replays-filter-period.day"),
+    (Period::Week, "replays-filter-period.week"),
+    (Period::Month, "replays-filter-period.month"),
+    (Period::Year, "replays-filter-period.year"),
+];
+
+const OUTCOMES: &[(tango_pvp::stepper::BattleOutcome, &str)] = &[
+    (tango_pvp::stepper::BattleOutcome::Win, "replays-outcome.win"),
+    (tango_pvp::stepper::BattleOutcome::Loss, "replays-outcome.loss"),
+    (tango_pvp::stepper::BattleOutcome::Draw, "replays-outcome.draw"),
+];
+
+fn outcome_label(language: &unic_langid::LanguageIdentifier, outcome: tango_pvp::stepper::BattleOutcome) -> String {
+    let (_, key) = OUTCOMES.iter().find(|(o, _)| *o == outcome).unwrap();
+    i18n::LOCALES.lookup(language, key).unwrap()
+}
+
+#[derive(Clone, PartialEq, Default)]
+struct Filter {
+    query: String,
+    game_family: Option<&'static str>,
+    patch: Option<String>,
+    outcome: Option<tango_pvp::stepper::BattleOutcome>,
+    period: Option<Period>,
+}
+
+impl Filter {
+    fn matches(&self, entry: &replay_index::Entry, now: std::time::SystemTime) -> bool {
+        let metadata = &entry.metadata;
+        let local_game_info = metadata.local_side.as_ref().and_then(|side| side.game_info.as_ref());
+
+        if !self.query.is_empty() {
+            let query = self.query.to_lowercase();
+            let nickname = metadata
+                .remote_side
+                .as_ref()
+                .map(|side| side.nickname.to_lowercase())
+                .unwrap_or_default();
+            if !nickname.contains(&query) && !metadata.link_code.to_lowercase().contains(&query) {
+                return false;
+            }
+        }
+
+        if let Some(game_family) = self.game_family {
+            if local_game_info.map(|gi| gi.rom_family.as_str()) != Some(game_family) {
+                return false;
+            }
+        }
+
+        if let Some(patch) = self.patch.as_ref() {
+            if local_game_info.and_then(|gi| gi.patch.as_ref()).map(|p| &p.name) != Some(patch) {
+                return false;
+            }
+        }
+
+        if self.outcome.is_some() && entry.outcome != self.outcome {
+            return false;
+        }
+
+        if let Some(period) = self.period {
+            let ts = std::time::UNIX_EPOCH + std::time::Duration::from_millis(metadata.ts);
+            if now.duration_since(ts).is_ok_and(|age| age > period.duration()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// How big thumbnails are drawn in the list of replays.
+const LIST_THUMBNAIL_SIZE: egui::Vec2 = egui::Vec2::new(48.0, 32.0);
+
 pub struct State {
-    replays_scanner: scanner::Scanner<Vec<(std::path::PathBuf, bool, tango_pvp::replay::Metadata)>>,
+    replays_scanner: scanner::Scanner<Vec<(std::path::PathBuf, replay_index::Entry)>>,
+    index: replay_index::Handle,
+    filter: Filter,
+    pending_selection: Option<std::path::PathBuf>,
     selection: Option<std::ops::Range<usize>>,
     save_view: gui::save_view::State,
     replay_cache: ResultCacheSingle<std::path::PathBuf, Option<Rc<CachedData>>>,
+    thumbnails: Thumbnails,
 }
 
 impl State {
-    pub fn new() -> Self {
+    pub fn new(index: replay_index::Handle) -> Self {
         Self {
             selection: None,
             replays_scanner: scanner::Scanner::new(),
+            index,
+            filter: Filter::default(),
+            pending_selection: None,
             save_view: gui::save_view::State::new(),
             replay_cache: Default::default(),
+            thumbnails: Thumbnails::new(),
         }
     }
 
@@ -56,55 +159,119 @@ impl State {
         self.selection = new_selection;
     }
 
-    pub fn rescan(&self, ctx: &egui::Context, replays_path: &std::path::Path) {
+    /// Selects the replay at the given path once the current scan is done.
+    pub fn select_path(&mut self, path: std::path::PathBuf) {
+        self.pending_selection = Some(path);
+    }
+
+    /// Rescans the replays directory, then renders thumbnails for any replays that don't have them yet.
+    pub fn rescan(
+        &self,
+        ctx: &egui::Context,
+        replays_path: &std::path::Path,
+        roms_scanner: rom::Scanner,
+        patches_path: &std::path::Path,
+    ) {
         tokio::task::spawn_blocking({
             let replays_scanner = self.replays_scanner.clone();
+            let index = self.index.clone();
+            let thumbnails = self.thumbnails.clone();
             let replays_path = replays_path.to_path_buf();
+            let patches_path = patches_path.to_path_buf();
             let egui_ctx = ctx.clone();
             move || {
-                replays_scanner.rescan(move || {
-                    let mut replays = vec![];
-                    for entry in walkdir::WalkDir::new(&replays_path) {
-                        let entry = match entry {
-                            Ok(entry) => entry,
-                            Err(_) => {
-                                continue;
-                            }
-                        };
+                replays_scanner.rescan(|| Some(index.update(&replays_path)));
+                egui_ctx.request_repaint();
 
-                        if !entry.file_type().is_file() {
-                            continue;
-                        }
+                let entries = replays_scanner.read().clone();
+                thumbnails
+                    .cache
+                    .render_missing(&entries, &roms_scanner, &patches_path, |path, images| {
+                        thumbnails.insert(&egui_ctx, path, images);
+                        egui_ctx.request_repaint();
+                    });
+            }
+        });
+    }
+}
 
-                        let path = entry.path();
-                        let mut f = match std::fs::File::open(path) {
-                            Ok(f) => f,
-                            Err(_) => {
-                                continue;
-                            }
-                        };
+/// Replay thumbnails, loaded into textures as they are needed.
+#[derive(Clone)]
+struct Thumbnails {
+    cache: replay_thumbnails::Handle,
 
-                        let (num_inputs, metadata) = match tango_pvp::replay::read_metadata(&mut f) {
-                            Ok((n, metadata)) => (n, metadata),
-                            Err(_) => {
-                                continue;
-                            }
-                        };
+    /// None if a replay doesn't have thumbnails yet.
+    textures: Arc<parking_lot::Mutex<std::collections::HashMap<std::path::PathBuf, Option<[egui::TextureHandle; 2]>>>>,
 
-                        replays.push((path.to_path_buf(), num_inputs > 0, metadata));
-                    }
-                    replays.sort_by_key(|(_, _, metadata)| {
-                        (
-                            std::cmp::Reverse(metadata.ts),
-                            metadata.link_code.clone(),
-                            metadata.round,
-                        )
-                    });
-                    Some(replays)
-                });
+    /// Replays whose thumbnails are being read from the cache.
+    loading: Arc<parking_lot::Mutex<std::collections::HashSet<std::path::PathBuf>>>,
+}
+
+impl Thumbnails {
+    fn new() -> Self {
+        Self {
+            cache: replay_thumbnails::Handle::new(),
+            textures: Arc::new(parking_lot::Mutex::new(std::collections::HashMap::new())),
+            loading: Arc::new(parking_lot::Mutex::new(std::collections::HashSet::new())),
+        }
+    }
+
+    fn load_textures(
+        ctx: &egui::Context,
+        path: &std::path::Path,
+        images: [image::RgbaImage; 2],
+    ) -> [egui::TextureHandle; 2] {
+        let [start, end] = images;
+        let load = |name: &str, image: image::RgbaImage| {
+            ctx.load_texture(
+                format!("replay thumbnail {} {}", name, path.display()),
+                egui::ColorImage::from_rgba_unmultiplied(
+                    [image.width() as usize, image.height() as usize],
+                    image.as_raw(),
+                ),
+                egui::TextureOptions::LINEAR,
+            )
+        };
+        [load("start", start), load("end", end)]
+    }
+
+    fn insert(&self, ctx: &egui::Context, path: &std::path::Path, images: [image::RgbaImage; 2]) {
+        let textures = Self::load_textures(ctx, path, images);
+        self.textures.lock().insert(path.to_path_buf(), Some(textures));
+    }
+
+    /// The start and end thumbnails for a replay. The first time they're asked for, they're read from the cache in the
+    /// background and this returns None until they're ready.
+    fn get(
+        &self,
+        ctx: &egui::Context,
+        path: &std::path::Path,
+        metadata: &tango_pvp::replay::Metadata,
+    ) -> Option<[egui::TextureHandle; 2]> {
+        if let Some(textures) = self.textures.lock().get(path) {
+            return textures.clone();
+        }
+        if !self.loading.lock().insert(path.to_path_buf()) {
+            return None;
+        }
+
+        tokio::task::spawn_blocking({
+            let thumbnails = self.clone();
+            let egui_ctx = ctx.clone();
+            let path = path.to_path_buf();
+            let metadata = metadata.clone();
+            move || {
+                let textures = thumbnails
+                    .cache
+                    .load(&path, &metadata)
+                    .map(|images| Self::load_textures(&egui_ctx, &path, images));
+                // Thumbnails rendered while this was loading are newer, so keep those.
+                thumbnails.textures.lock().entry(path.clone()).or_insert(textures);
+                thumbnails.loading.lock().remove(&path);
                 egui_ctx.request_repaint();
             }
         });
+        None
     }
 }
 
@@ -123,12 +290,32 @@ pub fn show(
     let roms = roms_scanner.read();
     let patches = patches_scanner.read();
 
+    if !state.replays_scanner.is_scanning() {
+        if let Some(path) = state.pending_selection.take() {
+            let replays = state.replays_scanner.read();
+            if let Some(i) = replays.iter().position(|(p, _)| *p == path) {
+                state.filter = Filter::default();
+                state.selection = Some(i..i + 1);
+                state.save_view = gui::save_view::State::new();
+            }
+        }
+    }
+
     egui::SidePanel::left("replays-window-left-panel")
         .frame(egui::Frame::default().inner_margin(egui::Margin {
             right: 8.0,
             ..Default::default()
         }))
         .show_inside(ui, |ui| {
+            show_upload_status(ui, language, &shared_root_state.uploader.status());
+
+            let previous_filter = state.filter.clone();
+            show_filter(ui, language, &state.replays_scanner.read(), &mut state.filter);
+            if state.filter != previous_filter {
+                state.selection = None;
+            }
+            ui.separator();
+
             egui::ScrollArea::vertical()
                 .auto_shrink([false, false])
                 .id_source("replays-window-left")
@@ -143,6 +330,7 @@ pub fn show(
 
                     let replays = state.replays_scanner.read();
                     let mut clicked_index = None;
+                    let now = std::time::SystemTime::now();
 
                     ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
                         let mut last_fingerprint = None;
@@ -152,7 +340,12 @@ pub fn show(
                         let default_spacing = ui.style().spacing.item_spacing;
                         ui.style_mut().spacing.item_spacing = Default::default();
 
-                        for (i, (_, _, metadata)) in replays.iter().enumerate() {
+                        for (i, (path, entry)) in replays.iter().enumerate() {
+                            if !state.filter.matches(entry, now) {
+                                continue;
+                            }
+                            let metadata = &entry.metadata;
+
                             let Some(ts) =
                                 std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_millis(metadata.ts))
                             else {
@@ -219,6 +412,13 @@ pub fn show(
                                     0.0,
                                     egui::TextFormat::simple(text_body_style.clone(), text_color),
                                 );
+                                if let Some(outcome) = entry.outcome {
+                                    layout_job.append(
+                                        &outcome_label(language, outcome),
+                                        8.0,
+                                        egui::TextFormat::simple(text_small_style.clone(), text_color),
+                                    );
+                                }
                                 layout_job.append(
                                     "\n",
                                     0.0,
@@ -252,9 +452,24 @@ pub fn show(
                                     egui::TextFormat::simple(text_small_style.clone(), text_color),
                                 );
 
-                                if ui.selectable_label(selected, layout_job).clicked() {
-                                    clicked_index = Some(i);
-                                }
+                                ui.horizontal(|ui| {
+                                    // Only load thumbnails for rows that are on screen.
+                                    let thumbnail_rect =
+                                        egui::Rect::from_min_size(ui.cursor().min, LIST_THUMBNAIL_SIZE);
+                                    if let Some([start, _]) = ui
+                                        .is_rect_visible(thumbnail_rect)
+                                        .then(|| state.thumbnails.get(ui.ctx(), path, metadata))
+                                        .flatten()
+                                    {
+                                        ui.image((start.id(), LIST_THUMBNAIL_SIZE));
+                                        ui.add_space(default_spacing.x);
+                                    }
+                                    ui.with_layout(egui::Layout::top_down_justified(egui::Align::LEFT), |ui| {
+                                        if ui.selectable_label(selected, layout_job).clicked() {
+                                            clicked_index = Some(i);
+                                        }
+                                    });
+                                });
                             });
                         }
                     });
@@ -279,7 +494,8 @@ pub fn show(
                 };
 
                 let replays = state.replays_scanner.read();
-                let (path, _, metadata) = &replays[selection.start];
+                let (path, entry) = &replays[selection.start];
+                let metadata = &entry.metadata;
 
                 let Some(local_side) = metadata.local_side.as_ref() else {
                     return;
@@ -414,6 +630,7 @@ pub fn show(
                         replay,
                         rom_assets: assets,
                         local_rom,
+                        remote_game,
                         remote_rom,
                         patch,
                         save,
@@ -424,6 +641,18 @@ pub fn show(
                     return;
                 };
 
+                if entry.is_complete && entry.outcome.is_none() && state.index.start_evaluating(path) {
+                    evaluate_outcome(
+                        ui.ctx().clone(),
+                        state.replays_scanner.clone(),
+                        state.index.clone(),
+                        path.clone(),
+                        cached_result.replay.clone(),
+                        cached_result.local_rom.clone(),
+                        local_game,
+                    );
+                }
+
                 let local_rom = &cached_result.local_rom;
                 let remote_rom = &cached_result.remote_rom;
                 let assets = &cached_result.rom_assets;
@@ -433,16 +662,33 @@ pub fn show(
 
                 ui.vertical(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                        let mut play = None;
                         if ui
                             .button(format!("▶️ {}", i18n::LOCALES.lookup(language, "replays-play").unwrap()))
                             .clicked()
                         {
+                            play = Some(false);
+                        }
+                        if ui
+                            .add_enabled(remote_rom.is_some(), egui::Button::new("👥"))
+                            .on_hover_text(i18n::LOCALES.lookup(language, "replays-play-both").unwrap())
+                            .clicked()
+                        {
+                            play = Some(true);
+                        }
+
+                        if let Some(both_sides) = play {
                             tokio::task::spawn_blocking({
                                 let egui_ctx = ui.ctx().clone();
                                 let audio_binder = shared_root_state.audio_binder.clone();
                                 let game = local_game;
                                 let patch = patch.as_ref().map(|(name, version, _)| (name.clone(), version.clone()));
                                 let rom = local_rom.clone();
+                                let remote = if both_sides {
+                                    remote_rom.clone().map(|rom| (cached_result.remote_game, rom))
+                                } else {
+                                    None
+                                };
                                 let emu_tps_counter = shared_root_state.emu_tps_counter.clone();
                                 let replay = replay.clone();
                                 let session = shared_root_state.session.clone();
@@ -456,6 +702,7 @@ pub fn show(
                                             &rom,
                                             emu_tps_counter,
                                             &replay,
+                                            remote.as_ref().map(|(game, rom)| (*game, rom.as_slice())),
                                         )
                                         .unwrap(),
                                     ); // TODO: Don't unwrap maybe
@@ -476,7 +723,7 @@ pub fn show(
                             let replays_to_render = replays[selection.clone()]
                                 .iter()
                                 .rev()
-                                .flat_map(|(path, _, _)| {
+                                .flat_map(|(path, _)| {
                                     let mut f = match std::fs::File::open(path) {
                                         Ok(f) => f,
                                         Err(e) => {
@@ -520,6 +767,23 @@ pub fn show(
                                 .push(move |id, ctx, config, _| window.show(id, ctx, config));
                         }
 
+                        let share_label = format!(
+                            "🕶️ {}",
+                            i18n::LOCALES.lookup(language, "replays-share-anonymized").unwrap()
+                        );
+
+                        ui.menu_button(share_label, |ui| {
+                            for (text_id, hide_setup) in [
+                                ("replays-share-anonymized.keep-setup", false),
+                                ("replays-share-anonymized.hide-setup", true),
+                            ] {
+                                if ui.button(i18n::LOCALES.lookup(language, text_id).unwrap()).clicked() {
+                                    ui.close_menu();
+                                    share_anonymized(path, config.last_export_folder.as_deref(), &replay, hide_setup);
+                                }
+                            }
+                        });
+
                         ui.with_layout(egui::Layout::top_down_justified(egui::Align::Min), |ui| {
                             ui.horizontal(|ui| {
                                 ui.with_layout(
@@ -535,6 +799,18 @@ pub fn show(
                         });
                     });
 
+                    if let Some(textures) = state.thumbnails.get(ui.ctx(), path, metadata) {
+                        ui.horizontal(|ui| {
+                            for (texture, text_id) in textures
+                                .iter()
+                                .zip(["replays-thumbnail-start", "replays-thumbnail-end"])
+                            {
+                                ui.image((texture.id(), texture.size_vec2() * 2.0))
+                                    .on_hover_text(i18n::LOCALES.lookup(language, text_id).unwrap());
+                            }
+                        });
+                    }
+
                     if let Some(assets) = assets.as_ref() {
                         let game_language = crate::game::region_to_language(local_game.gamedb_entry().region);
                         gui::save_view::show(
@@ -556,3 +832,214 @@ pub fn show(
             });
     });
 }
+
+fn share_anonymized(
+    path: &std::path::Path,
+    folder: Option<&std::path::Path>,
+    replay: &tango_pvp::replay::Replay,
+    hide_setup: bool,
+) {
+    // The original file name has the link code and the opponent's nickname in it, so don't suggest it.
+    let ts = std::time::UNIX_EPOCH + std::time::Duration::from_millis(replay.metadata.ts);
+    let Some(output_path) = rfd::FileDialog::new()
+        .set_directory(folder.or(path.parent()).unwrap_or(std::path::Path::new("")))
+        .set_file_name(&format!(
+            "{}-round{}-anonymized.tangoreplay",
+            chrono::DateTime::<chrono::Local>::from(ts).format("%Y%m%d%H%M%S"),
+            replay.metadata.round
+        ))
+        .add_filter("TANGOREPLAY", &["tangoreplay"])
+        .save_file()
+    else {
+        return;
+    };
+
+    let mut replay = replay.clone();
+    tokio::task::spawn_blocking(move || {
+        replay.edit_metadata(&tango_pvp::replay::MetadataEdit::anonymize(
+            replay.local_player_index,
+            hide_setup,
+        ));
+        let f = match std::fs::File::create(&output_path) {
+            Ok(f) => f,
+            Err(e) => {
+                log::error!("failed to write anonymized replay {}: {:?}", output_path.display(), e);
+                return;
+            }
+        };
+        if let Err(e) = replay.encode(f) {
+            log::error!("failed to write anonymized replay {}: {:?}", output_path.display(), e);
+        }
+    });
+}
+
+fn show_upload_status(ui: &mut egui::Ui, language: &unic_langid::LanguageIdentifier, status: &upload_queue::Status) {
+    if status.pending == 0 {
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.spinner();
+        ui.label(
+            i18n::LOCALES
+                .lookup_with_args(
+                    language,
+                    "replays-upload-pending",
+                    &std::collections::HashMap::from([("count", status.pending.into())]),
+                )
+                .unwrap(),
+        );
+    });
+    if let Some(error) = status.last_error.as_ref() {
+        ui.label(
+            egui::RichText::new(
+                i18n::LOCALES
+                    .lookup_with_args(
+                        language,
+                        "replays-upload-error",
+                        &std::collections::HashMap::from([("error", error.clone().into())]),
+                    )
+                    .unwrap(),
+            )
+            .color(ui.visuals().warn_fg_color),
+        );
+    }
+    ui.separator();
+}
+
+fn show_filter(
+    ui: &mut egui::Ui,
+    language: &unic_langid::LanguageIdentifier,
+    replays: &[(std::path::PathBuf, replay_index::Entry)],
+    filter: &mut Filter,
+) {
+    let mut game_families = std::collections::BTreeSet::new();
+    let mut patches = std::collections::BTreeSet::new();
+    for (_, entry) in replays {
+        let Some(game_info) = entry
+            .metadata
+            .local_side
+            .as_ref()
+            .and_then(|side| side.game_info.as_ref())
+        else {
+            continue;
+        };
+        if let Some(game) = game::find_by_family_and_variant(&game_info.rom_family, game_info.rom_variant as u8) {
+            game_families.insert(game.gamedb_entry().family_and_variant.0);
+        }
+        if let Some(patch) = game_info.patch.as_ref() {
+            patches.insert(patch.name.clone());
+        }
+    }
+
+    let any_label = i18n::LOCALES.lookup(language, "replays-filter-any").unwrap();
+
+    ui.add(
+        egui::TextEdit::singleline(&mut filter.query)
+            .hint_text(i18n::LOCALES.lookup(language, "replays-filter-search").unwrap())
+            .desired_width(f32::INFINITY),
+    );
+
+    ui.horizontal_wrapped(|ui| {
+        egui::ComboBox::from_id_source("replays-filter-game")
+            .selected_text(
+                filter
+                    .game_family
+                    .map(|family| {
+                        i18n::LOCALES
+                            .lookup(language, &format!("game-{}.short", family))
+                            .unwrap()
+                    })
+                    .unwrap_or_else(|| any_label.clone()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut filter.game_family, None, any_label.clone());
+                for family in game_families {
+                    ui.selectable_value(
+                        &mut filter.game_family,
+                        Some(family),
+                        i18n::LOCALES
+                            .lookup(language, &format!("game-{}.short", family))
+                            .unwrap(),
+                    );
+                }
+            });
+
+        egui::ComboBox::from_id_source("replays-filter-patch")
+            .selected_text(filter.patch.clone().unwrap_or_else(|| any_label.clone()))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut filter.patch, None, any_label.clone());
+                for patch in patches {
+                    ui.selectable_value(&mut filter.patch, Some(patch.clone()), patch);
+                }
+            });
+
+        egui::ComboBox::from_id_source("replays-filter-outcome")
+            .selected_text(
+                filter
+                    .outcome
+                    .map(|outcome| outcome_label(language, outcome))
+                    .unwrap_or_else(|| any_label.clone()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut filter.outcome, None, any_label.clone());
+                for (outcome, _) in OUTCOMES {
+                    ui.selectable_value(&mut filter.outcome, Some(*outcome), outcome_label(language, *outcome));
+                }
+            })
+            .response
+            .on_hover_text(i18n::LOCALES.lookup(language, "replays-filter-outcome").unwrap());
+
+        egui::ComboBox::from_id_source("replays-filter-period")
+            .selected_text(
+                filter
+                    .period
+                    .and_then(|period| PERIODS.iter().find(|(p, _)| *p == period))
+                    .map(|(_, key)| i18n::LOCALES.lookup(language, key).unwrap())
+                    .unwrap_or_else(|| any_label.clone()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut filter.period, None, any_label.clone());
+                for (period, key) in PERIODS {
+                    ui.selectable_value(
+                        &mut filter.period,
+                        Some(*period),
+                        i18n::LOCALES.lookup(language, key).unwrap(),
+                    );
+                }
+            });
+    });
+}
+
+/// Works out who won a replay in the background and remembers it in the index, so it can be shown and filtered on.
+fn evaluate_outcome(
+    egui_ctx: egui::Context,
+    replays_scanner: scanner::Scanner<Vec<(std::path::PathBuf, replay_index::Entry)>>,
+    index: replay_index::Handle,
+    path: std::path::PathBuf,
+    replay: Replay,
+    rom: Vec<u8>,
+    game: &'static (dyn game::Game + Send + Sync),
+) {
+    tokio::task::spawn_blocking(move || {
+        let hooks = tango_pvp::hooks::hooks_for_gamedb_entry(game.gamedb_entry()).unwrap();
+        let outcome = match sync::block_on(tango_pvp::eval::eval(&replay, &rom, hooks, Vec::new)) {
+            Ok((result, _, _)) => result.outcome,
+            Err(e) => {
+                // Leave the replay marked as in progress so we don't keep retrying it.
+                log::error!("failed to evaluate replay {}: {:?}", path.display(), e);
+                return;
+            }
+        };
+
+        index.set_outcomes(&[(path.clone(), outcome)]);
+
+        replays_scanner.modify(|replays| {
+            if let Some((_, entry)) = replays.iter_mut().find(|(p, _)| *p == path) {
+                entry.outcome = Some(outcome);
+            }
+        });
+        index.finish_evaluating(&path);
+        egui_ctx.request_repaint();
+    });
+}
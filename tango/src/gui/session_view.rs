@@ -1,9 +1,10 @@
-use crate::{config, discord, gui, i18n, input, session, sync, video};
+use crate::{config, discord, gui, i18n, input, presence, session, sync, video};
 use fluent_templates::Loader;
 mod replay_controls_window;
 
 pub struct State {
     vbuf: Option<VBuf>,
+    remote_vbuf: Option<VBuf>,
     opponent_save_view: gui::save_view::State,
     own_save_view: gui::save_view::State,
     debug_window: Option<gui::debug_window::State>,
@@ -13,6 +14,7 @@ impl State {
     pub fn new() -> State {
         Self {
             vbuf: None,
+            remote_vbuf: None,
             opponent_save_view: gui::save_view::State::new(),
             own_save_view: gui::save_view::State::new(),
             debug_window: None,
@@ -40,11 +42,12 @@ impl VBuf {
 
 fn show_emulator(
     ui: &mut egui::Ui,
-    session: &session::Session,
+    emu_vbuf: &[u8],
     video_filter: &str,
     max_scale: u32,
     integer_scaling: bool,
     vbuf: &mut Option<VBuf>,
+    input_display: Option<[u16; 2]>,
 ) {
     let video_filter = video::filter_by_name(video_filter).unwrap_or(Box::new(video::NullFilter));
 
@@ -64,7 +67,7 @@ fn show_emulator(
     };
 
     video_filter.apply(
-        &session.lock_vbuf(),
+        emu_vbuf,
         bytemuck::cast_slice_mut(&mut vbuf.image.pixels[..]),
         [mgba::gba::SCREEN_WIDTH as usize, mgba::gba::SCREEN_HEIGHT as usize],
     );
@@ -91,9 +94,46 @@ fn show_emulator(
     let rect = egui::Rect::from_center_size(center.to_pos2(), scaled_size);
 
     ui.put(rect, egui::Image::new((vbuf.texture.id(), scaled_size)));
+    if let Some(joyflags) = input_display {
+        show_input_display(ui, rect, joyflags);
+    }
     ui.ctx().request_repaint();
 }
 
+fn show_input_display(ui: &mut egui::Ui, screen_rect: egui::Rect, joyflags: [u16; 2]) {
+    let color = |c: [u8; 4]| egui::Color32::from_rgba_unmultiplied(c[0], c[1], c[2], c[3]);
+    let scale = screen_rect.width() / mgba::gba::SCREEN_WIDTH as f32;
+    let painter = ui.painter_at(screen_rect);
+
+    for ((x, y), joyflags) in tango_pvp::input_display::positions(mgba::gba::SCREEN_WIDTH, mgba::gba::SCREEN_HEIGHT)
+        .into_iter()
+        .zip(joyflags)
+    {
+        let rect = |bx: u32, by: u32, width: u32, height: u32| {
+            egui::Rect::from_min_size(
+                screen_rect.min + egui::Vec2::new((x + bx) as f32, (y + by) as f32) * scale,
+                egui::Vec2::new(width as f32, height as f32) * scale,
+            )
+        };
+        painter.rect_filled(
+            rect(0, 0, tango_pvp::input_display::WIDTH, tango_pvp::input_display::HEIGHT),
+            0.0,
+            color(tango_pvp::input_display::BACKGROUND_COLOR),
+        );
+        for button in tango_pvp::input_display::LAYOUT {
+            painter.rect_filled(
+                rect(button.x, button.y, button.width, button.height),
+                0.0,
+                color(if joyflags & button.key != 0 {
+                    tango_pvp::input_display::PRESSED_COLOR
+                } else {
+                    tango_pvp::input_display::RELEASED_COLOR
+                }),
+            );
+        }
+    }
+}
+
 pub fn show(
     ctx: &egui::Context,
     config: &config::Config,
@@ -106,12 +146,18 @@ pub fn show(
 ) {
     let language = &config.language;
     let discord_client = &shared_root_state.discord_client;
+    let presence_client = &shared_root_state.presence_client;
     let input_mapping = &config.input_mapping;
     let video_filter = &config.video_filter;
     let integer_scaling = config.integer_scaling;
     let max_scale = config.max_scale;
     let speed_change_factor = config.speed_change_percent as f32 / 100.0;
     let show_own_setup = config.show_own_setup;
+    let input_display = if config.show_input_display {
+        session.last_joyflags()
+    } else {
+        None
+    };
     let crashstates_path = &config.crashstates_path();
     let show_debug = config.show_debug;
 
@@ -126,6 +172,7 @@ pub fn show(
     let game_info = session.game_info();
     match session.mode() {
         session::Mode::SinglePlayer(_) => {
+            presence_client.set_status(presence::Status::Online);
             discord_client.set_current_activity(Some(discord::make_single_player_activity(
                 session.start_time(),
                 language,
@@ -137,6 +184,7 @@ pub fn show(
             )));
         }
         session::Mode::PvP(_) => {
+            presence_client.set_status(presence::Status::InMatch);
             discord_client.set_current_activity(Some(discord::make_in_progress_activity(
                 session.start_time(),
                 language,
@@ -147,7 +195,8 @@ pub fn show(
                 )),
             )));
         }
-        session::Mode::Replayer => {
+        session::Mode::Replayer(_) => {
+            presence_client.set_status(presence::Status::Online);
             discord_client.set_current_activity(Some(discord::make_base_activity(None)));
         }
     }
@@ -160,7 +209,7 @@ pub fn show(
                 session::EXPECTED_FPS
             });
         }
-        session::Mode::Replayer => {
+        session::Mode::Replayer(_) => {
             replay_controls_window::show(ctx, session, language, last_mouse_motion_time);
         }
         _ => {}
@@ -273,7 +322,43 @@ cpsr = {:08x}"#,
             ui.with_layout(
                 egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
                 |ui| {
-                    show_emulator(ui, session, video_filter, max_scale, integer_scaling, &mut state.vbuf);
+                    let remote_vbuf = match session.mode() {
+                        session::Mode::Replayer(replayer) => replayer.lock_remote_vbuf(),
+                        _ => None,
+                    };
+                    let Some(remote_vbuf) = remote_vbuf else {
+                        show_emulator(
+                            ui,
+                            &session.lock_vbuf(),
+                            video_filter,
+                            max_scale,
+                            integer_scaling,
+                            &mut state.vbuf,
+                            input_display,
+                        );
+                        return;
+                    };
+
+                    ui.columns(2, |columns| {
+                        show_emulator(
+                            &mut columns[0],
+                            &session.lock_vbuf(),
+                            video_filter,
+                            max_scale,
+                            integer_scaling,
+                            &mut state.vbuf,
+                            input_display,
+                        );
+                        show_emulator(
+                            &mut columns[1],
+                            &remote_vbuf,
+                            video_filter,
+                            max_scale,
+                            integer_scaling,
+                            &mut state.remote_vbuf,
+                            None,
+                        );
+                    });
                 },
             );
         });
@@ -399,6 +484,21 @@ fn show_status_bar(
                     ui.monospace(format!("ping {:4}ms", latency.as_millis()));
                 }
 
+                if let session::Mode::PvP(pvp) = session.mode() {
+                    ui.add(egui::Separator::default().vertical());
+                    match pvp.connection_stats() {
+                        Some(stats) if stats.is_relayed() => {
+                            ui.monospace("relay");
+                        }
+                        Some(_) => {
+                            ui.monospace("direct");
+                        }
+                        None => {
+                            ui.monospace("lan");
+                        }
+                    }
+                }
+
                 if let Some((_, _, _, _, local_player_index)) = round_info {
                     ui.add(egui::Separator::default().vertical());
                     ui.monospace(format!("P{}", local_player_index + 1));
@@ -57,6 +57,62 @@ pub fn show(
                     speed = std::cmp::min_by(speed + 0.25, 10.0, |x, y| x.partial_cmp(y).unwrap());
                 }
                 session.set_fps_target(speed * session::EXPECTED_FPS);
+
+                if let session::Mode::Replayer(replayer) = session.mode() {
+                    ui.add(egui::Separator::default().vertical());
+                    if ui
+                        .add_enabled(paused, egui::Button::new("📷"))
+                        .on_hover_text(i18n::LOCALES.lookup(language, "replay-viewer-save-state").unwrap())
+                        .on_disabled_hover_text(i18n::LOCALES.lookup(language, "replay-viewer-save-state").unwrap())
+                        .clicked()
+                    {
+                        save_state(session, replayer.current_tick());
+                    }
+                }
             });
+
+            let session::Mode::Replayer(replayer) = session.mode() else {
+                return;
+            };
+            if replayer.rom_mismatch() {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    i18n::LOCALES.lookup(language, "replay-viewer-rom-mismatch").unwrap(),
+                );
+            }
+            if let Some(tick) = replayer.divergence() {
+                ui.colored_label(
+                    egui::Color32::RED,
+                    i18n::LOCALES
+                        .lookup_with_args(
+                            language,
+                            "replay-viewer-diverged",
+                            &std::collections::HashMap::from([("tick", tick.into())]),
+                        )
+                        .unwrap(),
+                );
+            }
         });
 }
+
+/// Saves the current state as an mGBA savestate, which stock mGBA can load alongside the same ROM.
+fn save_state(session: &session::Session, tick: u32) {
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(&format!("tick{}.ss1", tick))
+        .add_filter("SS1", &["ss1"])
+        .save_file()
+    else {
+        return;
+    };
+
+    let state = match session.save_state() {
+        Ok(state) => state,
+        Err(e) => {
+            log::error!("failed to save state at tick {}: {:?}", tick, e);
+            return;
+        }
+    };
+    if let Err(e) = std::fs::write(&path, state.as_slice()) {
+        log::error!("failed to write state to {}: {:?}", path.display(), e);
+    }
+}
@@ -172,6 +172,16 @@ fn show_general_tab(ui: &mut egui::Ui, config: &mut config::Config, font_familie
                 ui.end_row();
             }
 
+            {
+                ui.strong(
+                    i18n::LOCALES
+                        .lookup(&config.language, "settings-show-input-display")
+                        .unwrap(),
+                );
+                ui.checkbox(&mut config.show_input_display, "");
+                ui.end_row();
+            }
+
             {
                 ui.strong(
                     i18n::LOCALES
@@ -584,6 +594,22 @@ fn show_netplay_tab(ui: &mut egui::Ui, config: &mut config::Config) {
             );
             ui.add(egui::TextEdit::singleline(&mut config.replaycollector_endpoint).desired_width(200.0));
             ui.end_row();
+
+            ui.strong(i18n::LOCALES.lookup(&config.language, "settings-save-match-files").unwrap());
+            ui.checkbox(&mut config.save_match_files, "").on_hover_text(
+                i18n::LOCALES
+                    .lookup(&config.language, "settings-save-match-files.tooltip")
+                    .unwrap(),
+            );
+            ui.end_row();
+
+            ui.strong(i18n::LOCALES.lookup(&config.language, "settings-enable-presence").unwrap());
+            ui.checkbox(&mut config.enable_presence, "");
+            ui.end_row();
+
+            ui.strong(i18n::LOCALES.lookup(&config.language, "settings-lan-discovery").unwrap());
+            ui.checkbox(&mut config.lan_discovery, "");
+            ui.end_row();
         });
 }
 
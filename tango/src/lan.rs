@@ -0,0 +1,186 @@
+use bincode::Options;
+
+use crate::net;
+
+#[cfg(test)]
+mod tests;
+
+pub const DISCOVERY_PORT: u16 = 13721;
+
+const MAGIC: &[u8] = b"TANGOLAN";
+const ANNOUNCE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+const LOBBY_EXPIRY: std::time::Duration = std::time::Duration::from_secs(5);
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+lazy_static! {
+    static ref BINCODE_OPTIONS: bincode::config::WithOtherLimit<
+        bincode::config::WithOtherIntEncoding<bincode::config::DefaultOptions, bincode::config::VarintEncoding>,
+        bincode::config::Bounded,
+    > = bincode::DefaultOptions::new().with_varint_encoding().with_limit(1024);
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Announcement {
+    pub protocol_version: u8,
+    pub nickname: String,
+    pub port: u16,
+}
+
+impl Announcement {
+    fn serialize(&self) -> bincode::Result<Vec<u8>> {
+        let mut buf = MAGIC.to_vec();
+        buf.extend(BINCODE_OPTIONS.serialize(self)?);
+        Ok(buf)
+    }
+
+    fn deserialize(d: &[u8]) -> Option<Self> {
+        BINCODE_OPTIONS.deserialize(d.strip_prefix(MAGIC)?).ok()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Lobby {
+    pub addr: std::net::SocketAddr,
+    pub nickname: String,
+}
+
+type LobbyMap = std::collections::HashMap<std::net::SocketAddr, (Lobby, std::time::Instant)>;
+
+/// Listens for lobby announcements on the local network for as long as it is alive.
+pub struct Browser {
+    lobbies: std::sync::Arc<parking_lot::Mutex<LobbyMap>>,
+    cancellation_token: tokio_util::sync::CancellationToken,
+}
+
+impl Browser {
+    pub fn new(egui_ctx: egui::Context) -> Self {
+        let lobbies = std::sync::Arc::new(parking_lot::Mutex::new(std::collections::HashMap::new()));
+        let cancellation_token = tokio_util::sync::CancellationToken::new();
+
+        tokio::task::spawn({
+            let lobbies = lobbies.clone();
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                let socket = match tokio::net::UdpSocket::bind((std::net::Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)).await
+                {
+                    Ok(socket) => socket,
+                    Err(e) => {
+                        log::warn!("failed to bind LAN discovery socket: {:?}", e);
+                        return;
+                    }
+                };
+
+                let mut buf = [0u8; 1024];
+                loop {
+                    let (n, addr) = tokio::select! {
+                        r = socket.recv_from(&mut buf) => {
+                            match r {
+                                Ok(r) => r,
+                                Err(e) => {
+                                    log::warn!("LAN discovery socket error: {:?}", e);
+                                    continue;
+                                }
+                            }
+                        }
+                        _ = cancellation_token.cancelled() => { break; }
+                    };
+
+                    let Some(announcement) = Announcement::deserialize(&buf[..n]) else {
+                        continue;
+                    };
+
+                    if announcement.protocol_version != net::protocol::VERSION {
+                        continue;
+                    }
+
+                    let addr = std::net::SocketAddr::new(addr.ip(), announcement.port);
+                    let is_new = lobbies
+                        .lock()
+                        .insert(
+                            addr,
+                            (
+                                Lobby {
+                                    addr,
+                                    nickname: announcement.nickname,
+                                },
+                                std::time::Instant::now(),
+                            ),
+                        )
+                        .is_none();
+                    if is_new {
+                        egui_ctx.request_repaint();
+                    }
+                }
+            }
+        });
+
+        Self {
+            lobbies,
+            cancellation_token,
+        }
+    }
+
+    pub fn lobbies(&self) -> Vec<Lobby> {
+        let mut lobbies = self.lobbies.lock();
+        let now = std::time::Instant::now();
+        lobbies.retain(|_, (_, last_seen)| now.duration_since(*last_seen) < LOBBY_EXPIRY);
+        let mut lobbies = lobbies.values().map(|(lobby, _)| lobby.clone()).collect::<Vec<_>>();
+        lobbies.sort_by_key(|lobby| lobby.addr);
+        lobbies
+    }
+}
+
+impl Drop for Browser {
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+/// Announces a lobby on the local network and waits for someone to join it.
+pub async fn host(nickname: &str) -> std::io::Result<tokio::net::TcpStream> {
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let socket = tokio::net::UdpSocket::bind((std::net::Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+
+    let announcement = Announcement {
+        protocol_version: net::protocol::VERSION,
+        nickname: nickname.to_string(),
+        port: listener.local_addr()?.port(),
+    }
+    .serialize()
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    let mut announce_timer = tokio::time::interval(ANNOUNCE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = announce_timer.tick() => {
+                if let Err(e) = socket.send_to(&announcement, (std::net::Ipv4Addr::BROADCAST, DISCOVERY_PORT)).await {
+                    log::warn!("failed to send LAN announcement: {:?}", e);
+                }
+            }
+            r = listener.accept() => {
+                let (stream, addr) = r?;
+                log::info!("accepted LAN connection from {}", addr);
+                stream.set_nodelay(true)?;
+                return Ok(stream);
+            }
+        }
+    }
+}
+
+/// Names a LAN session after the host's end of the connection. The host knows it as its own address and the joiner as
+/// the one it connected to, so both sides record the same link code, which the round digest covers.
+pub fn session_code(host_addr: std::net::SocketAddr) -> String {
+    format!(
+        "lan-{}",
+        std::net::SocketAddr::new(host_addr.ip().to_canonical(), host_addr.port())
+    )
+}
+
+pub async fn join(addr: std::net::SocketAddr) -> std::io::Result<tokio::net::TcpStream> {
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(addr))
+        .await
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::TimedOut, e))??;
+    stream.set_nodelay(true)?;
+    Ok(stream)
+}
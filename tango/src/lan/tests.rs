@@ -0,0 +1,37 @@
+#[tokio::test]
+async fn test_session_code() {
+    let listener = tokio::net::TcpListener::bind((std::net::Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let (joined, hosted) = tokio::join!(
+        tokio::net::TcpStream::connect(listener.local_addr().unwrap()),
+        listener.accept()
+    );
+    let host_code = super::session_code(hosted.unwrap().0.local_addr().unwrap());
+    let join_code = super::session_code(joined.unwrap().peer_addr().unwrap());
+    assert_eq!(host_code, join_code);
+
+    // Each side records itself as the local side, and both have to sign the same digest.
+    let side = |nickname: &str| tango_pvp::replay::metadata::Side {
+        nickname: nickname.to_string(),
+        ..Default::default()
+    };
+    let metadata = |link_code: String, local: &str, remote: &str| tango_pvp::replay::Metadata {
+        link_code,
+        local_side: Some(side(local)),
+        remote_side: Some(side(remote)),
+        round: 1,
+        ..Default::default()
+    };
+    assert_eq!(
+        tango_pvp::replay::signing::Digester::new(&metadata(host_code, "host", "joiner"), 0).finish(),
+        tango_pvp::replay::signing::Digester::new(&metadata(join_code, "joiner", "host"), 1).finish()
+    );
+}
+
+#[test]
+fn test_session_code_ipv4_mapped() {
+    let v4 = "192.168.1.2:4000".parse().unwrap();
+    let mapped = "[::ffff:192.168.1.2]:4000".parse().unwrap();
+    assert_eq!(super::session_code(v4), super::session_code(mapped));
+}
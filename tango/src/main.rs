@@ -17,9 +17,13 @@ mod gui;
 mod i18n;
 mod input;
 mod keyboard;
+mod lan;
 mod net;
 mod patch;
+mod presence;
 mod randomcode;
+mod replay_index;
+mod replay_thumbnails;
 mod rom;
 mod save;
 mod scanner;
@@ -27,6 +31,7 @@ mod session;
 mod stats;
 mod sync;
 mod updater;
+mod upload_queue;
 mod version;
 mod video;
 
@@ -259,6 +264,11 @@ fn child_main(mut config: config::Config) -> Result<(), anyhow::Error> {
 
     let discord_client = discord::Client::new();
 
+    let mut presence_client = presence::Client::new(config.clone());
+    presence_client.set_enabled(config.read().enable_presence);
+
+    let uploader = upload_queue::Uploader::new(config.clone());
+
     let roms_scanner = scanner::Scanner::new();
     let saves_scanner = scanner::Scanner::new();
     let patches_scanner = scanner::Scanner::new();
@@ -276,6 +286,8 @@ fn child_main(mut config: config::Config) -> Result<(), anyhow::Error> {
         show_update_info,
         config.clone(),
         discord_client,
+        presence_client,
+        uploader,
         audio_binder.clone(),
         fps_counter.clone(),
         emu_tps_counter.clone(),
@@ -486,6 +498,9 @@ fn child_main(mut config: config::Config) -> Result<(), anyhow::Error> {
         if next_config != old_config {
             last_config_dirty_time = Some(std::time::Instant::now());
             *config.write() = next_config.clone();
+            if next_config.friends != old_config.friends || next_config.nickname != old_config.nickname {
+                state.shared.presence_client.resync();
+            }
         }
 
         if last_config_dirty_time
@@ -499,6 +514,7 @@ fn child_main(mut config: config::Config) -> Result<(), anyhow::Error> {
 
         gfx_backend.set_ui_scale(next_config.ui_scale_percent as f32 / 100.0);
         patch_autoupdater.set_enabled(next_config.enable_patch_autoupdate);
+        state.shared.presence_client.set_enabled(next_config.enable_presence);
         updater.set_enabled(next_config.enable_updater);
     })?;
 
@@ -1,3 +1,6 @@
+use futures::StreamExt;
+use tokio::io::AsyncWriteExt;
+
 pub mod protocol;
 
 pub const PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
@@ -7,6 +10,9 @@ pub enum NegotiationError {
     #[error("expected hello")]
     ExpectedHello,
 
+    #[error("expected identity")]
+    ExpectedIdentity,
+
     #[error("remote protocol version too old")]
     RemoteProtocolVersionTooOld,
 
@@ -17,7 +23,13 @@ pub enum NegotiationError {
     Other(#[from] anyhow::Error),
 }
 
-pub async fn negotiate(sender: &mut Sender, receiver: &mut Receiver) -> Result<(), NegotiationError> {
+/// Checks that both sides speak the same protocol, then swaps signing keys. Returns the remote side's key, which is empty
+/// if it doesn't sign its rounds.
+pub async fn negotiate(
+    sender: &mut Sender,
+    receiver: &mut Receiver,
+    signing_public_key: &[u8],
+) -> Result<Vec<u8>, NegotiationError> {
     sender
         .send_hello()
         .await
@@ -38,20 +50,65 @@ pub async fn negotiate(sender: &mut Sender, receiver: &mut Receiver) -> Result<(
         return Err(NegotiationError::RemoteProtocolVersionTooNew);
     }
 
-    Ok(())
+    sender
+        .send_identity(signing_public_key)
+        .await
+        .map_err(|e| NegotiationError::Other(e.into()))?;
+
+    let identity = match receiver
+        .receive()
+        .await
+        .map_err(|_| NegotiationError::ExpectedIdentity)?
+    {
+        protocol::Packet::Identity(identity) => identity,
+        _ => {
+            return Err(NegotiationError::ExpectedIdentity);
+        }
+    };
+
+    Ok(identity.signing_public_key)
+}
+
+/// The largest frame we will accept over a stream transport.
+///
+/// Packets are already bounded by the bincode limit, so this is just a sanity check.
+const MAX_FRAME_SIZE: usize = 128 * 1024;
+
+enum SenderTransport {
+    DataChannel(datachannel_wrapper::DataChannelSender),
+    Tcp(tokio::net::tcp::OwnedWriteHalf),
 }
 
 pub struct Sender {
-    dc_tx: datachannel_wrapper::DataChannelSender,
+    transport: SenderTransport,
 }
 
 impl Sender {
     pub fn new(dc_tx: datachannel_wrapper::DataChannelSender) -> Self {
-        Self { dc_tx }
+        Self {
+            transport: SenderTransport::DataChannel(dc_tx),
+        }
+    }
+
+    pub fn new_tcp(tcp_tx: tokio::net::tcp::OwnedWriteHalf) -> Self {
+        Self {
+            transport: SenderTransport::Tcp(tcp_tx),
+        }
     }
 
     async fn send_packet(&mut self, p: &protocol::Packet) -> std::io::Result<()> {
-        self.dc_tx.send(p.serialize().unwrap().as_slice()).await?;
+        let buf = p.serialize().unwrap();
+        match &mut self.transport {
+            SenderTransport::DataChannel(dc_tx) => {
+                dc_tx.send(buf.as_slice()).await?;
+            }
+            SenderTransport::Tcp(tcp_tx) => {
+                let mut frame = Vec::with_capacity(4 + buf.len());
+                frame.extend_from_slice(&(buf.len() as u32).to_le_bytes());
+                frame.extend_from_slice(&buf);
+                tcp_tx.write_all(&frame).await?;
+            }
+        }
         Ok(())
     }
 
@@ -62,6 +119,13 @@ impl Sender {
         .await
     }
 
+    pub async fn send_identity(&mut self, signing_public_key: &[u8]) -> std::io::Result<()> {
+        self.send_packet(&protocol::Packet::Identity(protocol::Identity {
+            signing_public_key: signing_public_key.to_vec(),
+        }))
+        .await
+    }
+
     pub async fn send_ping(&mut self, ts: std::time::SystemTime) -> std::io::Result<()> {
         self.send_packet(&protocol::Packet::Ping(protocol::Ping { ts })).await
     }
@@ -95,85 +159,292 @@ impl Sender {
     }
 }
 
+enum ReceiverTransport {
+    DataChannel(datachannel_wrapper::DataChannelReceiver),
+    // Receives are raced against timers, so frames have to be read in a way that survives being cancelled partway.
+    Tcp(tokio_util::codec::FramedRead<tokio::net::tcp::OwnedReadHalf, tokio_util::codec::LengthDelimitedCodec>),
+}
+
 pub struct Receiver {
-    dc_rx: datachannel_wrapper::DataChannelReceiver,
+    transport: ReceiverTransport,
 }
 
 impl Receiver {
     pub fn new(dc_rx: datachannel_wrapper::DataChannelReceiver) -> Self {
-        Self { dc_rx }
+        Self {
+            transport: ReceiverTransport::DataChannel(dc_rx),
+        }
+    }
+
+    pub fn new_tcp(tcp_rx: tokio::net::tcp::OwnedReadHalf) -> Self {
+        Self {
+            transport: ReceiverTransport::Tcp(tokio_util::codec::FramedRead::new(
+                tcp_rx,
+                tokio_util::codec::LengthDelimitedCodec::builder()
+                    .little_endian()
+                    .length_field_length(4)
+                    .max_frame_length(MAX_FRAME_SIZE)
+                    .new_codec(),
+            )),
+        }
+    }
+
+    async fn receive_raw(&mut self) -> std::io::Result<Vec<u8>> {
+        match &mut self.transport {
+            ReceiverTransport::DataChannel(dc_rx) => match dc_rx.receive().await {
+                Some(d) => Ok(d),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "stream is empty",
+                )),
+            },
+            ReceiverTransport::Tcp(tcp_rx) => match tcp_rx.next().await {
+                Some(frame) => Ok(frame?.to_vec()),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "stream is empty",
+                )),
+            },
+        }
     }
 
     pub async fn receive(&mut self) -> std::io::Result<protocol::Packet> {
-        match protocol::Packet::deserialize(
-            match self.dc_rx.receive().await {
-                Some(d) => d,
-                None => {
+        match protocol::Packet::deserialize(self.receive_raw().await?.as_slice()) {
+            Ok(p) => Ok(p),
+            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/// How many unacknowledged inputs each bundle carries.
+const INPUT_BUNDLE_SIZE: usize = 16;
+
+/// How often to resend unacknowledged inputs and acknowledge received ones when nothing else is being sent.
+const INPUT_RESEND_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// How many unacknowledged inputs we hold on to before giving up on the remote.
+const MAX_UNACKED_INPUTS: usize = 1024;
+
+/// How far past the next expected input we buffer inputs that arrived out of order.
+const MAX_REORDER_DISTANCE: u32 = 1024;
+
+struct InputChannelState {
+    next_seq: u32,
+    unacked: std::collections::VecDeque<tango_pvp::net::Input>,
+    next_expected_seq: u32,
+    out_of_order: std::collections::BTreeMap<u32, tango_pvp::net::Input>,
+    ready: std::collections::VecDeque<tango_pvp::net::Input>,
+    ack_pending: bool,
+}
+
+impl InputChannelState {
+    fn first_unacked_seq(&self) -> u32 {
+        self.next_seq - self.unacked.len() as u32
+    }
+
+    fn make_bundle(&mut self, newest: bool) -> protocol::InputBundle {
+        let n = self.unacked.len().min(INPUT_BUNDLE_SIZE);
+        let skip = if newest { self.unacked.len() - n } else { 0 };
+        self.ack_pending = false;
+        protocol::InputBundle {
+            ack: self.next_expected_seq,
+            first_seq: self.first_unacked_seq() + skip as u32,
+            inputs: self.unacked.iter().skip(skip).take(n).cloned().collect(),
+        }
+    }
+
+    fn handle_bundle(&mut self, bundle: protocol::InputBundle) {
+        while !self.unacked.is_empty() && self.first_unacked_seq() < bundle.ack {
+            self.unacked.pop_front();
+        }
+
+        for (seq, input) in (bundle.first_seq..).zip(bundle.inputs) {
+            if seq < self.next_expected_seq || seq - self.next_expected_seq >= MAX_REORDER_DISTANCE {
+                continue;
+            }
+            self.out_of_order.insert(seq, input);
+        }
+
+        while let Some(input) = self.out_of_order.remove(&self.next_expected_seq) {
+            self.ready.push_back(input);
+            self.next_expected_seq += 1;
+            self.ack_pending = true;
+        }
+    }
+}
+
+/// Carries inputs over an unordered, unreliable data channel.
+///
+/// Each bundle repeats the most recent inputs the remote hasn't acknowledged, so a single lost packet doesn't stall
+/// the inputs behind it the way it would on the reliable channel.
+pub struct InputChannel {
+    tx: tokio::sync::Mutex<datachannel_wrapper::DataChannelSender>,
+    rx: tokio::sync::Mutex<datachannel_wrapper::DataChannelReceiver>,
+    state: parking_lot::Mutex<InputChannelState>,
+}
+
+impl InputChannel {
+    pub fn new(dc: datachannel_wrapper::DataChannel) -> Self {
+        let (tx, rx) = dc.split();
+        Self {
+            tx: tokio::sync::Mutex::new(tx),
+            rx: tokio::sync::Mutex::new(rx),
+            state: parking_lot::Mutex::new(InputChannelState {
+                next_seq: 0,
+                unacked: std::collections::VecDeque::new(),
+                next_expected_seq: 0,
+                out_of_order: std::collections::BTreeMap::new(),
+                ready: std::collections::VecDeque::new(),
+                ack_pending: false,
+            }),
+        }
+    }
+
+    async fn send_bundle(&self, bundle: protocol::InputBundle) -> std::io::Result<()> {
+        let buf = protocol::Packet::InputBundle(bundle).serialize().unwrap();
+        self.tx.lock().await.send(buf.as_slice()).await
+    }
+
+    pub async fn send(&self, input: &tango_pvp::net::Input) -> std::io::Result<()> {
+        let bundle = {
+            let mut state = self.state.lock();
+            if state.unacked.len() >= MAX_UNACKED_INPUTS {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "too many unacknowledged inputs",
+                ));
+            }
+            state.unacked.push_back(input.clone());
+            state.next_seq += 1;
+            state.make_bundle(true)
+        };
+        self.send_bundle(bundle).await
+    }
+
+    /// Resends the oldest unacknowledged inputs, so gaps get filled even if we stop sending new inputs.
+    async fn resend(&self) -> std::io::Result<()> {
+        let bundle = {
+            let mut state = self.state.lock();
+            if state.unacked.is_empty() && !state.ack_pending {
+                return Ok(());
+            }
+            state.make_bundle(false)
+        };
+        self.send_bundle(bundle).await
+    }
+
+    async fn receive(&self) -> std::io::Result<tango_pvp::net::Input> {
+        let mut rx = self.rx.lock().await;
+        loop {
+            if let Some(input) = self.state.lock().ready.pop_front() {
+                return Ok(input);
+            }
+
+            let Some(raw) = rx.receive().await else {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "stream is empty",
+                ));
+            };
+
+            match protocol::Packet::deserialize(&raw) {
+                Ok(protocol::Packet::InputBundle(bundle)) => {
+                    self.state.lock().handle_bundle(bundle);
+                }
+                Ok(p) => {
                     return Err(std::io::Error::new(
-                        std::io::ErrorKind::UnexpectedEof,
-                        "stream is empty",
+                        std::io::ErrorKind::InvalidData,
+                        format!("invalid packet: {:?}", p),
                     ));
                 }
+                Err(e) => {
+                    return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+                }
             }
-            .as_slice(),
-        ) {
-            Ok(p) => Ok(p),
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
         }
     }
 }
 
 pub struct PvpSender {
     sender: std::sync::Arc<tokio::sync::Mutex<Sender>>,
+    input_channel: Option<std::sync::Arc<InputChannel>>,
 }
 
 impl PvpSender {
-    pub fn new(sender: std::sync::Arc<tokio::sync::Mutex<Sender>>) -> Self {
-        Self { sender }
+    pub fn new(
+        sender: std::sync::Arc<tokio::sync::Mutex<Sender>>,
+        input_channel: Option<std::sync::Arc<InputChannel>>,
+    ) -> Self {
+        Self { sender, input_channel }
     }
 }
 
 #[async_trait::async_trait]
 impl tango_pvp::net::Sender for PvpSender {
     async fn send(&mut self, input: &tango_pvp::net::Input) -> std::io::Result<()> {
+        // Stream transports have nothing to gain from bundling, so inputs just go over the reliable channel.
+        if let Some(input_channel) = self.input_channel.as_ref() {
+            return input_channel.send(input).await;
+        }
+
         self.sender
             .lock()
             .await
             .send_packet(&protocol::Packet::Input(input.clone()))
             .await
     }
+
+    async fn send_round_signature(&mut self, signature: &tango_pvp::net::RoundSignature) -> std::io::Result<()> {
+        self.sender
+            .lock()
+            .await
+            .send_packet(&protocol::Packet::RoundSignature(signature.clone()))
+            .await
+    }
 }
 
 pub struct PvpReceiver {
     receiver: Receiver,
     sender: std::sync::Arc<tokio::sync::Mutex<Sender>>,
+    input_channel: Option<std::sync::Arc<InputChannel>>,
     latency_counter: std::sync::Arc<tokio::sync::Mutex<crate::stats::LatencyCounter>>,
     ping_timer: tokio::time::Interval,
+    resend_timer: tokio::time::Interval,
 }
 
 impl PvpReceiver {
     pub fn new(
         receiver: Receiver,
         sender: std::sync::Arc<tokio::sync::Mutex<Sender>>,
+        input_channel: Option<std::sync::Arc<InputChannel>>,
         latency_counter: std::sync::Arc<tokio::sync::Mutex<crate::stats::LatencyCounter>>,
     ) -> Self {
         Self {
             receiver,
             sender,
+            input_channel,
             latency_counter,
             ping_timer: tokio::time::interval(PING_INTERVAL),
+            resend_timer: tokio::time::interval(INPUT_RESEND_INTERVAL),
         }
     }
 }
 
 #[async_trait::async_trait]
 impl tango_pvp::net::Receiver for PvpReceiver {
-    async fn receive(&mut self) -> std::io::Result<tango_pvp::net::Input> {
+    async fn receive(&mut self) -> std::io::Result<tango_pvp::net::Message> {
+        let input_channel = self.input_channel.clone();
         loop {
             tokio::select! {
                 _ = self.ping_timer.tick() => {
                     self.sender.lock().await.send_ping(std::time::SystemTime::now()).await?;
                 }
+                _ = self.resend_timer.tick(), if input_channel.is_some() => {
+                    input_channel.as_ref().unwrap().resend().await?;
+                }
+                input = async { input_channel.as_ref().unwrap().receive().await }, if input_channel.is_some() => {
+                    return input.map(tango_pvp::net::Message::Input);
+                }
                 p = self.receiver.receive() => {
                     match p? {
                         protocol::Packet::Ping(ping) => {
@@ -185,7 +456,10 @@ impl tango_pvp::net::Receiver for PvpReceiver {
                             }
                         }
                         protocol::Packet::Input(input) => {
-                            return Ok(input);
+                            return Ok(tango_pvp::net::Message::Input(input));
+                        }
+                        protocol::Packet::RoundSignature(signature) => {
+                            return Ok(tango_pvp::net::Message::RoundSignature(signature));
                         }
                         p => {
                             return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, format!("invalid packet: {:?}", p)))
@@ -1,6 +1,6 @@
 use bincode::Options;
 
-pub const VERSION: u8 = 0x3a;
+pub const VERSION: u8 = tango_signaling::PROTOCOL_VERSION;
 
 lazy_static! {
     static ref BINCODE_OPTIONS: bincode::config::WithOtherLimit<
@@ -17,6 +17,7 @@ lazy_static! {
 pub enum Packet {
     // Handshake.
     Hello(Hello),
+    Identity(Identity),
 
     // Ping.
     Ping(Ping),
@@ -31,6 +32,8 @@ pub enum Packet {
 
     // In match.
     Input(tango_pvp::net::Input),
+    InputBundle(InputBundle),
+    RoundSignature(tango_pvp::net::RoundSignature),
 }
 
 impl Packet {
@@ -48,6 +51,13 @@ pub struct Hello {
     pub protocol_version: u8,
 }
 
+/// Sent once both sides agree on the protocol version.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct Identity {
+    /// The key this side will sign its rounds with, or empty if it doesn't sign them.
+    pub signing_public_key: Vec<u8>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct Commit {
     pub commitment: [u8; 16],
@@ -104,6 +114,19 @@ pub struct Input {
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct StartMatch {}
 
+/// Inputs sent over the unreliable channel.
+///
+/// Every input is numbered, and a bundle carries a run of consecutive inputs the remote hasn't acknowledged yet, so a
+/// lost bundle is covered by the next one.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct InputBundle {
+    /// The sequence number of the next input we expect, acknowledging every input before it.
+    pub ack: u32,
+    /// The sequence number of the first input in this bundle.
+    pub first_seq: u32,
+    pub inputs: Vec<tango_pvp::net::Input>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct NegotiatedState {
     pub nonce: [u8; 16],
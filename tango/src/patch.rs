@@ -397,21 +397,33 @@ pub fn apply_patch_from_disk(
     patch_name: &str,
     patch_version: &semver::Version,
 ) -> Result<Vec<u8>, anyhow::Error> {
-    let patch_name = std::path::Path::new(patch_name);
-    if patch_name.components().count() > 1 {
-        anyhow::bail!("attempted path traversal in patch name");
-    }
+    tango_pvp::patch::apply_from_disk(
+        rom,
+        game.gamedb_entry(),
+        patches_path,
+        patch_name,
+        &patch_version.to_string(),
+    )
+}
 
-    let (rom_code, revision) = game.gamedb_entry().rom_code_and_revision;
-    let raw = std::fs::read(
-        patches_path
-            .join(patch_name)
-            .join(format!("v{}", patch_version))
-            .join(format!(
-                "{}_{:02}.bps",
-                std::str::from_utf8(rom_code).unwrap(),
-                revision
-            )),
-    )?;
-    Ok(bps::Patch::decode(&raw)?.apply(rom)?)
+/// The ROM a side of a replay was played with, patched if it was played with a patch.
+pub fn rom_for_game_info(
+    game_info: &tango_pvp::replay::metadata::GameInfo,
+    roms: &std::collections::HashMap<&'static (dyn game::Game + Send + Sync), Vec<u8>>,
+    patches_path: &std::path::Path,
+) -> Option<(&'static (dyn game::Game + Send + Sync), Vec<u8>)> {
+    let game = game::find_by_family_and_variant(&game_info.rom_family, game_info.rom_variant as u8)?;
+    let rom = roms.get(&game)?;
+
+    let Some(patch_info) = game_info.patch.as_ref() else {
+        return Some((game, rom.clone()));
+    };
+    let version = semver::Version::parse(&patch_info.version).ok()?;
+    match apply_patch_from_disk(rom, game, patches_path, &patch_info.name, &version) {
+        Ok(rom) => Some((game, rom)),
+        Err(e) => {
+            log::error!("failed to apply patch {}: {:?}", patch_info.name, e);
+            None
+        }
+    }
 }
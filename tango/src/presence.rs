@@ -0,0 +1,257 @@
+use crate::config;
+
+pub use tango_signaling::presence::{Challenge, Status};
+
+const RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(15);
+
+#[derive(Clone)]
+pub struct FriendStatus {
+    pub nickname: String,
+    pub status: Status,
+}
+
+struct Inner {
+    identity: Option<String>,
+    friend_statuses: std::collections::HashMap<String, FriendStatus>,
+    incoming_challenges: std::collections::VecDeque<Challenge>,
+    status: Status,
+    outgoing_challenges: Vec<(String, String)>,
+}
+
+pub struct Client {
+    config: std::sync::Arc<parking_lot::RwLock<config::Config>>,
+    inner: std::sync::Arc<parking_lot::Mutex<Inner>>,
+    notify: std::sync::Arc<tokio::sync::Notify>,
+    cancellation_token: Option<tokio_util::sync::CancellationToken>,
+}
+
+pub fn endpoint(config: &config::Config) -> String {
+    let matchmaking_endpoint = if !config.matchmaking_endpoint.is_empty() {
+        config.matchmaking_endpoint.as_str()
+    } else {
+        config::DEFAULT_MATCHMAKING_ENDPOINT
+    };
+    format!("{}/presence", matchmaking_endpoint.trim_end_matches('/'))
+}
+
+#[derive(Default)]
+struct Sent {
+    status: Option<(Status, String)>,
+    friends: Option<Vec<String>>,
+}
+
+async fn sync(
+    config: &parking_lot::RwLock<config::Config>,
+    inner: &parking_lot::Mutex<Inner>,
+    sender: &mut tango_signaling::presence::Sender,
+    sent: &mut Sent,
+) -> Result<(), tango_signaling::presence::Error> {
+    let (nickname, friends) = {
+        let config = config.read();
+        (
+            config.nickname.clone().unwrap_or_default(),
+            config.friends.iter().map(|f| f.identity.clone()).collect::<Vec<_>>(),
+        )
+    };
+
+    let (status, outgoing_challenges) = {
+        let mut inner = inner.lock();
+        (inner.status, std::mem::take(&mut inner.outgoing_challenges))
+    };
+
+    if sent.friends.as_ref() != Some(&friends) {
+        sender.subscribe(friends.clone()).await?;
+        inner
+            .lock()
+            .friend_statuses
+            .retain(|identity, _| friends.contains(identity));
+        sent.friends = Some(friends);
+    }
+
+    let status = (status, nickname);
+    if sent.status.as_ref() != Some(&status) {
+        sender.set_status(status.0, &status.1).await?;
+        sent.status = Some(status);
+    }
+
+    for (identity, link_code) in outgoing_challenges {
+        sender.send_challenge(&identity, &link_code).await?;
+    }
+
+    Ok(())
+}
+
+async fn run(
+    config: &parking_lot::RwLock<config::Config>,
+    inner: &parking_lot::Mutex<Inner>,
+    notify: &tokio::sync::Notify,
+    cancellation_token: &tokio_util::sync::CancellationToken,
+    endpoint: &str,
+    secret: &str,
+    nickname: &str,
+) -> Result<(), tango_signaling::presence::Error> {
+    let (identity, mut sender, mut receiver) =
+        tango_signaling::presence::connect(endpoint, secret.as_bytes(), nickname).await?;
+    log::info!("connected to presence server as {}", identity);
+    inner.lock().identity = Some(identity);
+
+    let mut sent = Sent::default();
+    sync(config, inner, &mut sender, &mut sent).await?;
+
+    loop {
+        tokio::select! {
+            event = receiver.receive() => {
+                let Some(event) = event? else {
+                    return Ok(());
+                };
+                let mut inner = inner.lock();
+                match event {
+                    tango_signaling::presence::Event::Update(update) => {
+                        inner.friend_statuses.insert(
+                            update.identity.clone(),
+                            FriendStatus {
+                                status: Status::from_i32(update.status).unwrap_or(Status::Offline),
+                                nickname: update.nickname,
+                            },
+                        );
+                    }
+                    tango_signaling::presence::Event::Challenge(challenge) => {
+                        inner.incoming_challenges.retain(|c| c.identity != challenge.identity);
+                        inner.incoming_challenges.push_back(challenge);
+                    }
+                }
+            }
+            _ = notify.notified() => {
+                sync(config, inner, &mut sender, &mut sent).await?;
+            }
+            _ = cancellation_token.cancelled() => {
+                let _ = sender.close().await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Client {
+    pub fn new(config: std::sync::Arc<parking_lot::RwLock<config::Config>>) -> Self {
+        Self {
+            config,
+            inner: std::sync::Arc::new(parking_lot::Mutex::new(Inner {
+                identity: None,
+                friend_statuses: std::collections::HashMap::new(),
+                incoming_challenges: std::collections::VecDeque::new(),
+                status: Status::Online,
+                outgoing_challenges: vec![],
+            })),
+            notify: std::sync::Arc::new(tokio::sync::Notify::new()),
+            cancellation_token: None,
+        }
+    }
+
+    fn start(&mut self) {
+        if self.cancellation_token.is_some() {
+            return;
+        }
+
+        log::info!("starting presence client");
+        let cancellation_token = tokio_util::sync::CancellationToken::new();
+        tokio::task::spawn({
+            let cancellation_token = cancellation_token.clone();
+            let config = self.config.clone();
+            let inner = self.inner.clone();
+            let notify = self.notify.clone();
+            async move {
+                'l: loop {
+                    let (presence_endpoint, secret, nickname) = {
+                        let config = config.read();
+                        (
+                            endpoint(&config),
+                            config.presence_secret.clone(),
+                            config.nickname.clone().unwrap_or_default(),
+                        )
+                    };
+
+                    if let Err(e) = run(
+                        &config,
+                        &inner,
+                        &notify,
+                        &cancellation_token,
+                        &presence_endpoint,
+                        &secret,
+                        &nickname,
+                    )
+                    .await
+                    {
+                        log::warn!("presence client encountered error: {:?}", e);
+                    }
+
+                    {
+                        let mut inner = inner.lock();
+                        inner.identity = None;
+                        inner.friend_statuses.clear();
+                    }
+
+                    tokio::select! {
+                        _ = tokio::time::sleep(RECONNECT_DELAY) => { }
+                        _ = cancellation_token.cancelled() => { break 'l; }
+                    }
+                }
+                log::info!("stopped presence client");
+            }
+        });
+        self.cancellation_token = Some(cancellation_token);
+    }
+
+    fn stop(&mut self) {
+        if let Some(cancellation_token) = self.cancellation_token.take() {
+            cancellation_token.cancel();
+        }
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if enabled {
+            self.start();
+        } else {
+            self.stop();
+        }
+    }
+
+    /// Our own identity, as assigned by the server. This is what friends need to add us.
+    pub fn identity(&self) -> Option<String> {
+        self.inner.lock().identity.clone()
+    }
+
+    pub fn friend_status(&self, identity: &str) -> Option<FriendStatus> {
+        self.inner.lock().friend_statuses.get(identity).cloned()
+    }
+
+    pub fn set_status(&self, status: Status) {
+        let mut inner = self.inner.lock();
+        if inner.status == status {
+            return;
+        }
+        inner.status = status;
+        self.notify.notify_one();
+    }
+
+    /// Pushes changes to the friends list or nickname in the config to the server.
+    pub fn resync(&self) {
+        self.notify.notify_one();
+    }
+
+    pub fn send_challenge(&self, identity: &str, link_code: &str) {
+        self.inner
+            .lock()
+            .outgoing_challenges
+            .push((identity.to_string(), link_code.to_string()));
+        self.notify.notify_one();
+    }
+
+    pub fn incoming_challenges(&self) -> Vec<Challenge> {
+        self.inner.lock().incoming_challenges.iter().cloned().collect()
+    }
+
+    pub fn dismiss_challenge(&self, identity: &str) {
+        self.inner.lock().incoming_challenges.retain(|c| c.identity != identity);
+    }
+}
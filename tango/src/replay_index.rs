@@ -0,0 +1,228 @@
+const VERSION: u32 = 1;
+
+mod metadata_bytes {
+    use prost::Message;
+
+    pub fn serialize<S>(metadata: &tango_pvp::replay::Metadata, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_bytes(&metadata.encode_to_vec())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<tango_pvp::replay::Metadata, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let buf = <Vec<u8> as serde::Deserialize>::deserialize(deserializer)?;
+        tango_pvp::replay::Metadata::decode(&buf[..]).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct Entry {
+    mtime: std::time::SystemTime,
+    size: u64,
+    pub is_complete: bool,
+    #[serde(with = "metadata_bytes")]
+    pub metadata: tango_pvp::replay::Metadata,
+    pub outcome: Option<tango_pvp::stepper::BattleOutcome>,
+}
+
+/// What the replays pane knows about each replay on disk, keyed by path and invalidated by mtime and size.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Index {
+    version: u32,
+    entries: std::collections::HashMap<std::path::PathBuf, Entry>,
+}
+
+impl Default for Index {
+    fn default() -> Self {
+        Self {
+            version: VERSION,
+            entries: std::collections::HashMap::new(),
+        }
+    }
+}
+
+impl Index {
+    pub fn load(path: &std::path::Path) -> Self {
+        let index = match std::fs::read(path) {
+            Ok(buf) => match bincode::deserialize::<Self>(&buf) {
+                Ok(index) => index,
+                Err(e) => {
+                    log::warn!("failed to read replay index, rebuilding: {}", e);
+                    return Self::default();
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Self::default();
+            }
+            Err(e) => {
+                log::warn!("failed to read replay index, rebuilding: {}", e);
+                return Self::default();
+            }
+        };
+
+        if index.version != VERSION {
+            return Self::default();
+        }
+        index
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> Result<(), anyhow::Error> {
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bincode::serialize(self)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Brings the index in line with the replays directory, only reading replays that are new or have changed. Returns
+    /// whether anything changed.
+    pub fn update(&mut self, replays_path: &std::path::Path) -> bool {
+        let mut changed = false;
+        let mut seen = std::collections::HashSet::new();
+
+        for entry in walkdir::WalkDir::new(replays_path) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => {
+                    continue;
+                }
+            };
+
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let path = entry.path();
+            let Ok(fs_metadata) = entry.metadata() else {
+                continue;
+            };
+            let Ok(mtime) = fs_metadata.modified() else {
+                continue;
+            };
+            let size = fs_metadata.len();
+
+            if self
+                .entries
+                .get(path)
+                .is_some_and(|entry| entry.mtime == mtime && entry.size == size)
+            {
+                seen.insert(path.to_path_buf());
+                continue;
+            }
+
+            let mut f = match std::fs::File::open(path) {
+                Ok(f) => f,
+                Err(_) => {
+                    continue;
+                }
+            };
+
+            let (num_inputs, metadata) = match tango_pvp::replay::read_metadata(&mut f) {
+                Ok((n, metadata)) => (n, metadata),
+                Err(_) => {
+                    continue;
+                }
+            };
+
+            self.entries.insert(
+                path.to_path_buf(),
+                Entry {
+                    mtime,
+                    size,
+                    is_complete: num_inputs > 0,
+                    metadata,
+                    outcome: None,
+                },
+            );
+            seen.insert(path.to_path_buf());
+            changed = true;
+        }
+
+        let num_entries = self.entries.len();
+        self.entries.retain(|path, _| seen.contains(path));
+        changed || self.entries.len() != num_entries
+    }
+
+    pub fn set_outcome(&mut self, path: &std::path::Path, outcome: tango_pvp::stepper::BattleOutcome) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.outcome = Some(outcome);
+        }
+    }
+
+    /// All entries, newest first.
+    pub fn sorted_entries(&self) -> Vec<(std::path::PathBuf, Entry)> {
+        let mut entries = self
+            .entries
+            .iter()
+            .map(|(path, entry)| (path.clone(), entry.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, entry)| {
+            (
+                std::cmp::Reverse(entry.metadata.ts),
+                entry.metadata.link_code.clone(),
+                entry.metadata.round,
+            )
+        });
+        entries
+    }
+}
+
+/// The replay index shared between panes. It is loaded on first use and also tracks which replays are having their
+/// outcomes worked out, so they aren't evaluated twice.
+#[derive(Clone)]
+pub struct Handle {
+    index: std::sync::Arc<parking_lot::Mutex<Option<Index>>>,
+    path: Option<std::path::PathBuf>,
+    evaluating: std::sync::Arc<parking_lot::Mutex<std::collections::HashSet<std::path::PathBuf>>>,
+}
+
+impl Handle {
+    pub fn new() -> Self {
+        Self {
+            index: std::sync::Arc::new(parking_lot::Mutex::new(None)),
+            path: crate::config::get_replay_index_path().ok(),
+            evaluating: std::sync::Arc::new(parking_lot::Mutex::new(std::collections::HashSet::new())),
+        }
+    }
+
+    fn save(&self, index: &Index) {
+        let Some(path) = self.path.as_ref() else {
+            return;
+        };
+        if let Err(e) = index.save(path) {
+            log::error!("failed to save replay index: {:?}", e);
+        }
+    }
+
+    /// Updates the index from the replays directory and returns its entries, newest first.
+    pub fn update(&self, replays_path: &std::path::Path) -> Vec<(std::path::PathBuf, Entry)> {
+        let mut index = self.index.lock();
+        let index = index.get_or_insert_with(|| self.path.as_ref().map(|path| Index::load(path)).unwrap_or_default());
+        if index.update(replays_path) {
+            self.save(index);
+        }
+        index.sorted_entries()
+    }
+
+    pub fn set_outcomes(&self, outcomes: &[(std::path::PathBuf, tango_pvp::stepper::BattleOutcome)]) {
+        if let Some(index) = self.index.lock().as_mut() {
+            for (path, outcome) in outcomes {
+                index.set_outcome(path, *outcome);
+            }
+            self.save(index);
+        }
+    }
+
+    /// Marks a replay as being evaluated. Returns false if it already was.
+    pub fn start_evaluating(&self, path: &std::path::Path) -> bool {
+        self.evaluating.lock().insert(path.to_path_buf())
+    }
+
+    pub fn finish_evaluating(&self, path: &std::path::Path) {
+        self.evaluating.lock().remove(path);
+    }
+}
@@ -0,0 +1,127 @@
+use prost::Message;
+use sha2::Digest;
+
+use crate::{game, patch, replay_index, rom};
+
+/// Thumbnails are stored at this fraction of the screen size.
+const SCALE: u32 = 2;
+
+/// Cached frames from the start and end of each replay, rendered in the background and stored as PNGs.
+#[derive(Clone)]
+pub struct Handle {
+    path: Option<std::path::PathBuf>,
+    failed: std::sync::Arc<parking_lot::Mutex<std::collections::HashSet<std::path::PathBuf>>>,
+    rendering: std::sync::Arc<parking_lot::Mutex<()>>,
+}
+
+impl Handle {
+    pub fn new() -> Self {
+        Self {
+            path: crate::config::get_replay_thumbnails_path().ok(),
+            failed: std::sync::Arc::new(parking_lot::Mutex::new(std::collections::HashSet::new())),
+            rendering: std::sync::Arc::new(parking_lot::Mutex::new(())),
+        }
+    }
+
+    /// Where the start and end frames for a replay go. The key covers the metadata too, so a replay that has been
+    /// rewritten in place gets rendered again.
+    fn paths(&self, path: &std::path::Path, metadata: &tango_pvp::replay::Metadata) -> Option<[std::path::PathBuf; 2]> {
+        let root = self.path.as_ref()?;
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(path.to_string_lossy().as_bytes());
+        hasher.update(metadata.encode_to_vec());
+        let key = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        Some([
+            root.join(format!("{}-start.png", key)),
+            root.join(format!("{}-end.png", key)),
+        ])
+    }
+
+    /// Loads the thumbnails for a replay, if they have been rendered.
+    pub fn load(
+        &self,
+        path: &std::path::Path,
+        metadata: &tango_pvp::replay::Metadata,
+    ) -> Option<[image::RgbaImage; 2]> {
+        let [start_path, end_path] = self.paths(path, metadata)?;
+        let load = |path: &std::path::Path| image::open(path).ok().map(|img| img.into_rgba8());
+        Some([load(&start_path)?, load(&end_path)?])
+    }
+
+    /// Renders thumbnails for every complete replay that doesn't have them yet, calling the callback after each one.
+    /// Replays that fail to render are not tried again until the next launch. Does nothing if another render is already
+    /// running.
+    pub fn render_missing(
+        &self,
+        entries: &[(std::path::PathBuf, replay_index::Entry)],
+        roms_scanner: &rom::Scanner,
+        patches_path: &std::path::Path,
+        on_rendered: impl Fn(&std::path::Path, [image::RgbaImage; 2]),
+    ) {
+        let Some(_rendering) = self.rendering.try_lock() else {
+            return;
+        };
+
+        for (path, entry) in entries {
+            if !entry.is_complete || self.failed.lock().contains(path) {
+                continue;
+            }
+            let Some(paths) = self.paths(path, &entry.metadata) else {
+                return;
+            };
+            if paths.iter().all(|path| path.exists()) {
+                continue;
+            }
+
+            match self.render(path, &entry.metadata, &roms_scanner.read(), patches_path, &paths) {
+                Ok(Some(thumbnails)) => {
+                    on_rendered(path, thumbnails);
+                }
+                Ok(None) => {
+                    // The ROM or patch isn't available yet: try again on the next scan.
+                }
+                Err(e) => {
+                    log::error!("failed to render thumbnails for {}: {:?}", path.display(), e);
+                    self.failed.lock().insert(path.clone());
+                }
+            }
+        }
+    }
+
+    fn render(
+        &self,
+        path: &std::path::Path,
+        metadata: &tango_pvp::replay::Metadata,
+        roms: &std::collections::HashMap<&'static (dyn game::Game + Send + Sync), Vec<u8>>,
+        patches_path: &std::path::Path,
+        paths: &[std::path::PathBuf; 2],
+    ) -> Result<Option<[image::RgbaImage; 2]>, anyhow::Error> {
+        let Some(game_info) = metadata.local_side.as_ref().and_then(|side| side.game_info.as_ref()) else {
+            return Ok(None);
+        };
+        let Some((game, rom)) = patch::rom_for_game_info(game_info, roms, patches_path) else {
+            return Ok(None);
+        };
+
+        let replay = tango_pvp::replay::Replay::decode(&mut std::fs::File::open(path)?)?;
+        let hooks = tango_pvp::hooks::hooks_for_gamedb_entry(game.gamedb_entry()).unwrap();
+        let thumbnails = tango_pvp::eval::thumbnails(&replay, &rom, hooks)?.map(|frame| {
+            image::imageops::resize(
+                &frame,
+                frame.width() / SCALE,
+                frame.height() / SCALE,
+                image::imageops::FilterType::Triangle,
+            )
+        });
+
+        std::fs::create_dir_all(paths[0].parent().unwrap())?;
+        for (thumbnail, path) in thumbnails.iter().zip(paths) {
+            thumbnail.save(path)?;
+        }
+        Ok(Some(thumbnails))
+    }
+}
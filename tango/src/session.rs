@@ -34,21 +34,143 @@ pub struct PvP {
     pub match_: std::sync::Arc<tokio::sync::Mutex<Option<std::sync::Arc<tango_pvp::battle::Match>>>>,
     cancellation_token: tokio_util::sync::CancellationToken,
     latency_counter: std::sync::Arc<tokio::sync::Mutex<crate::stats::LatencyCounter>>,
-    _peer_conn: datachannel_wrapper::PeerConnection,
+    peer_conn: Option<datachannel_wrapper::PeerConnection>,
 }
 
 impl PvP {
     pub async fn latency(&self) -> std::time::Duration {
         self.latency_counter.lock().await.median()
     }
+
+    /// Statistics for the underlying WebRTC connection, or `None` if we're connected directly over LAN.
+    pub fn connection_stats(&self) -> Option<datachannel_wrapper::Stats> {
+        self.peer_conn.as_ref().map(|peer_conn| peer_conn.stats())
+    }
 }
 
 pub struct SinglePlayer {}
 
+pub struct Replayer {
+    stepper_state: tango_pvp::stepper::State,
+    rom_mismatch: bool,
+    remote_vbuf: Option<Arc<Mutex<Vec<u8>>>>,
+}
+
+/// The other side of a replay, run on its own core in step with the one being played back.
+struct RemotePerspective {
+    core: mgba::core::Core,
+    stepper_state: tango_pvp::stepper::State,
+    vbuf: Arc<Mutex<Vec<u8>>>,
+    done: bool,
+}
+
+impl RemotePerspective {
+    fn new(
+        game: &'static (dyn game::Game + Send + Sync),
+        rom: &[u8],
+        replay: &tango_pvp::replay::Replay,
+    ) -> Result<Self, anyhow::Error> {
+        let mut core = mgba::core::Core::new_gba("tango")?;
+        core.enable_video_buffer();
+        core.as_mut().load_rom(mgba::vfile::VFile::from_vec(rom.to_vec()))?;
+        core.as_mut().reset();
+
+        let hooks = tango_pvp::hooks::hooks_for_gamedb_entry(game.gamedb_entry()).unwrap();
+        hooks.patch(core.as_mut());
+
+        let stepper_state = tango_pvp::stepper::State::new(
+            (replay.metadata.match_type as u8, replay.metadata.match_subtype as u8),
+            replay.local_player_index,
+            replay.input_pairs.clone(),
+            0,
+            Box::new(|| {}),
+        );
+        let mut traps = hooks.common_traps();
+        traps.extend(hooks.stepper_traps(stepper_state.clone()));
+        traps.extend(hooks.stepper_replay_traps());
+        core.set_traps(traps);
+        core.as_mut().load_state(&replay.local_state)?;
+
+        let vbuf = vec![0u8; (mgba::gba::SCREEN_WIDTH * mgba::gba::SCREEN_HEIGHT * 4) as usize];
+        Ok(Self {
+            core,
+            stepper_state,
+            vbuf: Arc::new(Mutex::new(vbuf)),
+            done: false,
+        })
+    }
+
+    fn run_frame(&mut self) {
+        self.core.as_mut().run_frame();
+
+        // Only the side being played back is heard.
+        self.core.as_mut().audio_channel(0).clear();
+        self.core.as_mut().audio_channel(1).clear();
+
+        let mut vbuf = self.vbuf.lock();
+        vbuf.copy_from_slice(self.core.video_buffer().unwrap());
+        video::fix_vbuf_alpha(&mut vbuf);
+    }
+
+    /// Runs frames to keep up with the other side, which has just run a frame and is now at `tick`: alongside it while
+    /// they're on the same tick, and catching up once it moves on, the same way two-sided export does. This runs on the
+    /// emulator thread, so catching up is spread over several calls rather than stalling the frame it's behind on.
+    fn follow(&mut self, tick: u32) {
+        const MAX_CATCH_UP_FRAMES: usize = 4;
+
+        if self.done {
+            return;
+        }
+
+        let mut frames = 0;
+        loop {
+            {
+                let mut stepper_state = self.stepper_state.lock_inner();
+                if let Some(err) = stepper_state.take_error() {
+                    log::error!("remote perspective stopped: {:?}", err);
+                    self.done = true;
+                    return;
+                }
+                if stepper_state.input_pairs_left() == 0 || stepper_state.is_round_ended() {
+                    self.done = true;
+                    return;
+                }
+                let current_tick = stepper_state.current_tick();
+                if current_tick > tick || (current_tick == tick && frames > 0) || frames >= MAX_CATCH_UP_FRAMES {
+                    return;
+                }
+            }
+            self.run_frame();
+            frames += 1;
+        }
+    }
+}
+
+impl Replayer {
+    /// The first tick where playback stopped matching the WRAM checksums recorded in the replay.
+    pub fn divergence(&self) -> Option<u32> {
+        self.stepper_state.lock_inner().wram_divergence()
+    }
+
+    pub fn current_tick(&self) -> u32 {
+        self.stepper_state.lock_inner().current_tick()
+    }
+
+    /// The other side's screen, if it's being played back too.
+    pub fn lock_remote_vbuf(&self) -> Option<parking_lot::MutexGuard<Vec<u8>>> {
+        self.remote_vbuf.as_ref().map(|vbuf| vbuf.lock())
+    }
+
+    /// Whether the ROM being played back differs from the one the replay was recorded with.
+    pub fn rom_mismatch(&self) -> bool {
+        self.rom_mismatch
+    }
+}
+
 pub enum Mode {
     SinglePlayer(SinglePlayer),
     PvP(PvP),
-    Replayer,
+    Replayer(Replayer),
 }
 
 impl Session {
@@ -68,10 +190,12 @@ impl Session {
         remote_patch_overrides: &rom::Overrides,
         remote_rom: &[u8],
         remote_save: Box<dyn tango_dataview::save::Save + Send + Sync + 'static>,
+        remote_signing_public_key: Vec<u8>,
         emu_tps_counter: Arc<Mutex<stats::Counter>>,
         sender: net::Sender,
         receiver: net::Receiver,
-        peer_conn: datachannel_wrapper::PeerConnection,
+        input_dc: Option<datachannel_wrapper::DataChannel>,
+        peer_conn: Option<datachannel_wrapper::PeerConnection>,
         is_offerer: bool,
         replays_path: std::path::PathBuf,
         match_type: (u8, u8),
@@ -115,9 +239,13 @@ impl Session {
 
         let reveal_setup = remote_settings.reveal_setup;
 
+        let (local_rom_sha256, local_rom_crc32) = tango_pvp::replay::hash_rom(local_rom);
+        let (remote_rom_sha256, remote_rom_crc32) = tango_pvp::replay::hash_rom(remote_rom);
+
         let thread = mgba::thread::Thread::new(core);
 
         let sender = std::sync::Arc::new(tokio::sync::Mutex::new(sender));
+        let input_channel = input_dc.map(|dc| std::sync::Arc::new(net::InputChannel::new(dc)));
         let latency_counter = std::sync::Arc::new(tokio::sync::Mutex::new(crate::stats::LatencyCounter::new(5)));
 
         let cancellation_token = tokio_util::sync::CancellationToken::new();
@@ -130,12 +258,42 @@ impl Session {
             let local_settings = local_settings.clone();
             let remote_settings = remote_settings.clone();
             let replaycollector_endpoint = config.replaycollector_endpoint.clone();
+            let upload_queue_path = config.upload_queue_path();
+            let signing_key = config.signing_key();
+            // Local side first, for the replay metadata.
+            let signing_public_keys = [
+                signing_key.as_ref().map(|k| k.public_key()).unwrap_or_default(),
+                remote_signing_public_key.clone(),
+            ];
+            const TIME_DESCRIPTION: &[time::format_description::FormatItem<'_>] = time::macros::format_description!(
+                "[year padding:zero][month padding:zero repr:numerical][day padding:zero][hour padding:zero][minute padding:zero][second padding:zero]"
+            );
+            let match_file_path = if config.save_match_files {
+                Some(replays_path.join(format!(
+                    "{}.tangomatch",
+                    format!(
+                        "{}-{}-{}-vs-{}",
+                        time::OffsetDateTime::from(std::time::SystemTime::now())
+                            .format(TIME_DESCRIPTION)
+                            .expect("format time"),
+                        link_code,
+                        netplay_compatibility,
+                        remote_settings.nickname,
+                    )
+                    .chars()
+                    .filter(|c| "/\\?%*:|\"<>. ".chars().all(|c2| c2 != *c))
+                    .collect::<String>()
+                )))
+            } else {
+                None
+            };
+            let save_to_match_file = match_file_path.is_some();
             let inner_match = tango_pvp::battle::Match::new(
                 local_rom.to_vec(),
                 local_hooks,
                 tango_pvp::hooks::hooks_for_gamedb_entry(remote_game.gamedb_entry()).unwrap(),
                 cancellation_token.clone(),
-                Box::new(crate::net::PvpSender::new(sender.clone())),
+                Box::new(crate::net::PvpSender::new(sender.clone(), input_channel.clone())),
                 rand_pcg::Mcg128Xsl64::from_seed(rng_seed),
                 is_offerer,
                 thread.handle(),
@@ -144,9 +302,6 @@ impl Session {
                 match_type,
                 config.input_delay,
                 move |round_number, local_player_index| {
-                    const TIME_DESCRIPTION: &[time::format_description::FormatItem<'_>] = time::macros::format_description!(
-                        "[year padding:zero][month padding:zero repr:numerical][day padding:zero][hour padding:zero][minute padding:zero][second padding:zero]"
-                    );
                     let replay_filename = replays_path.join(format!(
                         "{}.tangoreplay",
                         format!(
@@ -164,12 +319,18 @@ impl Session {
                         .filter(|c| "/\\?%*:|\"<>. ".chars().all(|c2| c2 != *c))
                         .collect::<String>()
                     ));
-                    log::info!("open replay: {}", replay_filename.display());
-
                     let local_game_settings = local_settings.game_info.as_ref().unwrap();
                     let remote_game_settings = remote_settings.game_info.as_ref().unwrap();
 
-                    let replay_file = std::fs::OpenOptions::new().read(true).write(true).create(true).open(&replay_filename)?;
+                    // With match files on, the round only goes into the match file once it's done, so it's written to a
+                    // scratch file that goes away by itself.
+                    let replay_file = if save_to_match_file {
+                        log::info!("open replay for round {} of the match file", round_number);
+                        tempfile::tempfile()?
+                    } else {
+                        log::info!("open replay: {}", replay_filename.display());
+                        std::fs::OpenOptions::new().read(true).write(true).create(true).open(&replay_filename)?
+                    };
                     Ok(Some(tango_pvp::replay::Writer::new(
                         replay_file,
                         tango_pvp::replay::Metadata {
@@ -189,8 +350,11 @@ impl Session {
                                             version: patch.version.to_string(),
                                         }
                                     ),
+                                    rom_sha256: local_rom_sha256.clone(),
+                                    rom_crc32: local_rom_crc32,
                                 }),
                                 reveal_setup: local_settings.reveal_setup,
+                                signing_public_key: signing_public_keys[0].clone(),
                             }),
                             remote_side: Some(tango_pvp::replay::metadata::Side {
                                 nickname: remote_settings.nickname.clone(),
@@ -203,8 +367,11 @@ impl Session {
                                             version: patch.version.to_string(),
                                         }
                                     ),
+                                    rom_sha256: remote_rom_sha256.clone(),
+                                    rom_crc32: remote_rom_crc32,
                                 }),
                                 reveal_setup: remote_settings.reveal_setup,
+                                signing_public_key: signing_public_keys[1].clone(),
                             }),
                             round: round_number as u32,
                             match_type: match_type.0 as u32,
@@ -214,36 +381,31 @@ impl Session {
                         local_hooks.packet_size() as u8,
                     )?))
                 },
-                move |r| {
-                    if replaycollector_endpoint.is_empty() {
+                move |r, outcome| {
+                    if replaycollector_endpoint.is_empty() && match_file_path.is_none() {
                         return Ok(());
                     }
 
                     let mut buf = vec![];
                     r.read_to_end(&mut buf)?;
 
-                    let replaycollector_endpoint = replaycollector_endpoint.clone();
-
-                    tokio::spawn(async move {
-                        if let Err(e) = async move {
-                            let client = reqwest::Client::new();
-                            client
-                                .post(replaycollector_endpoint)
-                                .header("Content-Type", "application/x-tango-replay")
-                                .body(buf)
-                                .send()
-                                .await?
-                                .error_for_status()?;
-                            Ok::<(), anyhow::Error>(())
-                        }
-                        .await
-                        {
-                            log::error!("failed to submit replay: {:?}", e);
+                    if !replaycollector_endpoint.is_empty() {
+                        if let Err(e) = crate::upload_queue::enqueue(&upload_queue_path, &buf) {
+                            log::error!("failed to queue replay for upload: {:?}", e);
                         }
-                    });
+                    }
+
+                    if let Some(match_file_path) = match_file_path.as_ref() {
+                        tango_pvp::replay::container::append(
+                            match_file_path,
+                            tango_pvp::replay::container::Round::new(buf, Some(outcome))?,
+                        )?;
+                    }
 
                     Ok(())
                 },
+                signing_key,
+                remote_signing_public_key,
             )
             .expect("new match");
 
@@ -253,6 +415,7 @@ impl Session {
                 let receiver = Box::new(crate::net::PvpReceiver::new(
                     receiver,
                     sender.clone(),
+                    input_channel.clone(),
                     latency_counter.clone(),
                 ));
                 tokio::task::spawn(async move {
@@ -315,7 +478,7 @@ impl Session {
             mode: Mode::PvP(PvP {
                 match_,
                 cancellation_token,
-                _peer_conn: peer_conn,
+                peer_conn,
                 latency_counter,
             }),
             completion_token,
@@ -425,6 +588,7 @@ impl Session {
         rom: &[u8],
         emu_tps_counter: Arc<Mutex<stats::Counter>>,
         replay: &tango_pvp::replay::Replay,
+        remote: Option<(&'static (dyn game::Game + Send + Sync), &[u8])>,
     ) -> Result<Self, anyhow::Error> {
         let mut core = mgba::core::Core::new_gba("tango")?;
         core.enable_video_buffer();
@@ -450,11 +614,33 @@ impl Session {
                 }
             }),
         );
+        stepper_state
+            .lock_inner()
+            .set_wram_checksums(replay.wram_checksums.clone());
+        let rom_mismatch = replay
+            .metadata
+            .local_side
+            .as_ref()
+            .and_then(|side| side.game_info.as_ref())
+            .and_then(|game_info| tango_pvp::replay::rom_matches(game_info, rom))
+            == Some(false);
         let mut traps = hooks.common_traps();
         traps.extend(hooks.stepper_traps(stepper_state.clone()));
         traps.extend(hooks.stepper_replay_traps());
         core.set_traps(traps);
 
+        let remote_perspective = if let Some((remote_game, remote_rom)) = remote {
+            Some(RemotePerspective::new(
+                remote_game,
+                remote_rom,
+                &replay.clone().into_remote(),
+            )?)
+        } else {
+            None
+        };
+        let remote_vbuf = remote_perspective.as_ref().map(|remote| remote.vbuf.clone());
+        let remote_perspective = remote_perspective.map(Mutex::new);
+
         let thread = mgba::thread::Thread::new(core);
 
         thread.start()?;
@@ -490,6 +676,12 @@ impl Session {
                 video::fix_vbuf_alpha(&mut vbuf);
                 emu_tps_counter.lock().mark();
 
+                if let Some(remote_perspective) = remote_perspective.as_ref() {
+                    remote_perspective
+                        .lock()
+                        .follow(stepper_state.lock_inner().current_tick());
+                }
+
                 if !replay_is_complete && stepper_state.lock_inner().input_pairs_left() == 0 {
                     completion_token.complete();
                 }
@@ -509,7 +701,11 @@ impl Session {
             _audio_binding: audio_binding,
             thread,
             joyflags: Arc::new(std::sync::atomic::AtomicU32::new(0)),
-            mode: Mode::Replayer,
+            mode: Mode::Replayer(Replayer {
+                stepper_state,
+                rom_mismatch,
+                remote_vbuf,
+            }),
             completion_token,
             pause_on_next_frame,
             own_setup: None,
@@ -573,6 +769,23 @@ impl Session {
         }
     }
 
+    pub fn save_state(&self) -> Result<Box<mgba::state::State>, anyhow::Error> {
+        Ok(self.thread.handle().lock_audio().core_mut().save_state()?)
+    }
+
+    /// Both players' latest inputs, P1 first: the last committed ones in a match, or the ones being played back.
+    pub fn last_joyflags(&self) -> Option<[u16; 2]> {
+        match &self.mode {
+            Mode::SinglePlayer(_) => None,
+            Mode::PvP(pvp) => {
+                let match_ = pvp.match_.blocking_lock();
+                let round_state = match_.as_ref()?.lock_round_state();
+                round_state.round.as_ref().map(|round| round.last_committed_joyflags())
+            }
+            Mode::Replayer(replayer) => replayer.stepper_state.lock_inner().last_joyflags(),
+        }
+    }
+
     pub fn lock_vbuf(&self) -> parking_lot::MutexGuard<Vec<u8>> {
         self.vbuf.lock()
     }
@@ -0,0 +1,194 @@
+use crate::config;
+
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(6 * 60 * 60);
+
+/// Retry bookkeeping, kept next to each queued replay. A replay without one hasn't been tried yet.
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+struct Attempts {
+    count: u32,
+    next_attempt_at: u64,
+    last_error: Option<String>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// Puts a replay in the queue to be uploaded. The replay is on disk by the time this returns, so it will be uploaded
+/// eventually even if we exit first.
+pub fn enqueue(queue_path: &std::path::Path, raw: &[u8]) -> Result<(), anyhow::Error> {
+    std::fs::create_dir_all(queue_path)?;
+    let name = format!("{}-{:08x}", now_millis(), rand::random::<u32>());
+    let path = queue_path.join(&name).with_extension("tangoreplay");
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, raw)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+fn queued_replays(queue_path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let Ok(read_dir) = std::fs::read_dir(queue_path) else {
+        return vec![];
+    };
+    let mut paths = read_dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension() == Some(std::ffi::OsStr::new("tangoreplay")))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn read_attempts(path: &std::path::Path) -> Attempts {
+    std::fs::read(path.with_extension("json"))
+        .ok()
+        .and_then(|buf| serde_json::from_slice(&buf).ok())
+        .unwrap_or_default()
+}
+
+fn write_attempts(path: &std::path::Path, attempts: &Attempts) -> Result<(), anyhow::Error> {
+    std::fs::write(path.with_extension("json"), serde_json::to_vec(attempts)?)?;
+    Ok(())
+}
+
+async fn upload(endpoint: &str, raw: Vec<u8>) -> Result<(), anyhow::Error> {
+    reqwest::Client::new()
+        .post(endpoint)
+        .header("Content-Type", "application/x-tango-replay")
+        .body(raw)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Whether the collector turned the replay down for good, e.g. because it couldn't be decoded. Retrying those would
+/// only send the same bytes again, so they're set aside instead.
+fn is_rejected(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<reqwest::Error>()
+        .and_then(|e| e.status())
+        .is_some_and(|status| {
+            status.is_client_error()
+                && status != reqwest::StatusCode::REQUEST_TIMEOUT
+                && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+/// Moves a rejected replay and its attempts out of the queue, into the failed directory next to it.
+fn set_aside(queue_path: &std::path::Path, path: &std::path::Path) -> Result<(), anyhow::Error> {
+    let failed_path = queue_path.join("failed");
+    std::fs::create_dir_all(&failed_path)?;
+    let _ = std::fs::rename(
+        path.with_extension("json"),
+        failed_path.join(path.with_extension("json").file_name().unwrap()),
+    );
+    std::fs::rename(path, failed_path.join(path.file_name().unwrap()))?;
+    Ok(())
+}
+
+#[derive(Clone, Default)]
+pub struct Status {
+    pub pending: usize,
+    pub last_error: Option<String>,
+}
+
+/// Uploads queued replays in the background, backing off on replays that fail.
+pub struct Uploader {
+    status: std::sync::Arc<parking_lot::Mutex<Status>>,
+}
+
+impl Uploader {
+    pub fn new(config: std::sync::Arc<parking_lot::RwLock<config::Config>>) -> Self {
+        let status = std::sync::Arc::new(parking_lot::Mutex::new(Status::default()));
+        tokio::task::spawn({
+            let status = status.clone();
+            async move {
+                loop {
+                    let (queue_path, endpoint) = {
+                        let config = config.read();
+                        (config.upload_queue_path(), config.replaycollector_endpoint.clone())
+                    };
+                    process(&queue_path, &endpoint, &status).await;
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        });
+        Self { status }
+    }
+
+    pub fn status(&self) -> Status {
+        self.status.lock().clone()
+    }
+}
+
+async fn process(queue_path: &std::path::Path, endpoint: &str, status: &parking_lot::Mutex<Status>) {
+    if endpoint.is_empty() {
+        // Queued replays stay where they are, but nothing is being uploaded.
+        status.lock().pending = 0;
+        return;
+    }
+    let paths = queued_replays(queue_path);
+    status.lock().pending = paths.len();
+
+    for path in paths {
+        let mut attempts = read_attempts(&path);
+        if attempts.next_attempt_at > now_millis() {
+            continue;
+        }
+
+        let result = match tokio::fs::read(&path).await {
+            Ok(raw) => upload(endpoint, raw).await,
+            Err(e) => Err(e.into()),
+        };
+
+        match result {
+            Ok(()) => {
+                log::info!("uploaded queued replay {}", path.display());
+                let _ = std::fs::remove_file(path.with_extension("json"));
+                if let Err(e) = std::fs::remove_file(&path) {
+                    log::error!("failed to remove uploaded replay {}: {:?}", path.display(), e);
+                }
+                let mut status = status.lock();
+                status.pending = status.pending.saturating_sub(1);
+                status.last_error = None;
+            }
+            Err(e) if is_rejected(&e) => {
+                log::warn!("replay {} was rejected, not retrying: {:?}", path.display(), e);
+                attempts.count += 1;
+                attempts.last_error = Some(e.to_string());
+                if let Err(e) = write_attempts(&path, &attempts) {
+                    log::error!("failed to record upload attempt for {}: {:?}", path.display(), e);
+                }
+                if let Err(e) = set_aside(queue_path, &path) {
+                    log::error!("failed to set aside rejected replay {}: {:?}", path.display(), e);
+                }
+                let mut status = status.lock();
+                status.pending = status.pending.saturating_sub(1);
+                status.last_error = attempts.last_error;
+            }
+            Err(e) => {
+                let backoff = INITIAL_BACKOFF
+                    .saturating_mul(1 << attempts.count.min(16))
+                    .min(MAX_BACKOFF);
+                log::warn!(
+                    "failed to upload replay {}, retrying in {:?}: {:?}",
+                    path.display(),
+                    backoff,
+                    e
+                );
+                attempts.count += 1;
+                attempts.next_attempt_at = now_millis() + backoff.as_millis() as u64;
+                attempts.last_error = Some(e.to_string());
+                if let Err(e) = write_attempts(&path, &attempts) {
+                    log::error!("failed to record upload attempt for {}: {:?}", path.display(), e);
+                }
+                status.lock().last_error = attempts.last_error;
+            }
+        }
+    }
+}